@@ -0,0 +1,65 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::hint::black_box;
+use volley::physics::{RigidBody, Vector3, World};
+
+// Fixed seed so numbers stay comparable between runs
+const SEED: u64 = 42;
+const BODY_COUNTS: [usize; 3] = [10, 100, 1000];
+// Keeps density roughly constant as the count grows
+fn extent_for(count: usize) -> f32 {
+    (count as f32).cbrt() * 4.0
+}
+
+fn bench_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("world_step");
+    for count in BODY_COUNTS {
+        let world = World::with_random_bodies(count, extent_for(count), SEED);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &world, |b, world| {
+            let mut world = world.clone();
+            // Same work as one engine tick, without tick()'s per-body logging
+            b.iter(|| world.step(1.0 / 120.0))
+        });
+    }
+    group.finish();
+}
+
+fn bench_collision_pairs(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision_pairs");
+    for count in BODY_COUNTS {
+        let world = World::with_random_bodies(count, extent_for(count), SEED);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &world, |b, world| {
+            b.iter(|| black_box(world.collision_pairs()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_handle_collision(c: &mut Criterion) {
+    let body = |id: &str, x: f32, vx: f32| {
+        RigidBody::from_extents_with_id(
+            id.to_string(),
+            Vector3::new(x, 0.0, 0.0),
+            Vector3::new(vx, 0.0, 0.0),
+            Vector3::new(0.5, 0.5, 0.5),
+            1.0,
+            1.0,
+            false,
+        )
+    };
+    let (a, b) = (body("a", -0.4, 1.0), body("b", 0.4, -1.0));
+    c.bench_function("handle_collision", |bench| {
+        bench.iter_batched(
+            || (a.clone(), b.clone()),
+            |(mut a, mut b)| black_box(World::handle_collision(&mut a, &mut b)),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_step,
+    bench_collision_pairs,
+    bench_handle_collision
+);
+criterion_main!(benches);
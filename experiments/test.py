@@ -168,8 +168,8 @@ class AsyncPhysicsClient:
         self.state_socket = self.context.socket(zmq.SUB)
         self.state_socket.connect(state_port)
         self.state_socket.setsockopt_string(
-            zmq.SUBSCRIBE, ""
-        )  # Subscribe to all messages
+            zmq.SUBSCRIBE, "world/"
+        )  # Main world only, rooms publish under "room/<id>/"
 
         self.latest_state = None
         self.running = True
@@ -187,7 +187,7 @@ class AsyncPhysicsClient:
         while self.running:
             try:
                 # Receive state (non-blocking)
-                raw_state = self.state_socket.recv(zmq.DONTWAIT)
+                raw_state = self.state_socket.recv(zmq.DONTWAIT)[len(b"world/"):]
 
                 # Parse WorldState from MessagePack
                 try:
@@ -0,0 +1,111 @@
+use crate::physics::{RigidBody, Vector3};
+use crate::server::models::Action;
+use std::collections::HashSet;
+use winit::keyboard::KeyCode;
+
+pub const PADDLE_SPEED: f32 = 15.0; // World units per second, independent of the tick rate
+
+// (positive, negative) key pair per axis, None leaves that axis at zero
+pub struct PaddleKeys {
+    pub x: Option<(KeyCode, KeyCode)>,
+    pub y: Option<(KeyCode, KeyCode)>,
+    pub z: Option<(KeyCode, KeyCode)>,
+}
+
+// W/S forward and back, Space/Shift up and down, A/D sideways
+pub const PADDLE1_KEYS: PaddleKeys = PaddleKeys {
+    x: Some((KeyCode::KeyW, KeyCode::KeyS)),
+    y: Some((KeyCode::Space, KeyCode::ShiftLeft)),
+    z: Some((KeyCode::KeyD, KeyCode::KeyA)),
+};
+
+// Arrows move paddle2 across its face, it never leaves its goal line
+pub const PADDLE2_KEYS: PaddleKeys = PaddleKeys {
+    x: None,
+    y: Some((KeyCode::ArrowUp, KeyCode::ArrowDown)),
+    z: Some((KeyCode::ArrowLeft, KeyCode::ArrowRight)),
+};
+
+// The positive key wins when both are held, released keys contribute nothing
+fn axis(keys_pressed: &HashSet<KeyCode>, pair: Option<(KeyCode, KeyCode)>, speed: f32) -> f32 {
+    match pair {
+        Some((positive, _)) if keys_pressed.contains(&positive) => speed,
+        Some((_, negative)) if keys_pressed.contains(&negative) => -speed,
+        _ => 0.0,
+    }
+}
+
+pub fn paddle_velocity(keys_pressed: &HashSet<KeyCode>, keys: &PaddleKeys, speed: f32) -> Vector3 {
+    Vector3::new(
+        axis(keys_pressed, keys.x, speed),
+        axis(keys_pressed, keys.y, speed),
+        axis(keys_pressed, keys.z, speed),
+    )
+}
+
+// Drive `paddle` at `velocity`, echoing its other properties unchanged
+pub fn paddle_action(paddle: &RigidBody, velocity: Vector3) -> Action {
+    Action {
+        body_id: paddle.id.clone(),
+        velocity,
+        position: paddle.position,
+        aabb: paddle.aabb.clone(),
+        mass: paddle.mass,
+        restitution: paddle.restitution,
+        dynamic: !paddle.is_static(),
+        friction: Some(paddle.friction),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::arena::ArenaConfig;
+    use crate::physics::object::BodyKind;
+    use crate::server::server::apply_action;
+
+    #[test]
+    fn paddle_action_keeps_the_paddle_kinematic() {
+        let (mut world, _) = ArenaConfig::default().pong_world();
+        let paddle = world.get_body("paddle1").unwrap();
+        assert_eq!(paddle.kind, BodyKind::Kinematic);
+
+        let action = paddle_action(paddle, Vector3::new(0.0, PADDLE_SPEED, 0.0));
+        let action = Action::from_msgpack(&action.to_msgpack().unwrap()).unwrap();
+        apply_action(&mut world, action).unwrap();
+        let paddle = world.get_body("paddle1").unwrap();
+        assert_eq!(paddle.kind, BodyKind::Kinematic);
+        assert_eq!(paddle.velocity, Vector3::new(0.0, PADDLE_SPEED, 0.0));
+    }
+
+    #[test]
+    fn held_keys_map_to_paddle_velocity() {
+        let keys = |pressed: &[KeyCode]| pressed.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(
+            paddle_velocity(&keys(&[]), &PADDLE1_KEYS, 2.0),
+            Vector3::zero()
+        );
+        assert_eq!(
+            paddle_velocity(&keys(&[KeyCode::KeyW, KeyCode::Space]), &PADDLE1_KEYS, 2.0),
+            Vector3::new(2.0, 2.0, 0.0)
+        );
+        assert_eq!(
+            paddle_velocity(&keys(&[KeyCode::KeyS, KeyCode::KeyA]), &PADDLE1_KEYS, 2.0),
+            Vector3::new(-2.0, 0.0, -2.0)
+        );
+        // Opposite keys together: the positive one wins
+        assert_eq!(
+            paddle_velocity(&keys(&[KeyCode::KeyD, KeyCode::KeyA]), &PADDLE1_KEYS, 2.0),
+            Vector3::new(0.0, 0.0, 2.0)
+        );
+        // Paddle2 has no x keys, and ignores paddle1's
+        assert_eq!(
+            paddle_velocity(
+                &keys(&[KeyCode::ArrowDown, KeyCode::ArrowLeft, KeyCode::KeyW]),
+                &PADDLE2_KEYS,
+                2.0
+            ),
+            Vector3::new(0.0, -2.0, 2.0)
+        );
+    }
+}
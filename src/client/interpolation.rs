@@ -0,0 +1,161 @@
+use crate::physics::RigidBody;
+use crate::server::models::WorldState;
+use std::collections::VecDeque;
+
+// Render this far behind the newest snapshot so there is usually a pair to interpolate between
+pub const INTERPOLATION_DELAY_MS: u64 = 50;
+
+// Recent server snapshots ordered by timestamp_ms, sampled at a render time in between
+pub struct StateBuffer {
+    pub capacity: usize,
+    states: VecDeque<WorldState>,
+}
+
+impl StateBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(2),
+            states: VecDeque::new(),
+        }
+    }
+
+    // Out of order or duplicate snapshots are dropped
+    pub fn push(&mut self, state: WorldState) {
+        if self
+            .states
+            .back()
+            .is_some_and(|last| state.timestamp_ms <= last.timestamp_ms)
+        {
+            return;
+        }
+        self.states.push_back(state);
+        while self.states.len() > self.capacity {
+            self.states.pop_front();
+        }
+    }
+
+    pub fn latest(&self) -> Option<&WorldState> {
+        self.states.back()
+    }
+
+    pub fn latest_body(&self, id: &str) -> Option<&RigidBody> {
+        self.latest()?.bodies.iter().find(|b| b.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    // Positions are lerped between the snapshots bracketing render_time_ms, everything else
+    // comes from the earlier one; outside the buffered range the nearest snapshot is returned
+    pub fn sample(&self, render_time_ms: u64) -> Option<WorldState> {
+        let first = self.states.front()?;
+        if render_time_ms <= first.timestamp_ms {
+            return Some(first.clone());
+        }
+
+        let Some(i) = self
+            .states
+            .iter()
+            .position(|s| s.timestamp_ms >= render_time_ms)
+        else {
+            return self.latest().cloned();
+        };
+        let (from, to) = (&self.states[i - 1], &self.states[i]);
+        let span = (to.timestamp_ms - from.timestamp_ms) as f32;
+        let alpha = (render_time_ms - from.timestamp_ms) as f32 / span;
+
+        let mut state = from.clone();
+        for body in state.bodies.iter_mut() {
+            if let Some(next) = to.bodies.iter().find(|b| b.id == body.id) {
+                let position = body.position.lerp(&next.position, alpha);
+                body.update_position(position.x, position.y, position.z);
+            }
+        }
+        state.time = from.time + (to.time - from.time) * alpha;
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::game_engine::GamePhase;
+    use crate::physics::Vector3;
+
+    // One ball at (x, 0, 0), stamped `timestamp_ms`
+    fn state_at(timestamp_ms: u64, x: f32) -> WorldState {
+        let ball = RigidBody::from_extents_with_id(
+            "ball".to_string(),
+            Vector3::new(x, 0.0, 0.0),
+            Vector3::new(10.0, 0.0, 0.0),
+            Vector3::new(0.5, 0.5, 0.5),
+            1.0,
+            1.0,
+            false,
+        );
+        WorldState {
+            bodies: vec![ball],
+            time: timestamp_ms as f32 / 1000.0,
+            score_player1: 0,
+            score_player2: 0,
+            tick_id: timestamp_ms,
+            timestamp_ms,
+            phase: GamePhase::Playing,
+            tick_count: 0,
+            sim_time: 0.0,
+        }
+    }
+
+    fn ball_x(state: &WorldState) -> f32 {
+        state.bodies[0].position.x
+    }
+
+    #[test]
+    fn empty_buffer_has_nothing_to_sample() {
+        let buffer = StateBuffer::new(4);
+        assert!(buffer.is_empty());
+        assert!(buffer.sample(100).is_none());
+    }
+
+    #[test]
+    fn single_state_is_returned_at_any_time() {
+        let mut buffer = StateBuffer::new(4);
+        buffer.push(state_at(100, 1.0));
+        for time in [0, 100, 500] {
+            assert_eq!(ball_x(&buffer.sample(time).unwrap()), 1.0);
+        }
+    }
+
+    #[test]
+    fn constant_velocity_is_interpolated_between_ticks() {
+        // 10 units/s, so 1 unit per 100 ms
+        let mut buffer = StateBuffer::new(4);
+        buffer.push(state_at(1000, 0.0));
+        buffer.push(state_at(1100, 1.0));
+        buffer.push(state_at(1200, 2.0));
+
+        let mid = buffer.sample(1150).unwrap();
+        assert!((ball_x(&mid) - 1.5).abs() < 1e-6);
+        assert!((mid.bodies[0].aabb.get_center().x - 1.5).abs() < 1e-6);
+        assert!((mid.time - 1.15).abs() < 1e-6);
+        assert_eq!(ball_x(&buffer.sample(1100).unwrap()), 1.0);
+    }
+
+    #[test]
+    fn outside_the_buffer_clamps_to_the_nearest_state() {
+        let mut buffer = StateBuffer::new(2);
+        buffer.push(state_at(1000, 0.0));
+        buffer.push(state_at(1100, 1.0));
+        buffer.push(state_at(1200, 2.0));
+        buffer.push(state_at(1150, 9.0)); // Out of order, dropped
+        assert_eq!(buffer.len(), 2);
+
+        assert_eq!(ball_x(&buffer.sample(900).unwrap()), 1.0);
+        assert_eq!(ball_x(&buffer.sample(5000).unwrap()), 2.0);
+    }
+}
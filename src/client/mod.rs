@@ -0,0 +1,4 @@
+pub mod input;
+pub mod interpolation;
+pub mod subscriber;
+pub mod sync;
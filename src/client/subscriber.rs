@@ -0,0 +1,140 @@
+use crate::server::ipc::{IPCChannel, WORLD_TOPIC};
+use log::{info, warn};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connected,
+    Reconnecting, // Silent for longer than the timeout, the socket has been recreated
+}
+
+// SUB socket that is torn down and reconnected after `timeout` without any message
+pub struct StateSubscriber {
+    pub endpoint: String,
+    pub timeout: Duration,
+    pub state: ConnectionState,
+    pub reconnects: u32,
+    channel: IPCChannel,
+    last_message: Instant, // Or the last reconnect, so each attempt gets a full timeout
+}
+
+impl StateSubscriber {
+    pub fn new(endpoint: &str, timeout_ms: u64) -> Result<Self, zmq::Error> {
+        Ok(StateSubscriber {
+            endpoint: endpoint.to_string(),
+            timeout: Duration::from_millis(timeout_ms),
+            state: ConnectionState::Connected,
+            reconnects: 0,
+            channel: Self::connect(endpoint)?,
+            last_message: Instant::now(),
+        })
+    }
+
+    // All frames queued right now without their topic, reconnecting first if the stream
+    // has gone quiet
+    pub fn drain(&mut self) -> Vec<Vec<u8>> {
+        let mut frames = Vec::new();
+        while let Ok(mut bytes) = self.channel.recv_bytes_nonblocking() {
+            bytes.drain(..WORLD_TOPIC.len());
+            frames.push(bytes);
+        }
+
+        if !frames.is_empty() {
+            self.last_message = Instant::now();
+            if self.state == ConnectionState::Reconnecting {
+                info!("Reconnected to {}", self.endpoint);
+            }
+            self.state = ConnectionState::Connected;
+        } else if self.last_message.elapsed() > self.timeout {
+            self.reconnect();
+        }
+        frames
+    }
+
+    // Linger 0 so dropping the old socket on reconnect never blocks. Only the main world,
+    // rooms publish on their own topics.
+    fn connect(endpoint: &str) -> Result<IPCChannel, zmq::Error> {
+        IPCChannel::new(zmq::SUB)?
+            .with_linger(0)?
+            .connect(endpoint)?
+            .subscribe(WORLD_TOPIC.as_bytes())
+    }
+
+    fn reconnect(&mut self) {
+        if self.state == ConnectionState::Connected {
+            warn!(
+                "No messages from {} for {:?}, reconnecting",
+                self.endpoint, self.timeout
+            );
+        }
+        self.state = ConnectionState::Reconnecting;
+        self.last_message = Instant::now();
+        match Self::connect(&self.endpoint) {
+            Ok(channel) => {
+                self.channel = channel;
+                self.reconnects += 1;
+            }
+            // Keep the old socket and try again after another timeout
+            Err(e) => warn!("Failed to reconnect to {}: {}", self.endpoint, e),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.state == ConnectionState::Connected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Publishes `payload` until the subscriber drains something, or gives up after a second
+    fn publish_until_received(
+        publisher: &IPCChannel,
+        subscriber: &mut StateSubscriber,
+        payload: &[u8],
+    ) -> Vec<Vec<u8>> {
+        for _ in 0..100 {
+            publisher.send_topic(WORLD_TOPIC, payload).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+            let frames = subscriber.drain();
+            if !frames.is_empty() {
+                return frames;
+            }
+        }
+        Vec::new()
+    }
+
+    #[test]
+    fn resumes_after_the_server_restarts() {
+        // Separate contexts, so tcp on a port picked by the OS
+        let publisher = IPCChannel::new(zmq::PUB)
+            .unwrap()
+            .with_linger(0)
+            .unwrap()
+            .bind("tcp://127.0.0.1:*")
+            .unwrap();
+        let endpoint = publisher.socket.get_last_endpoint().unwrap().unwrap();
+        let mut subscriber = StateSubscriber::new(&endpoint, 100).unwrap();
+
+        let frames = publish_until_received(&publisher, &mut subscriber, b"first");
+        assert_eq!(frames.last().unwrap(), b"first");
+        assert!(subscriber.is_connected());
+
+        drop(publisher);
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(subscriber.drain().is_empty());
+        assert_eq!(subscriber.state, ConnectionState::Reconnecting);
+        assert_eq!(subscriber.reconnects, 1);
+
+        let publisher = IPCChannel::new(zmq::PUB)
+            .unwrap()
+            .with_linger(0)
+            .unwrap()
+            .bind(&endpoint)
+            .unwrap();
+        let frames = publish_until_received(&publisher, &mut subscriber, b"second");
+        assert_eq!(frames.last().unwrap(), b"second");
+        assert!(subscriber.is_connected());
+    }
+}
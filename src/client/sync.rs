@@ -0,0 +1,53 @@
+use crate::server::models::{StateMessage, WorldState};
+use log::debug;
+
+// Rebuilds full states from the engine's broadcast stream. Deltas are against the previous
+// broadcast, and PUB/SUB drops everything sent before a subscriber connected, so a late
+// joiner has no baseline until the next keyframe (every Engine::keyframe_interval
+// broadcasts). A delta whose base isn't the last state rebuilt means one was missed, and
+// also waits for the next keyframe.
+#[derive(Default)]
+pub struct StateSync {
+    baseline: Option<WorldState>,
+}
+
+impl StateSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn has_baseline(&self) -> bool {
+        self.baseline.is_some()
+    }
+
+    // The full state after `message`, None while waiting for a keyframe
+    pub fn push(&mut self, message: StateMessage) -> Option<WorldState> {
+        match message {
+            StateMessage::Keyframe(state) => {
+                self.baseline = Some(state.clone());
+                Some(state)
+            }
+            StateMessage::Delta(delta) => {
+                let baseline = self.baseline.as_mut()?;
+                if baseline.tick_id != delta.base_tick_id {
+                    debug!(
+                        "Delta {} is against tick {}, last state was {}, waiting for a keyframe",
+                        delta.tick_id, delta.base_tick_id, baseline.tick_id
+                    );
+                    self.baseline = None;
+                    return None;
+                }
+                baseline.apply_delta(&delta);
+                Some(baseline.clone())
+            }
+        }
+    }
+
+    // Servers without delta broadcasting send plain WorldStates, those pass straight through
+    pub fn decode(&mut self, bytes: &[u8]) -> Option<WorldState> {
+        if let Ok(message) = StateMessage::from_msgpack(bytes) {
+            return self.push(message);
+        }
+        WorldState::from_msgpack(bytes).ok()
+    }
+}
@@ -0,0 +1,143 @@
+use crate::client::input::paddle_action;
+use crate::physics::Vector3;
+use crate::server::models::{Action, WorldState};
+use std::collections::VecDeque;
+
+// How hard the paddle chases the ball per unit of distance, before max_speed clamps it
+const AI_GAIN: f32 = 5.0;
+
+// Tracking opponent: steers its paddle toward where the ball was `reaction_delay` seconds ago
+pub struct PaddleAi {
+    pub paddle_id: String,
+    pub max_speed: f32,
+    pub reaction_delay: f32,           // Seconds
+    pub deadband: f32,                 // Stop when this close to the target to avoid jitter
+    history: VecDeque<(f32, Vector3)>, // (state time, ball position)
+}
+
+impl PaddleAi {
+    pub fn new(paddle_id: String, max_speed: f32, reaction_delay: f32) -> Self {
+        PaddleAi {
+            paddle_id,
+            max_speed,
+            reaction_delay,
+            deadband: 0.5,
+            history: VecDeque::new(),
+        }
+    }
+
+    // Ball position as the AI perceives it, lagging by reaction_delay
+    fn perceived_ball(&mut self, time: f32, ball_position: Vector3) -> Vector3 {
+        // A clock going backwards means the world was reset
+        if self.history.back().is_some_and(|(t, _)| *t > time) {
+            self.history.clear();
+        }
+        self.history.push_back((time, ball_position));
+        while self.history.len() > 1 && self.history[1].0 <= time - self.reaction_delay {
+            self.history.pop_front();
+        }
+        self.history[0].1
+    }
+
+    fn axis_velocity(&self, offset: f32) -> f32 {
+        if offset.abs() <= self.deadband {
+            0.0
+        } else {
+            (offset * AI_GAIN).clamp(-self.max_speed, self.max_speed)
+        }
+    }
+
+    // Only y and z are driven, the paddle keeps its x velocity
+    pub fn target_velocity(&mut self, state: &WorldState) -> Option<Vector3> {
+        let ball = state.bodies.iter().find(|b| b.id == "ball")?;
+        let paddle = state.bodies.iter().find(|b| b.id == self.paddle_id)?;
+        let target = self.perceived_ball(state.time, ball.position);
+        Some(Vector3::new(
+            paddle.velocity.x,
+            self.axis_velocity(target.y - paddle.position.y),
+            self.axis_velocity(target.z - paddle.position.z),
+        ))
+    }
+
+    pub fn action(&mut self, state: &WorldState) -> Option<Action> {
+        let velocity = self.target_velocity(state)?;
+        let paddle = state.bodies.iter().find(|b| b.id == self.paddle_id)?;
+        Some(paddle_action(paddle, velocity))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::arena::ArenaConfig;
+    use crate::game::game_engine::GamePhase;
+
+    // Default Pong world with the ball moved to `ball`, at `time` seconds
+    fn state_with_ball(ball: Vector3, time: f32) -> WorldState {
+        let (mut world, _) = ArenaConfig::default().pong_world();
+        world
+            .get_body_mut("ball")
+            .unwrap()
+            .update_position(ball.x, ball.y, ball.z);
+        WorldState {
+            bodies: world.bodies,
+            time,
+            score_player1: 0,
+            score_player2: 0,
+            tick_id: 0,
+            timestamp_ms: 0,
+            phase: GamePhase::Playing,
+            tick_count: 0,
+            sim_time: time as f64,
+        }
+    }
+
+    #[test]
+    fn moves_toward_a_ball_above_and_clamps_to_max_speed() {
+        let mut ai = PaddleAi::new("paddle2".to_string(), 8.0, 0.0);
+        let action = ai
+            .action(&state_with_ball(Vector3::new(0.0, 10.0, -0.2), 0.0))
+            .unwrap();
+        assert_eq!(action.body_id, "paddle2");
+        assert_eq!(action.velocity, Vector3::new(0.0, 8.0, 0.0));
+
+        let velocity = ai
+            .target_velocity(&state_with_ball(Vector3::new(0.0, -1.0, 0.0), 0.1))
+            .unwrap();
+        assert_eq!(velocity, Vector3::new(0.0, -5.0, 0.0));
+    }
+
+    #[test]
+    fn stops_inside_the_deadband() {
+        let mut ai = PaddleAi::new("paddle2".to_string(), 8.0, 0.0);
+        let velocity = ai
+            .target_velocity(&state_with_ball(Vector3::new(0.0, 0.4, -0.5), 0.0))
+            .unwrap();
+        assert_eq!(velocity, Vector3::zero());
+    }
+
+    #[test]
+    fn reacts_to_where_the_ball_was() {
+        let mut ai = PaddleAi::new("paddle2".to_string(), 100.0, 0.5);
+        ai.target_velocity(&state_with_ball(Vector3::new(0.0, 10.0, 0.0), 0.0));
+        let late = ai
+            .target_velocity(&state_with_ball(Vector3::new(0.0, -10.0, 0.0), 0.25))
+            .unwrap();
+        assert!(late.y > 0.0, "still chasing the old position");
+        let caught_up = ai
+            .target_velocity(&state_with_ball(Vector3::new(0.0, -10.0, 0.0), 0.75))
+            .unwrap();
+        assert!(caught_up.y < 0.0);
+    }
+
+    #[test]
+    fn no_ball_or_paddle_means_no_action() {
+        let mut state = state_with_ball(Vector3::zero(), 0.0);
+        state.bodies.retain(|b| b.id != "ball");
+        let mut ai = PaddleAi::new("paddle2".to_string(), 8.0, 0.0);
+        assert!(ai.action(&state).is_none());
+
+        let mut ai = PaddleAi::new("paddle3".to_string(), 8.0, 0.0);
+        assert!(ai.action(&state_with_ball(Vector3::zero(), 0.0)).is_none());
+    }
+}
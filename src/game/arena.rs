@@ -0,0 +1,196 @@
+use crate::game::game_engine::GameObject;
+use crate::physics::object::AABB;
+use crate::physics::world::{
+    PONG_LAYER_BALL, PONG_LAYER_PADDLE, PONG_LAYER_WALL, PONG_MASK_BALL, PONG_MASK_PADDLE,
+    PONG_MASK_WALL,
+};
+use crate::physics::{RigidBody, Vector3, World};
+use serde::{Deserialize, Serialize};
+
+// Arena geometry shared by world setup, scoring and action limits; the arena is centred on the origin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)] // Missing fields keep their default, e.g. in a scene's arena block
+pub struct ArenaConfig {
+    pub width: f32,  // Along x, the scoring axis
+    pub height: f32, // Along y
+    pub depth: f32,  // Along z
+    pub wall_thickness: f32,
+    pub paddle_inset: f32, // Distance from each scoring plane to the paddle centre
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        ArenaConfig {
+            width: 60.0,
+            height: 40.0,
+            depth: 40.0,
+            wall_thickness: 2.0,
+            paddle_inset: 5.0,
+        }
+    }
+}
+
+impl ArenaConfig {
+    pub fn half_width(&self) -> f32 {
+        self.width / 2.0
+    }
+
+    pub fn half_height(&self) -> f32 {
+        self.height / 2.0
+    }
+
+    pub fn half_depth(&self) -> f32 {
+        self.depth / 2.0
+    }
+
+    // Player 1 defends -x, player 2 defends +x; a ball past either plane scores
+    pub fn scoring_plane_x(&self) -> f32 {
+        self.half_width()
+    }
+
+    // Some(scorer) if the ball position is past a scoring plane
+    pub fn scorer_for(&self, ball_position: &Vector3) -> Option<u8> {
+        if ball_position.x < -self.scoring_plane_x() {
+            Some(2)
+        } else if ball_position.x > self.scoring_plane_x() {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    pub fn paddle_position(&self, player: u8) -> Vector3 {
+        let x = self.half_width() - self.paddle_inset;
+        if player == 1 {
+            Vector3::new(-x, 0.0, 0.0)
+        } else {
+            Vector3::new(x, 0.0, 0.0)
+        }
+    }
+
+    pub fn bounds(&self) -> AABB {
+        AABB::new(
+            Vector3::new(-self.half_width(), -self.half_height(), -self.half_depth()),
+            Vector3::new(self.half_width(), self.half_height(), self.half_depth()),
+        )
+    }
+
+    // Floor, ceiling and the two side walls, centred on the arena faces
+    pub fn wall_bodies(&self) -> Vec<RigidBody> {
+        let half_thickness = self.wall_thickness / 2.0;
+        let walls = [
+            (
+                Vector3::new(0.0, -self.half_height(), 0.0),
+                Vector3::new(self.half_width(), half_thickness, self.half_depth()),
+            ),
+            (
+                Vector3::new(0.0, self.half_height(), 0.0),
+                Vector3::new(self.half_width(), half_thickness, self.half_depth()),
+            ),
+            (
+                Vector3::new(0.0, 0.0, -self.half_depth()),
+                Vector3::new(self.half_width(), self.half_height(), half_thickness),
+            ),
+            (
+                Vector3::new(0.0, 0.0, self.half_depth()),
+                Vector3::new(self.half_width(), self.half_height(), half_thickness),
+            ),
+        ];
+
+        walls
+            .iter()
+            .enumerate()
+            .map(|(i, (position, half_extents))| {
+                let mut wall = RigidBody::from_extents_with_id(
+                    format!("wall_{}", i),
+                    *position,
+                    Vector3::zero(),
+                    *half_extents,
+                    0.0,
+                    1.0,
+                    true,
+                );
+                wall.set_collision_layers(PONG_LAYER_WALL, PONG_MASK_WALL);
+                wall
+            })
+            .collect()
+    }
+
+    // Kinematic paddle for `player` (1 or 2) at its inset position: it moves at the
+    // velocity it is given and the ball can't push it
+    pub fn paddle_body(&self, player: u8) -> RigidBody {
+        let position = self.paddle_position(player);
+        let mut paddle = RigidBody::new_kinematic(
+            format!("paddle{}", player),
+            position,
+            Vector3::zero(),
+            AABB::from_center_size(&position, &Vector3::new(2.0, 6.0, 6.0)),
+            1.0,
+        );
+        paddle.set_collision_layers(PONG_LAYER_PADDLE, PONG_MASK_PADDLE);
+        paddle
+    }
+
+    // Ball at the origin, already moving towards player 2
+    pub fn ball_body(&self) -> RigidBody {
+        let mut ball = RigidBody::from_extents_with_id(
+            "ball".to_string(),
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(8.0, 4.0, 0.0),
+            Vector3::new(0.5, 0.5, 0.5),
+            1.0,
+            1.0,
+            false,
+        );
+        ball.set_collision_layers(PONG_LAYER_BALL, PONG_MASK_BALL);
+        ball
+    }
+
+    // Standard Pong world: wall_0..wall_3, paddle1, paddle2 and ball, with a GameObject per body
+    pub fn pong_world(&self) -> (World, Vec<GameObject>) {
+        let mut world = World::new_empty();
+        for wall in self.wall_bodies() {
+            world.add_body(wall);
+        }
+        world.add_body(self.paddle_body(1));
+        world.add_body(self.paddle_body(2));
+        world.add_body(self.ball_body());
+
+        let game_objects = world
+            .bodies
+            .iter()
+            .cloned()
+            .map(GameObject::from_body)
+            .collect();
+        (world, game_objects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::object::BodyKind;
+
+    #[test]
+    fn pong_world_has_four_walls_two_paddles_and_a_ball() {
+        let arena = ArenaConfig::default();
+        let (world, game_objects) = arena.pong_world();
+
+        let ids: Vec<&str> = world.bodies.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["wall_0", "wall_1", "wall_2", "wall_3", "paddle1", "paddle2", "ball"]
+        );
+        assert_eq!(game_objects.len(), ids.len());
+        assert!(world.bodies_with_prefix("wall").all(|w| w.is_static()));
+
+        for player in [1, 2] {
+            let paddle = world.get_body(&format!("paddle{player}")).unwrap();
+            assert_eq!(paddle.kind, BodyKind::Kinematic);
+            assert_eq!(paddle.position, arena.paddle_position(player));
+        }
+        let ball = world.get_body("ball").unwrap();
+        assert!(ball.is_dynamic());
+        assert_eq!(ball.position, Vector3::zero());
+    }
+}
@@ -1,8 +1,9 @@
-use crate::physics::{RigidBody, Vector3};
+use crate::game::arena::ArenaConfig;
+use crate::physics::{RigidBody, Vector3, World};
 use glam::{Mat4, Vec3};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum GameObjectType {
     Wall,
     Paddle,
@@ -37,11 +38,21 @@ impl GameObject {
         }
     }
 
+    // Object type inferred from the Pong body id convention, extra balls are "ball_<n>"
+    pub fn from_body(body: RigidBody) -> Self {
+        let obj_type = match body.id.as_str() {
+            id if id.starts_with("ball") => GameObjectType::Ball,
+            "paddle1" | "paddle2" => GameObjectType::Paddle,
+            _ => GameObjectType::Wall,
+        };
+        Self::new(body, obj_type)
+    }
+
     pub fn get_model_matrix(&self) -> Mat4 {
         let position = Vec3::new(
-            self.body.position.x as f32,
-            self.body.position.y as f32,
-            self.body.position.z as f32,
+            self.body.position.x,
+            self.body.position.y,
+            self.body.position.z,
         );
 
         let half_extents = self.body.get_half_extents();
@@ -51,44 +62,134 @@ impl GameObject {
             half_extents.z * 2.0,
         );
 
-        Mat4::from_scale_rotation_translation(scale, glam::Quat::IDENTITY, position)
+        Mat4::from_scale_rotation_translation(scale, self.body.orientation, position)
+    }
+}
+
+pub const DEFAULT_WIN_SCORE: u32 = 11;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GamePhase {
+    #[default]
+    Playing,
+    GameOver {
+        winner: u8,
+    },
+}
+
+// A Pong world must hold both "paddle1" and "paddle2", see GameState::for_world
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingPaddle(pub String);
+
+impl std::fmt::Display for MissingPaddle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "world has no body with id '{}'", self.0)
     }
 }
 
-#[derive(Serialize)]
+impl std::error::Error for MissingPaddle {}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct GameState {
     pub score_player1: u32,
     pub score_player2: u32,
+    pub win_score: u32,
+    pub phase: GamePhase,
     pub paddle1_index: usize,
     pub paddle2_index: usize,
-    pub ball_index: usize,
+    pub ball_indices: Vec<usize>, // Every ball in play, the first is the primary one
 }
 
 impl GameState {
-    pub fn new(paddle1_index: usize, paddle2_index: usize, ball_index: usize) -> Self {
+    // `ball_index` None starts with no ball in play, e.g. before one is spawned
+    pub fn new(paddle1_index: usize, paddle2_index: usize, ball_index: Option<usize>) -> Self {
         Self {
             score_player1: 0,
             score_player2: 0,
+            win_score: DEFAULT_WIN_SCORE,
+            phase: GamePhase::Playing,
             paddle1_index,
             paddle2_index,
+            ball_indices: ball_index.into_iter().collect(),
+        }
+    }
+
+    // Indices of "paddle1", "paddle2" and "ball" in `world`; the ball is optional
+    pub fn for_world(world: &World) -> Result<Self, MissingPaddle> {
+        let index_of = |id: &str| {
+            world
+                .body_map
+                .get(id)
+                .copied()
+                .ok_or_else(|| MissingPaddle(id.to_string()))
+        };
+        let ball_index = world.body_map.get("ball").copied();
+        Ok(Self::new(
+            index_of("paddle1")?,
+            index_of("paddle2")?,
             ball_index,
+        ))
+    }
+
+    pub fn check_scoring(
+        &mut self,
+        game_objects: &[GameObject],
+        arena: &ArenaConfig,
+    ) -> Option<u8> {
+        let scored = self.scored_balls_by(|i| game_objects.get(i).map(|o| &o.body), arena);
+        for (_, player) in &scored {
+            self.award_point(*player);
         }
+        scored.first().map(|(_, player)| *player)
+    }
+
+    // The primary ball, None when no ball is in play
+    pub fn ball_index(&self) -> Option<usize> {
+        self.ball_indices.first().copied()
+    }
+
+    // (body index, scorer) for every ball past a scoring plane
+    pub fn scored_balls(&self, bodies: &[RigidBody], arena: &ArenaConfig) -> Vec<(usize, u8)> {
+        self.scored_balls_by(|i| bodies.get(i), arena)
     }
 
-    pub fn check_scoring(&mut self, game_objects: &[GameObject]) -> Option<u8> {
-        let ball = &game_objects[self.ball_index].body;
+    // As scored_balls, with `body_at` looking up the body at a ball index
+    fn scored_balls_by<'a>(
+        &self,
+        body_at: impl Fn(usize) -> Option<&'a RigidBody>,
+        arena: &ArenaConfig,
+    ) -> Vec<(usize, u8)> {
+        self.ball_indices
+            .iter()
+            .filter_map(|&i| {
+                let ball = body_at(i)?;
+                arena.scorer_for(&ball.position).map(|scorer| (i, scorer))
+            })
+            .collect()
+    }
 
-        if ball.position.x < -30.0 {
-            // Updated for new arena size
-            self.score_player2 += 1;
-            return Some(2);
-        } else if ball.position.x > 30.0 {
-            // Updated for new arena size
-            self.score_player1 += 1;
-            return Some(1);
+    // Adds a point and ends the match once a player reaches win_score
+    pub fn award_point(&mut self, player: u8) -> GamePhase {
+        let score = if player == 1 {
+            &mut self.score_player1
+        } else {
+            &mut self.score_player2
+        };
+        *score += 1;
+        if *score >= self.win_score {
+            self.phase = GamePhase::GameOver { winner: player };
         }
+        self.phase
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        matches!(self.phase, GamePhase::GameOver { .. })
+    }
 
-        None
+    pub fn restart(&mut self) {
+        self.score_player1 = 0;
+        self.score_player2 = 0;
+        self.phase = GamePhase::Playing;
     }
 
     pub fn reset_ball(game_objects: &mut [GameObject]) {
@@ -100,3 +201,89 @@ impl GameState {
         game_objects[ball_index].body.velocity = Vector3::new(5.0, 2.0, 0.0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reaching_win_score_ends_the_match() {
+        let mut state = GameState::new(0, 1, Some(2));
+        state.win_score = 3;
+        for _ in 0..2 {
+            assert_eq!(state.award_point(2), GamePhase::Playing);
+        }
+        assert_eq!(state.award_point(1), GamePhase::Playing);
+        assert_eq!(state.award_point(2), GamePhase::GameOver { winner: 2 });
+        assert!(state.is_game_over());
+    }
+
+    #[test]
+    fn restart_returns_to_playing() {
+        let mut state = GameState::new(0, 1, Some(2));
+        state.win_score = 1;
+        state.award_point(1);
+        assert!(state.is_game_over());
+
+        state.restart();
+        assert_eq!(state.phase, GamePhase::Playing);
+        assert_eq!((state.score_player1, state.score_player2), (0, 0));
+    }
+
+    #[test]
+    fn orientation_follows_angular_velocity() {
+        let mut body = RigidBody::from_extents_with_id(
+            "box".to_string(),
+            Vector3::zero(),
+            Vector3::zero(),
+            Vector3::new(2.0, 0.5, 1.0),
+            1.0,
+            1.0,
+            false,
+        );
+        body.angular_velocity = Vector3::new(0.0, std::f32::consts::FRAC_PI_2, 0.0);
+        let mut world = crate::physics::World::new_empty();
+        world.add_body(body);
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+
+        let object = GameObject::from_body(world.bodies[0].clone());
+        let expected = glam::Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        assert!(object.body.orientation.angle_between(expected) < 1e-3);
+
+        // The shader's normal matrix: a +X face ends up facing -Z despite the scale
+        let normal_matrix = glam::Mat3::from_mat4(object.get_model_matrix())
+            .inverse()
+            .transpose();
+        let normal = (normal_matrix * Vec3::X).normalize();
+        assert!(normal.abs_diff_eq(Vec3::NEG_Z, 1e-3));
+    }
+
+    #[test]
+    fn balls_past_a_scoring_plane_score_for_the_other_side() {
+        let arena = ArenaConfig {
+            width: 20.0,
+            ..ArenaConfig::default()
+        };
+        assert_eq!(arena.scoring_plane_x(), 10.0);
+        let (_, mut objects) = arena.pong_world();
+        let ball = objects
+            .iter()
+            .position(|o| o.object_type == GameObjectType::Ball)
+            .unwrap();
+        let mut state = GameState::new(0, 1, Some(ball));
+
+        objects[ball].body.update_position(9.5, 0.0, 0.0);
+        assert_eq!(state.check_scoring(&objects, &arena), None);
+        objects[ball].body.update_position(10.5, 0.0, 0.0);
+        assert_eq!(state.check_scoring(&objects, &arena), Some(1));
+        objects[ball].body.update_position(-10.5, 0.0, 0.0);
+        assert_eq!(state.check_scoring(&objects, &arena), Some(2));
+        assert_eq!((state.score_player1, state.score_player2), (1, 1));
+
+        // No ball in play, nothing to score
+        let mut state = GameState::new(0, 1, None);
+        assert_eq!(state.check_scoring(&objects, &arena), None);
+    }
+}
@@ -1,2 +1,5 @@
+pub mod ai;
+pub mod arena;
 pub mod game_engine;
+pub mod scene;
 
@@ -0,0 +1,234 @@
+use crate::game::arena::ArenaConfig;
+use crate::game::game_engine::{GameObject, GameObjectType};
+use crate::physics::object::BodyKind;
+use crate::physics::world::{
+    PONG_LAYER_BALL, PONG_LAYER_PADDLE, PONG_LAYER_WALL, PONG_MASK_BALL, PONG_MASK_PADDLE,
+    PONG_MASK_WALL,
+};
+use crate::physics::{RigidBody, Vector3, World};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+fn default_restitution() -> f32 {
+    1.0
+}
+
+fn default_mass() -> f32 {
+    1.0
+}
+
+// One body of a scene file; the AABB is derived from position and half_extents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodyDef {
+    pub id: String,
+    pub position: Vector3,
+    #[serde(default = "Vector3::zero")]
+    pub velocity: Vector3,
+    pub half_extents: Vector3,
+    #[serde(default = "default_mass")]
+    pub mass: f32, // Ignored for static bodies, must be positive otherwise
+    #[serde(default = "default_restitution")]
+    pub restitution: f32,
+    #[serde(default, rename = "static")]
+    pub is_static: bool,
+    #[serde(rename = "type")]
+    pub object_type: GameObjectType, // Also picks the Pong collision layers
+    #[serde(default)]
+    pub color: Option<[f32; 4]>, // None uses the type's colour, see GameObject::new
+}
+
+impl BodyDef {
+    // Paddles are kinematic, as in ArenaConfig::paddle_body, so balls can't push them around
+    pub fn to_body(&self) -> RigidBody {
+        let mut body = RigidBody::from_extents_with_id(
+            self.id.clone(),
+            self.position,
+            self.velocity,
+            self.half_extents,
+            self.mass,
+            self.restitution,
+            self.is_static,
+        );
+        let (layer, mask) = match self.object_type {
+            GameObjectType::Wall => (PONG_LAYER_WALL, PONG_MASK_WALL),
+            GameObjectType::Paddle => (PONG_LAYER_PADDLE, PONG_MASK_PADDLE),
+            GameObjectType::Ball => (PONG_LAYER_BALL, PONG_MASK_BALL),
+        };
+        body.set_collision_layers(layer, mask);
+        if self.object_type == GameObjectType::Paddle {
+            body.set_kind(BodyKind::Kinematic);
+        }
+        body
+    }
+}
+
+// Arena layout loaded from JSON instead of built in code, e.g.
+// {"bodies": [{"id": "ball", "position": {"x": 0, "y": 0, "z": 0},
+//   "half_extents": {"x": 0.5, "y": 0.5, "z": 0.5}, "mass": 1, "type": "Ball"}],
+//  "arena": {"width": 40, "height": 30}}
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scene {
+    pub bodies: Vec<BodyDef>,
+    #[serde(default)]
+    pub arena: Option<ArenaConfig>, // Scoring planes and action limits, None for the default
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    DuplicateId(String), // Ids key World::body_map, so each may appear once
+    InvalidMass(String), // A non-static body with mass <= 0 would ignore every impulse
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(err: std::io::Error) -> Self {
+        SceneError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SceneError {
+    fn from(err: serde_json::Error) -> Self {
+        SceneError::Json(err)
+    }
+}
+
+impl Scene {
+    pub fn from_json(json: &str) -> Result<Self, SceneError> {
+        let scene: Scene = serde_json::from_str(json)?;
+        scene.validate()?;
+        Ok(scene)
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, SceneError> {
+        let reader = BufReader::new(File::open(path)?);
+        let scene: Scene = serde_json::from_reader(reader)?;
+        scene.validate()?;
+        Ok(scene)
+    }
+
+    fn validate(&self) -> Result<(), SceneError> {
+        for (i, def) in self.bodies.iter().enumerate() {
+            if self.bodies[..i].iter().any(|other| other.id == def.id) {
+                return Err(SceneError::DuplicateId(def.id.clone()));
+            }
+            if !def.is_static && def.mass <= 0.0 {
+                return Err(SceneError::InvalidMass(def.id.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    // The world with every body in file order and a GameObject per body, like
+    // ArenaConfig::pong_world
+    pub fn build(&self) -> (World, Vec<GameObject>) {
+        let mut world = World::new_empty();
+        let mut game_objects = Vec::with_capacity(self.bodies.len());
+        for def in &self.bodies {
+            let body = def.to_body();
+            let mut object = GameObject::new(body.clone(), def.object_type);
+            if let Some(color) = def.color {
+                object.color = color;
+            }
+            world.add_body(body);
+            game_objects.push(object);
+        }
+        (world, game_objects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BALL: &str = r#"{"id": "ball", "position": {"x": 0, "y": 0, "z": 0},
+        "half_extents": {"x": 0.5, "y": 0.5, "z": 0.5}, "type": "Ball"}"#;
+    const WALL: &str = r#"{"id": "wall_0", "position": {"x": 0, "y": 10, "z": 0},
+        "half_extents": {"x": 20, "y": 0.5, "z": 5}, "static": true, "type": "Wall"}"#;
+
+    fn scene_of(bodies: &[&str]) -> Result<Scene, SceneError> {
+        Scene::from_json(&format!(r#"{{"bodies": [{}]}}"#, bodies.join(",")))
+    }
+
+    #[test]
+    fn missing_mass_defaults_to_one() {
+        let scene = scene_of(&[BALL, WALL]).unwrap();
+        let (world, objects) = scene.build();
+        assert_eq!(objects.len(), 2);
+        let ball = world.get_body("ball").unwrap();
+        assert_eq!(ball.mass, 1.0);
+        assert_eq!(ball.inv_mass, 1.0);
+        assert_eq!(ball.inverse_mass(), 1.0);
+        assert_eq!(ball.restitution, 1.0);
+        assert_eq!(ball.aabb.min, Vector3::new(-0.5, -0.5, -0.5));
+        assert_eq!(ball.aabb.max, Vector3::new(0.5, 0.5, 0.5));
+
+        // The AABB is centred on the position, and static bodies can't be pushed
+        let wall = world.get_body("wall_0").unwrap();
+        assert!(!wall.is_dynamic());
+        assert_eq!(wall.inv_mass, 0.0);
+        assert_eq!(wall.aabb.min, Vector3::new(-20.0, 9.5, -5.0));
+        assert_eq!(wall.aabb.max, Vector3::new(20.0, 10.5, 5.0));
+    }
+
+    #[test]
+    fn paddles_are_kinematic() {
+        let paddle = BALL
+            .replace(r#""ball""#, r#""paddle1""#)
+            .replace(r#""Ball""#, r#""Paddle""#);
+        let (world, _) = scene_of(&[&paddle]).unwrap().build();
+        let paddle = world.get_body("paddle1").unwrap();
+        assert_eq!(paddle.kind, BodyKind::Kinematic);
+        assert_eq!(paddle.inverse_mass(), 0.0);
+        assert_eq!(paddle.layer, PONG_LAYER_PADDLE);
+    }
+
+    #[test]
+    fn arena_block_fills_in_missing_fields() {
+        assert!(scene_of(&[BALL]).unwrap().arena.is_none());
+
+        let scene = Scene::from_json(&format!(
+            r#"{{"bodies": [{BALL}], "arena": {{"width": 30}}}}"#
+        ))
+        .unwrap();
+        let arena = scene.arena.unwrap();
+        assert_eq!(arena.width, 30.0);
+        assert_eq!(arena.height, ArenaConfig::default().height);
+    }
+
+    #[test]
+    fn zero_mass_dynamic_body_is_rejected() {
+        let weightless = BALL.replace(r#""type""#, r#""mass": 0, "type""#);
+        assert!(matches!(
+            scene_of(&[&weightless]),
+            Err(SceneError::InvalidMass(id)) if id == "ball"
+        ));
+        // Static bodies never use their mass
+        let wall = WALL.replace(r#""type""#, r#""mass": 0, "type""#);
+        assert!(scene_of(&[&wall]).is_ok());
+    }
+
+    #[test]
+    fn duplicate_ids_are_rejected() {
+        assert!(matches!(
+            scene_of(&[BALL, BALL]),
+            Err(SceneError::DuplicateId(id)) if id == "ball"
+        ));
+    }
+
+    #[test]
+    fn load_from_file_matches_from_json() {
+        let path = std::env::temp_dir().join(format!("volley_scene_{}.json", std::process::id()));
+        std::fs::write(&path, format!(r#"{{"bodies": [{BALL}, {WALL}]}}"#)).unwrap();
+        let loaded = Scene::load_from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let ids: Vec<String> = loaded.unwrap().bodies.into_iter().map(|b| b.id).collect();
+        assert_eq!(ids, ["ball", "wall_0"]);
+        assert!(matches!(
+            Scene::load_from_file(&path),
+            Err(SceneError::Io(_))
+        ));
+    }
+}
@@ -7,6 +7,12 @@ pub struct CameraUniform {
     pub view_proj: [[f32; 4]; 4],
 }
 
+impl Default for CameraUniform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CameraUniform {
     pub fn new() -> Self {
         Self {
@@ -19,6 +25,47 @@ impl CameraUniform {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectionMode {
+    Perspective,
+    Orthographic { height: f32 }, // Visible world-space height, width follows aspect
+}
+
+// Plane with inward-facing unit normal: points inside have distance >= 0
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub d: f32,
+}
+
+impl Plane {
+    fn from_coefficients(v: glam::Vec4) -> Self {
+        let length = v.truncate().length();
+        Self {
+            normal: v.truncate() / length,
+            d: v.w / length,
+        }
+    }
+
+    pub fn distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) + self.d
+    }
+}
+
+// A box is culled only if it lies entirely on the outside of some plane
+pub fn aabb_in_frustum(planes: &[Plane; 6], min: Vec3, max: Vec3) -> bool {
+    planes.iter().all(|plane| {
+        // Corner furthest along the plane normal
+        let positive = Vec3::new(
+            if plane.normal.x >= 0.0 { max.x } else { min.x },
+            if plane.normal.y >= 0.0 { max.y } else { min.y },
+            if plane.normal.z >= 0.0 { max.z } else { min.z },
+        );
+        plane.distance(positive) >= 0.0
+    })
+}
+
+#[derive(Clone)]
 pub struct Camera {
     pub position: Vec3,
     pub yaw: f32,
@@ -30,6 +77,19 @@ pub struct Camera {
     pub zfar: f32,
     pub target: Vec3,
     pub eye: Vec3,
+    pub projection: ProjectionMode,
+    pub following: bool,        // Mouse look is ignored while following a target
+    pub mouse_sensitivity: f32, // Radians per pixel of mouse motion
+}
+
+pub const DEFAULT_MOUSE_SENSITIVITY: f32 = 0.005;
+
+// Pitch stays just short of straight up or down, where look_at_rh has no valid up vector
+pub const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+// Mouse delta in pixels to (yaw, pitch) change in radians; moving the mouse up looks up
+pub fn scale_mouse_delta(dx: f64, dy: f64, sensitivity: f32) -> (f32, f32) {
+    (dx as f32 * sensitivity, -(dy as f32) * sensitivity)
 }
 
 impl Camera {
@@ -44,38 +104,249 @@ impl Camera {
             yaw,
             pitch,
             up: Vec3::Y,
-            aspect: width as f32 / height as f32,
+            aspect: width.max(1) as f32 / height.max(1) as f32,
             fovy: 60.0,
             znear: 0.1,
             zfar: 1000.0, // Increased render distance
             target,
             eye: position,
+            projection: ProjectionMode::Perspective,
+            following: false,
+            mouse_sensitivity: DEFAULT_MOUSE_SENSITIVITY,
         }
     }
 
-    pub fn build_view_projection_matrix(&self) -> Mat4 {
-        let direction = Vec3::new(
+    // Unit view direction from yaw and pitch
+    pub fn forward(&self) -> Vec3 {
+        Vec3::new(
             self.pitch.cos() * self.yaw.sin(),
             self.pitch.sin(),
             self.pitch.cos() * self.yaw.cos(),
         )
-        .normalize();
+        .normalize()
+    }
+
+    pub fn build_view_projection_matrix(&self) -> Mat4 {
+        let direction = self.forward();
         let view = Mat4::look_at_rh(self.position, self.position + direction, self.up);
-        let proj = Mat4::perspective_rh(self.fovy.to_radians(), self.aspect, self.znear, self.zfar);
+        let proj = match self.projection {
+            ProjectionMode::Perspective => {
+                Mat4::perspective_rh(self.fovy.to_radians(), self.aspect, self.znear, self.zfar)
+            }
+            ProjectionMode::Orthographic { height } => {
+                let half_height = height / 2.0;
+                let half_width = half_height * self.aspect;
+                Mat4::orthographic_rh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    self.znear,
+                    self.zfar,
+                )
+            }
+        };
         proj * view
     }
 
+    pub fn set_projection_mode(&mut self, mode: ProjectionMode) {
+        self.projection = mode;
+    }
+
+    // Left, right, bottom, top, near, far, extracted from the view-projection
+    // matrix (Gribb/Hartmann) for wgpu's 0..1 clip depth
+    pub fn build_frustum_planes(&self) -> [Plane; 6] {
+        let m = self.build_view_projection_matrix();
+        let (r0, r1, r2, r3) = (m.row(0), m.row(1), m.row(2), m.row(3));
+        [
+            Plane::from_coefficients(r3 + r0),
+            Plane::from_coefficients(r3 - r0),
+            Plane::from_coefficients(r3 + r1),
+            Plane::from_coefficients(r3 - r1),
+            Plane::from_coefficients(r2),
+            Plane::from_coefficients(r3 - r2),
+        ]
+    }
+
+    // Minimized windows report a zero size, keep the last aspect until they come back
     pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
         self.aspect = width as f32 / height as f32;
     }
 
+    pub fn set_following(&mut self, following: bool) {
+        self.following = following;
+    }
+
+    // Place the camera at target + offset, looking at the target; a (nearly) vertical
+    // offset looks down or up at MAX_PITCH instead
+    pub fn follow(&mut self, target: Vec3, offset: Vec3) {
+        self.position = target + offset;
+        self.eye = self.position;
+        self.target = target;
+        let direction = (target - self.position).normalize_or_zero();
+        if direction != Vec3::ZERO {
+            self.yaw = direction.x.atan2(direction.z);
+            self.pitch = direction
+                .y
+                .clamp(-1.0, 1.0)
+                .asin()
+                .clamp(-MAX_PITCH, MAX_PITCH);
+        }
+    }
+
+    // Turn by the deltas (radians) and sit `radius` back from the target along the new view
+    // direction, so the camera circles the target while always looking at it
+    pub fn orbit(&mut self, target: Vec3, radius: f32, yaw_delta: f32, pitch_delta: f32) {
+        self.yaw += yaw_delta;
+        self.pitch = (self.pitch + pitch_delta).clamp(-MAX_PITCH, MAX_PITCH);
+        self.position = target - self.forward() * radius;
+        self.eye = self.position;
+        self.target = target;
+    }
+
     pub fn process_mouse(&mut self, dx: f64, dy: f64) {
-        let sensitivity = 0.005;
-        self.yaw += (dx as f32) * sensitivity;
-        self.pitch -= (dy as f32) * sensitivity;
-        self.pitch = self.pitch.clamp(
-            -std::f32::consts::FRAC_PI_2 + 0.01,
-            std::f32::consts::FRAC_PI_2 - 0.01,
+        if self.following {
+            return;
+        }
+        let (yaw, pitch) = scale_mouse_delta(dx, dy, self.mouse_sensitivity);
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follow_looks_at_the_target() {
+        let mut camera = Camera::new(800, 600);
+        let target = Vec3::new(3.0, 0.0, -2.0);
+        let offset = Vec3::new(0.0, 5.0, -10.0);
+        camera.follow(target, offset);
+
+        assert_eq!(camera.position, target + offset);
+        assert!(camera.forward().abs_diff_eq(-offset.normalize(), 1e-5));
+    }
+
+    #[test]
+    fn follow_from_straight_above_clamps_pitch() {
+        let mut camera = Camera::new(800, 600);
+        camera.follow(Vec3::ZERO, Vec3::new(0.0, 10.0, 0.0));
+
+        assert_eq!(camera.pitch, -MAX_PITCH);
+        assert!(camera.forward().y < 0.0 && camera.forward().y > -1.0);
+        assert!(camera.build_view_projection_matrix().is_finite());
+    }
+
+    #[test]
+    fn half_an_orbit_moves_to_the_opposite_side() {
+        let mut camera = Camera::new(800, 600);
+        let target = Vec3::new(1.0, 2.0, 3.0);
+        camera.orbit(target, 10.0, 0.0, 0.0);
+        let start = camera.position;
+        assert!((start.distance(target) - 10.0).abs() < 1e-4);
+
+        camera.orbit(target, 10.0, std::f32::consts::PI, 0.0);
+        // Mirrored through the target, still 10 away and looking straight at it
+        assert!(camera.position.abs_diff_eq(2.0 * target - start, 1e-4));
+        assert!(camera
+            .forward()
+            .abs_diff_eq((target - camera.position).normalize(), 1e-5));
+        assert_eq!(camera.target, target);
+    }
+
+    // Clip space to normalized device coordinates
+    fn project(camera: &Camera, point: Vec3) -> Vec3 {
+        let clip = camera.build_view_projection_matrix() * point.extend(1.0);
+        clip.truncate() / clip.w
+    }
+
+    #[test]
+    fn near_plane_points_map_under_both_projections() {
+        // Looking down +x from (0, 8, 8), so screen right is +z and screen up is +y
+        let mut camera = Camera::new(800, 400);
+        let near = camera.position + camera.forward() * camera.znear;
+        assert!(project(&camera, near).abs_diff_eq(Vec3::ZERO, 1e-4));
+        let half_height = camera.znear * (camera.fovy.to_radians() / 2.0).tan();
+        let top = near + Vec3::Y * half_height;
+        assert!(project(&camera, top).abs_diff_eq(Vec3::new(0.0, 1.0, 0.0), 1e-4));
+
+        camera.set_projection_mode(ProjectionMode::Orthographic { height: 10.0 });
+        assert_eq!(
+            camera.projection,
+            ProjectionMode::Orthographic { height: 10.0 }
         );
+        assert!(project(&camera, near).abs_diff_eq(Vec3::ZERO, 1e-4));
+        let corner = near + Vec3::new(0.0, 5.0, 10.0);
+        assert!(project(&camera, corner).abs_diff_eq(Vec3::new(1.0, 1.0, 0.0), 1e-4));
+    }
+
+    #[test]
+    fn frustum_keeps_visible_and_straddling_boxes() {
+        // Looking at the origin down -z from z = 10
+        let mut camera = Camera::new(800, 600);
+        camera.follow(Vec3::ZERO, Vec3::new(0.0, 0.0, 10.0));
+        let planes = camera.build_frustum_planes();
+        let unit = Vec3::splat(0.5);
+
+        assert!(aabb_in_frustum(&planes, -unit, unit));
+        // Behind the camera
+        let behind = Vec3::new(0.0, 0.0, 20.0);
+        assert!(!aabb_in_frustum(&planes, behind - unit, behind + unit));
+        // Straddling the near plane, and the left plane far off to the side
+        let near = Vec3::new(0.0, 0.0, 10.0 - camera.znear);
+        assert!(aabb_in_frustum(&planes, near - unit, near + unit));
+        let left = Vec3::new(-100.0, 0.0, 0.0);
+        assert!(!aabb_in_frustum(&planes, left - unit, left + unit));
+        assert!(aabb_in_frustum(
+            &planes,
+            left - unit,
+            Vec3::new(0.0, 0.5, 0.5)
+        ));
+    }
+
+    #[test]
+    fn resize_updates_the_aspect_and_ignores_zero_sizes() {
+        let mut camera = Camera::new(800, 600);
+        camera.resize(1920, 1080);
+        assert_eq!(camera.aspect, 1920.0 / 1080.0);
+
+        // Minimized: the last usable aspect is kept
+        camera.resize(1920, 0);
+        camera.resize(0, 1080);
+        assert_eq!(camera.aspect, 1920.0 / 1080.0);
+        assert!(camera.build_view_projection_matrix().is_finite());
+    }
+
+    #[test]
+    fn mouse_delta_scales_by_sensitivity() {
+        assert_eq!(scale_mouse_delta(8.0, 4.0, 0.25), (2.0, -1.0));
+        assert_eq!(scale_mouse_delta(-10.0, -4.0, 0.5), (-5.0, 2.0));
+
+        let mut camera = Camera::new(800, 600);
+        camera.mouse_sensitivity = 0.01;
+        let (yaw, pitch) = (camera.yaw, camera.pitch);
+        camera.process_mouse(10.0, -4.0);
+        assert!((camera.yaw - yaw - 0.1).abs() < 1e-6);
+        assert!((camera.pitch - pitch - 0.04).abs() < 1e-6);
+
+        // Ignored while following
+        camera.set_following(true);
+        camera.process_mouse(100.0, 100.0);
+        assert!((camera.yaw - yaw - 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn mouse_look_clamps_pitch_at_the_poles() {
+        let mut camera = Camera::new(800, 600);
+        camera.process_mouse(0.0, -1e6);
+        assert_eq!(camera.pitch, MAX_PITCH);
+        camera.process_mouse(0.0, 1e6);
+        assert_eq!(camera.pitch, -MAX_PITCH);
+        assert!(camera.build_view_projection_matrix().is_finite());
     }
 }
@@ -0,0 +1,76 @@
+use crate::graphics::vertex::Vertex;
+use crate::physics::object::AABB;
+
+pub const AABB_DEBUG_COLOR: [f32; 3] = [1.0, 1.0, 0.0];
+
+// Line-list vertices for the 12 edges of an AABB (24 vertices)
+pub fn create_aabb_line_vertices(aabb: &AABB, color: [f32; 3]) -> Vec<Vertex> {
+    let (min, max) = (&aabb.min, &aabb.max);
+    let corners = [
+        [min.x, min.y, min.z],
+        [max.x, min.y, min.z],
+        [max.x, max.y, min.z],
+        [min.x, max.y, min.z],
+        [min.x, min.y, max.z],
+        [max.x, min.y, max.z],
+        [max.x, max.y, max.z],
+        [min.x, max.y, max.z],
+    ];
+    const EDGES: [(usize, usize); 12] = [
+        // Bottom face
+        (0, 1),
+        (1, 5),
+        (5, 4),
+        (4, 0),
+        // Top face
+        (3, 2),
+        (2, 6),
+        (6, 7),
+        (7, 3),
+        // Verticals
+        (0, 3),
+        (1, 2),
+        (5, 6),
+        (4, 7),
+    ];
+
+    EDGES
+        .iter()
+        .flat_map(|&(a, b)| [corners[a], corners[b]])
+        .map(|position| Vertex {
+            position,
+            color,
+            normal: [0.0, 1.0, 0.0],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::object::Vec3;
+
+    #[test]
+    fn aabb_lines_are_the_twelve_box_edges() {
+        let aabb = AABB::new(Vec3::new(-1.0, 0.0, 2.0), Vec3::new(3.0, 0.5, 4.0));
+        let vertices = create_aabb_line_vertices(&aabb, AABB_DEBUG_COLOR);
+        assert_eq!(vertices.len(), 24);
+
+        let is_corner = |p: [f32; 3]| {
+            [aabb.min.x, aabb.max.x].contains(&p[0])
+                && [aabb.min.y, aabb.max.y].contains(&p[1])
+                && [aabb.min.z, aabb.max.z].contains(&p[2])
+        };
+        let mut edges = Vec::new();
+        for segment in vertices.chunks_exact(2) {
+            let (a, b) = (segment[0].position, segment[1].position);
+            assert!(is_corner(a) && is_corner(b));
+            // An edge joins corners that differ along exactly one axis
+            assert_eq!((0..3).filter(|&i| a[i] != b[i]).count(), 1);
+            let edge = if a < b { (a, b) } else { (b, a) };
+            assert!(!edges.contains(&edge), "duplicate edge {:?}", edge);
+            edges.push(edge);
+        }
+        assert!(vertices.iter().all(|v| v.color == AABB_DEBUG_COLOR));
+    }
+}
@@ -1,5 +1,8 @@
 use crate::graphics::vertex::Vertex;
 
+// Grid lines are unlit, they just need a well-formed normal
+const GRID_NORMAL: [f32; 3] = [0.0, 1.0, 0.0];
+
 pub fn create_grid_vertices(
     width: f32,
     height: f32,
@@ -30,10 +33,12 @@ pub fn create_grid_vertices(
             vertices.push(Vertex {
                 position: [x, -height / 2.0, z],
                 color,
+                normal: GRID_NORMAL,
             });
             vertices.push(Vertex {
                 position: [x, height / 2.0, z],
                 color,
+                normal: GRID_NORMAL,
             });
 
             indices.push(index);
@@ -48,10 +53,12 @@ pub fn create_grid_vertices(
             vertices.push(Vertex {
                 position: [x, y, -depth / 2.0],
                 color,
+                normal: GRID_NORMAL,
             });
             vertices.push(Vertex {
                 position: [x, y, depth / 2.0],
                 color,
+                normal: GRID_NORMAL,
             });
 
             indices.push(index);
@@ -72,10 +79,12 @@ pub fn create_grid_vertices(
             vertices.push(Vertex {
                 position: [-width / 2.0, y, z],
                 color,
+                normal: GRID_NORMAL,
             });
             vertices.push(Vertex {
                 position: [width / 2.0, y, z],
                 color,
+                normal: GRID_NORMAL,
             });
 
             indices.push(index);
@@ -90,10 +99,12 @@ pub fn create_grid_vertices(
             vertices.push(Vertex {
                 position: [x, y, -depth / 2.0],
                 color,
+                normal: GRID_NORMAL,
             });
             vertices.push(Vertex {
                 position: [x, y, depth / 2.0],
                 color,
+                normal: GRID_NORMAL,
             });
 
             indices.push(index);
@@ -114,10 +125,12 @@ pub fn create_grid_vertices(
             vertices.push(Vertex {
                 position: [-width / 2.0, y, z],
                 color,
+                normal: GRID_NORMAL,
             });
             vertices.push(Vertex {
                 position: [width / 2.0, y, z],
                 color,
+                normal: GRID_NORMAL,
             });
 
             indices.push(index);
@@ -132,10 +145,12 @@ pub fn create_grid_vertices(
             vertices.push(Vertex {
                 position: [x, -height / 2.0, z],
                 color,
+                normal: GRID_NORMAL,
             });
             vertices.push(Vertex {
                 position: [x, height / 2.0, z],
                 color,
+                normal: GRID_NORMAL,
             });
 
             indices.push(index);
@@ -146,3 +161,84 @@ pub fn create_grid_vertices(
 
     (vertices, indices)
 }
+
+// Only the XZ floor plane of the 3D grid, lines every `spacing` along both axes at height `y`
+pub fn create_floor_grid(width: f32, depth: f32, spacing: f32, y: f32) -> (Vec<Vertex>, Vec<u16>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut index = 0u16;
+
+    let grid_color = [0.3, 0.3, 0.3];
+    let accent_color = [0.5, 0.5, 0.5];
+
+    let lines_x = ((width / spacing) as i32) + 1;
+    let lines_z = ((depth / spacing) as i32) + 1;
+
+    // Lines along X at different Z positions
+    for i in 0..lines_z {
+        let z = -depth / 2.0 + i as f32 * spacing;
+        let color = if i % 5 == 0 { accent_color } else { grid_color };
+
+        vertices.push(Vertex {
+            position: [-width / 2.0, y, z],
+            color,
+            normal: GRID_NORMAL,
+        });
+        vertices.push(Vertex {
+            position: [width / 2.0, y, z],
+            color,
+            normal: GRID_NORMAL,
+        });
+
+        indices.push(index);
+        indices.push(index + 1);
+        index += 2;
+    }
+
+    // Lines along Z at different X positions
+    for i in 0..lines_x {
+        let x = -width / 2.0 + i as f32 * spacing;
+        let color = if i % 5 == 0 { accent_color } else { grid_color };
+
+        vertices.push(Vertex {
+            position: [x, y, -depth / 2.0],
+            color,
+            normal: GRID_NORMAL,
+        });
+        vertices.push(Vertex {
+            position: [x, y, depth / 2.0],
+            color,
+            normal: GRID_NORMAL,
+        });
+
+        indices.push(index);
+        indices.push(index + 1);
+        index += 2;
+    }
+
+    (vertices, indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floor_grid_is_flat_and_spans_the_extents() {
+        // 11 lines along x and 7 along z, each a pair of vertices
+        let (vertices, indices) = create_floor_grid(20.0, 12.0, 2.0, -3.0);
+        assert_eq!(vertices.len(), 2 * (7 + 11));
+        assert_eq!(indices, (0..vertices.len() as u16).collect::<Vec<_>>());
+
+        for vertex in &vertices {
+            let [x, y, z] = vertex.position;
+            assert_eq!(y, -3.0);
+            assert!((-10.0..=10.0).contains(&x) && (-6.0..=6.0).contains(&z));
+        }
+        // Every line runs edge to edge
+        for line in vertices.chunks_exact(2) {
+            let (a, b) = (line[0].position, line[1].position);
+            assert!((a[0] == -10.0 && b[0] == 10.0) || (a[2] == -6.0 && b[2] == 6.0));
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::graphics::vertex::Vertex;
+
+// Exponential moving average of frame times, so the displayed FPS doesn't flicker
+pub struct FpsCounter {
+    pub smoothing: f32, // Weight of the newest sample, in (0, 1]
+    smoothed_frame_time: Option<f32>,
+}
+
+impl FpsCounter {
+    pub fn new(smoothing: f32) -> Self {
+        Self {
+            smoothing: smoothing.clamp(f32::EPSILON, 1.0),
+            smoothed_frame_time: None,
+        }
+    }
+
+    pub fn update(&mut self, frame_time: f32) -> f32 {
+        let smoothed = match self.smoothed_frame_time {
+            Some(prev) => prev + self.smoothing * (frame_time - prev),
+            None => frame_time,
+        };
+        self.smoothed_frame_time = Some(smoothed);
+        self.fps()
+    }
+
+    pub fn fps(&self) -> f32 {
+        match self.smoothed_frame_time {
+            Some(t) if t > 0.0 => 1.0 / t,
+            _ => 0.0,
+        }
+    }
+}
+
+// 3x5 bitmap font, one row per byte with the leftmost pixel in bit 2
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ' ' => [0b000; GLYPH_HEIGHT],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010], // '?'
+    }
+}
+
+// Triangle-list quads in normalized device coordinates, text starts at the top-left corner
+pub fn create_text_vertices(
+    lines: &[String],
+    screen_width: u32,
+    screen_height: u32,
+    pixel_size: f32,
+    color: [f32; 3],
+) -> Vec<Vertex> {
+    let mut vertices = Vec::new();
+    if screen_width == 0 || screen_height == 0 {
+        return vertices;
+    }
+
+    let margin = pixel_size * 2.0;
+    let to_ndc = |x: f32, y: f32| {
+        [
+            x / screen_width as f32 * 2.0 - 1.0,
+            1.0 - y / screen_height as f32 * 2.0,
+            0.0,
+        ]
+    };
+
+    for (line_index, line) in lines.iter().enumerate() {
+        let top = margin + line_index as f32 * (GLYPH_HEIGHT + 2) as f32 * pixel_size;
+        for (char_index, c) in line.chars().enumerate() {
+            let left = margin + char_index as f32 * (GLYPH_WIDTH + 1) as f32 * pixel_size;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - col)) == 0 {
+                        continue;
+                    }
+                    let x0 = left + col as f32 * pixel_size;
+                    let y0 = top + row as f32 * pixel_size;
+                    let (x1, y1) = (x0 + pixel_size, y0 + pixel_size);
+                    for (x, y) in [(x0, y0), (x1, y0), (x1, y1), (x1, y1), (x0, y1), (x0, y0)] {
+                        vertices.push(Vertex {
+                            position: to_ndc(x, y),
+                            color,
+                            normal: [0.0, 0.0, 1.0],
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fps_averages_fixed_frame_times() {
+        let mut counter = FpsCounter::new(0.5);
+        assert_eq!(counter.fps(), 0.0);
+        assert_eq!(counter.update(0.02), 50.0);
+        assert_eq!(counter.update(0.02), 50.0);
+
+        // Half of each new sample: 0.02 -> 0.03 -> 0.035
+        assert!((counter.update(0.04) - 1.0 / 0.03).abs() < 1e-3);
+        assert!((counter.update(0.04) - 1.0 / 0.035).abs() < 1e-3);
+    }
+
+    #[test]
+    fn old_frame_times_fade_out() {
+        // A hitch is forgotten once enough normal frames have gone by
+        let mut counter = FpsCounter::new(0.1);
+        counter.update(1.0 / 60.0);
+        assert!(counter.update(1.0) < 10.0);
+        for _ in 0..200 {
+            counter.update(1.0 / 60.0);
+        }
+        assert!((counter.fps() - 60.0).abs() < 0.01);
+
+        // Smoothing 1 keeps only the newest frame time
+        let mut counter = FpsCounter::new(1.0);
+        counter.update(0.1);
+        assert_eq!(counter.update(0.5), 2.0);
+    }
+}
@@ -0,0 +1,48 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+// Matches `LightUniform` in the fragment shader (16 bytes)
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct LightUniform {
+    pub direction: [f32; 3],
+    pub ambient: f32,
+}
+
+impl LightUniform {
+    pub fn new(direction: Vec3, ambient: f32) -> Self {
+        Self {
+            direction: direction.normalize_or_zero().to_array(),
+            ambient,
+        }
+    }
+}
+
+impl Default for LightUniform {
+    fn default() -> Self {
+        // Light coming down and slightly from the front-left
+        Self::new(Vec3::new(0.3, -1.0, -0.5), 0.25)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::shader::FRAGMENT_SHADER;
+
+    #[test]
+    fn uniform_layout_matches_the_shader() {
+        // vec3 direction then the f32 ambient packed into its padding, group 0 binding 1
+        assert_eq!(std::mem::size_of::<LightUniform>(), 16);
+        assert_eq!(std::mem::offset_of!(LightUniform, direction), 0);
+        assert_eq!(std::mem::offset_of!(LightUniform, ambient), 12);
+        assert!(FRAGMENT_SHADER.contains("direction: vec3<f32>,\n    ambient: f32,"));
+        assert!(
+            FRAGMENT_SHADER.contains("@group(0) @binding(1)\nvar<uniform> light: LightUniform;")
+        );
+
+        let light = LightUniform::new(Vec3::new(0.0, -3.0, 4.0), 0.5);
+        assert_eq!(light.direction, [0.0, -0.6, 0.8]);
+        assert_eq!(light.ambient, 0.5);
+    }
+}
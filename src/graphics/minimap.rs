@@ -0,0 +1,111 @@
+use crate::game::game_engine::{GameObject, GameObjectType};
+use crate::graphics::vertex::InstanceRaw;
+use glam::{Mat4, Vec3};
+
+pub const MINIMAP_BACKGROUND: [f32; 4] = [0.15, 0.15, 0.2, 1.0];
+
+// Top-down view of the x/z plane, +x to the right and +z down, in the top-right corner
+#[derive(Debug, Clone)]
+pub struct MinimapConfig {
+    pub half_width: f32,      // World extent along x
+    pub half_depth: f32,      // World extent along z
+    pub height_fraction: f32, // Of the window height, the width follows the extents' aspect
+    pub margin: u32,          // Pixels from the window edges
+}
+
+impl MinimapConfig {
+    pub fn new(half_width: f32, half_depth: f32) -> Self {
+        MinimapConfig {
+            half_width,
+            half_depth,
+            height_fraction: 0.25,
+            margin: 10,
+        }
+    }
+
+    // (x, y, width, height) in pixels, y measured down from the top of the window
+    pub fn viewport(&self, window_width: u32, window_height: u32) -> (f32, f32, f32, f32) {
+        let height = window_height as f32 * self.height_fraction;
+        let width = height * self.half_width / self.half_depth;
+        let x = window_width as f32 - width - self.margin as f32;
+        (x.max(0.0), self.margin as f32, width, height)
+    }
+
+    // Pixel position of a world point inside `viewport`, y is ignored
+    pub fn world_to_minimap(&self, position: Vec3, viewport: (f32, f32, f32, f32)) -> (f32, f32) {
+        let (x, y, width, height) = viewport;
+        let u = (position.x + self.half_width) / (2.0 * self.half_width);
+        let v = (position.z + self.half_depth) / (2.0 * self.half_depth);
+        (x + u * width, y + v * height)
+    }
+
+    // Orthographic camera above the origin looking down -y, framing exactly the extents
+    pub fn view_proj(&self) -> Mat4 {
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 100.0, 0.0), Vec3::ZERO, Vec3::NEG_Z);
+        let proj = Mat4::orthographic_rh(
+            -self.half_width,
+            self.half_width,
+            -self.half_depth,
+            self.half_depth,
+            0.1,
+            200.0,
+        );
+        proj * view
+    }
+}
+
+// A background plate and every non-wall object flattened onto it; walls are left out
+// because the floor and ceiling would cover the whole map from above
+pub fn minimap_instances(config: &MinimapConfig, game_objects: &[GameObject]) -> Vec<InstanceRaw> {
+    let background = Mat4::from_scale_rotation_translation(
+        Vec3::new(2.0 * config.half_width, 0.1, 2.0 * config.half_depth),
+        glam::Quat::IDENTITY,
+        Vec3::new(0.0, -1.0, 0.0),
+    );
+    let bodies = game_objects
+        .iter()
+        .filter(|obj| obj.body.active && obj.object_type != GameObjectType::Wall)
+        .map(|obj| {
+            let size = obj.body.aabb.get_size();
+            let model = Mat4::from_scale_rotation_translation(
+                Vec3::new(size.x, 0.1, size.z),
+                glam::Quat::IDENTITY,
+                Vec3::new(obj.body.position.x, 0.0, obj.body.position.z),
+            );
+            // Opaque on the map even when the object itself is translucent
+            let mut color = obj.color;
+            color[3] = 1.0;
+            InstanceRaw::new(model, color)
+        });
+
+    std::iter::once(InstanceRaw::new(background, MINIMAP_BACKGROUND))
+        .chain(bodies)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arena_corners_and_centre_map_onto_the_viewport() {
+        let config = MinimapConfig::new(30.0, 20.0);
+        let viewport = config.viewport(800, 600);
+        // 150 tall, 225 wide at the 30:20 aspect, 10 in from the top-right corner
+        assert_eq!(viewport, (565.0, 10.0, 225.0, 150.0));
+
+        let at = |x, z| config.world_to_minimap(Vec3::new(x, 7.0, z), viewport);
+        assert_eq!(at(-30.0, -20.0), (565.0, 10.0));
+        assert_eq!(at(30.0, 20.0), (790.0, 160.0));
+        assert_eq!(at(30.0, -20.0), (790.0, 10.0));
+        assert_eq!(at(0.0, 0.0), (677.5, 85.0));
+    }
+
+    #[test]
+    fn view_proj_frames_the_extents() {
+        let config = MinimapConfig::new(30.0, 20.0);
+        let corner = config.view_proj() * glam::Vec4::new(30.0, 0.0, 20.0, 1.0);
+        // +x to the right and +z down, matching world_to_minimap
+        assert!((corner.x - 1.0).abs() < 1e-5 && (corner.y + 1.0).abs() < 1e-5);
+    }
+}
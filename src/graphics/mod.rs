@@ -1,10 +1,15 @@
 pub mod camera;
+pub mod debug;
 pub mod grid;
+pub mod hud;
+pub mod light;
+pub mod minimap;
 pub mod renderer;
 pub mod shader;
+pub mod trail;
 pub mod vertex;
 
 pub use camera::Camera;
-pub use grid::create_grid_vertices;
+pub use grid::{create_floor_grid, create_grid_vertices};
 pub use renderer::Renderer;
-pub use vertex::{CUBE_INDICES, CUBE_VERTICES};
+pub use vertex::{create_box_mesh, CUBE_INDICES, CUBE_VERTICES};
@@ -1,59 +1,222 @@
+use crate::graphics::light::LightUniform;
+use log::warn;
 use std::sync::Arc;
 use wgpu::{Device, PresentMode, Queue, Surface, SurfaceConfiguration, TextureUsages};
 use winit::window::Window;
 
+// Color format of the offscreen target used by headless renderers
+pub const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+// Shared by the depth texture and every depth-tested pipeline, which must agree
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// MSAA sample counts accepted by `Renderer::new`, 1 disables multisampling
+pub const SUPPORTED_SAMPLE_COUNTS: [u32; 3] = [1, 4, 8];
+
+// Downsampled frame for pixel-based RL agents, rows are tightly packed
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub channels: u32, // 1 for grayscale, 3 for RGB
+    pub data: Vec<u8>,
+}
+
+// Variants of the instanced cube pipeline
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CubePipeline {
+    Fill,
+    Line,        // Needs POLYGON_MODE_LINE
+    Transparent, // Alpha blended, depth tested without writing depth
+}
+
+// Wireframe toggle. PolygonMode::Line needs the POLYGON_MODE_LINE device feature, without
+// it requests to enable wireframe are ignored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WireframeMode {
+    supported: bool,
+    enabled: bool,
+}
+
+impl WireframeMode {
+    fn new(supported: bool) -> Self {
+        Self {
+            supported,
+            enabled: false,
+        }
+    }
+
+    fn set(&mut self, enabled: bool) {
+        self.enabled = enabled && self.supported;
+    }
+
+    fn polygon_mode(self) -> wgpu::PolygonMode {
+        if self.enabled {
+            wgpu::PolygonMode::Line
+        } else {
+            wgpu::PolygonMode::Fill
+        }
+    }
+}
+
+// Vertex data rewritten every frame with queue.write_buffer; the buffer is only
+// reallocated, doubling, when the data outgrows it
+struct GrowableBuffer {
+    label: &'static str,
+    buffer: wgpu::Buffer,
+    capacity: wgpu::BufferAddress, // Bytes
+}
+
+impl GrowableBuffer {
+    const INITIAL_CAPACITY: wgpu::BufferAddress = 4096;
+
+    fn new(device: &wgpu::Device, label: &'static str) -> Self {
+        Self {
+            label,
+            buffer: Self::create(device, label, Self::INITIAL_CAPACITY),
+            capacity: Self::INITIAL_CAPACITY,
+        }
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        label: &'static str,
+        capacity: wgpu::BufferAddress,
+    ) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: capacity,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    // `data` must be a multiple of 4 bytes long, as every vertex and instance type is
+    fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[u8]) {
+        let size = data.len() as wgpu::BufferAddress;
+        if size > self.capacity {
+            self.capacity = size.next_power_of_two();
+            self.buffer = Self::create(device, self.label, self.capacity);
+        }
+        queue.write_buffer(&self.buffer, 0, data);
+    }
+}
+
+// What differs between the renderer's pipelines, see Renderer::create_pipeline
+struct PipelineSpec<'a> {
+    label: &'a str,
+    instanced: bool, // vs_instanced with the instance buffer, else vs_main
+    fs_entry: &'a str,
+    target: wgpu::ColorTargetState,
+    topology: wgpu::PrimitiveTopology,
+    polygon_mode: wgpu::PolygonMode,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ObservationConfig {
+    pub width: u32,
+    pub height: u32,
+    pub grayscale: bool,
+}
+
+impl Default for ObservationConfig {
+    fn default() -> Self {
+        Self {
+            width: 84,
+            height: 84,
+            grayscale: true,
+        }
+    }
+}
+
 pub struct Renderer {
-    window: Arc<Window>,
-    surface: Surface<'static>,
+    // Windowed renderers draw to the surface, headless ones to `offscreen`
+    window: Option<Arc<Window>>,
+    surface: Option<Surface<'static>>,
+    offscreen: Option<wgpu::Texture>,
     device: Device,
     queue: Queue,
     config: SurfaceConfiguration,
     size: winit::dpi::PhysicalSize<u32>,
     render_pipeline: wgpu::RenderPipeline,
-    wireframe_pipeline: wgpu::RenderPipeline,
+    transparent_pipeline: wgpu::RenderPipeline,
+    // Both need POLYGON_MODE_LINE and are None when the adapter lacks it
+    wireframe_pipeline: Option<wgpu::RenderPipeline>,
+    line_mode_pipeline: Option<wgpu::RenderPipeline>,
+    wireframe: WireframeMode,
+    debug_aabb: bool,
+    clear_color: wgpu::Color, // Background behind the grid, alpha 0 for a transparent frame
     line_pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     num_indices: u32,
+    instance_buffer: wgpu::Buffer,
+    instance_capacity: usize,
+    instance_count: u32,
     grid_vertex_buffer: wgpu::Buffer,
     grid_index_buffer: wgpu::Buffer,
     grid_num_indices: u32,
+    floor_grid: bool, // Only the floor plane of the grid instead of the full lattice
     camera_bind_group: wgpu::BindGroup,
     camera_buffer: wgpu::Buffer,
+    hud_pipeline: wgpu::RenderPipeline,
+    hud_bind_group: wgpu::BindGroup, // Identity view-projection, vertices are already in NDC
+    hud_lines: Vec<String>,
+    trail_vertices: Vec<crate::graphics::vertex::Vertex>,
+    // Per-frame vertex data for the trail, AABB debug, minimap and HUD passes
+    trail_buffer: GrowableBuffer,
+    aabb_buffer: GrowableBuffer,
+    minimap_instance_buffer: GrowableBuffer,
+    hud_buffer: GrowableBuffer,
+    minimap: Option<crate::graphics::minimap::MinimapConfig>, // None hides the minimap
+    minimap_camera_buffer: wgpu::Buffer,
+    minimap_bind_group: wgpu::BindGroup,
+    observation: ObservationConfig,
+    observation_camera: crate::graphics::Camera,
+    light_buffer: wgpu::Buffer,
+    light: LightUniform,
     depth_texture: wgpu::TextureView,
+    sample_count: u32,
+    msaa_texture: Option<wgpu::TextureView>, // Resolved into the target at the end of a frame
 }
 
 impl Renderer {
-    pub fn window(&self) -> &Window {
-        &self.window
+    pub fn window(&self) -> Option<&Window> {
+        self.window.as_deref()
     }
 
-    pub async fn new(window: Arc<Window>) -> Self {
-        let size = window.inner_size();
-
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+    fn create_instance() -> wgpu::Instance {
+        wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             dx12_shader_compiler: Default::default(),
             flags: wgpu::InstanceFlags::empty(),
             gles_minor_version: wgpu::Gles3MinorVersion::Automatic,
-        });
-
-        let surface = instance.create_surface(window.clone()).unwrap();
+        })
+    }
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
+    // Returns the device, its queue and whether line polygon mode is available
+    async fn request_device(adapter: &wgpu::Adapter) -> (Device, Queue, bool) {
+        // Line polygon mode is optional, wireframe rendering is disabled without it
+        let supports_line_mode = adapter
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE);
+        let mut required_features = wgpu::Features::PUSH_CONSTANTS;
+        if supports_line_mode {
+            required_features |= wgpu::Features::POLYGON_MODE_LINE;
+        }
+        // Needed for sample counts other than 1 and 4
+        if adapter
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        {
+            required_features |= wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        }
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::PUSH_CONSTANTS
-                        | wgpu::Features::POLYGON_MODE_LINE,
+                    required_features,
                     required_limits: wgpu::Limits {
                         max_push_constant_size: 128,
                         ..wgpu::Limits::default()
@@ -65,6 +228,63 @@ impl Renderer {
             .await
             .unwrap();
 
+        (device, queue, supports_line_mode)
+    }
+
+    // Highest supported sample count not above `requested`, for both the color and depth targets
+    fn validate_sample_count(
+        adapter: &wgpu::Adapter,
+        device: &Device,
+        format: wgpu::TextureFormat,
+        requested: u32,
+    ) -> u32 {
+        if !SUPPORTED_SAMPLE_COUNTS.contains(&requested) {
+            warn!(
+                "Unsupported MSAA sample count {}, expected one of {:?}",
+                requested, SUPPORTED_SAMPLE_COUNTS
+            );
+        }
+        let adapter_specific = device
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        let supported = |count: u32| {
+            (count <= 4 || adapter_specific)
+                && [format, DEPTH_FORMAT].iter().all(|f| {
+                    adapter
+                        .get_texture_format_features(*f)
+                        .flags
+                        .sample_count_supported(count)
+                })
+        };
+
+        let count = pick_sample_count(requested, supported);
+        if count != requested && SUPPORTED_SAMPLE_COUNTS.contains(&requested) {
+            warn!(
+                "MSAA x{} unsupported on this GPU, using x{}",
+                requested, count
+            );
+        }
+        count
+    }
+
+    // `sample_count` is 1, 4 or 8 and falls back to the highest count the GPU supports
+    pub async fn new(window: Arc<Window>, sample_count: u32) -> Self {
+        let size = window.inner_size();
+        let instance = Self::create_instance();
+
+        let surface = instance.create_surface(window.clone()).unwrap();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .unwrap();
+
+        let (device, queue, supports_line_mode) = Self::request_device(&adapter).await;
+
         let surface_caps = surface.get_capabilities(&adapter);
         let surface_format = surface_caps
             .formats
@@ -84,6 +304,75 @@ impl Renderer {
             desired_maximum_frame_latency: 2,
         };
         surface.configure(&device, &config);
+        let sample_count =
+            Self::validate_sample_count(&adapter, &device, surface_format, sample_count);
+
+        Self::from_device(
+            device,
+            queue,
+            config,
+            supports_line_mode,
+            sample_count,
+            Some((window, surface)),
+            None,
+        )
+    }
+
+    // Renders into an offscreen texture instead of a window, see `capture_frame`
+    pub async fn new_headless(width: u32, height: u32) -> Self {
+        Self::try_new_headless(width, height)
+            .await
+            .expect("no GPU adapter for a headless renderer")
+    }
+
+    // Like `new_headless`, but None when the machine has no adapter (e.g. CI without a GPU)
+    pub async fn try_new_headless(width: u32, height: u32) -> Option<Self> {
+        let instance = Self::create_instance();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await?;
+
+        let (device, queue, supports_line_mode) = Self::request_device(&adapter).await;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            format: HEADLESS_FORMAT,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: PresentMode::AutoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let offscreen = Self::create_offscreen_texture(&device, &config);
+
+        Some(Self::from_device(
+            device,
+            queue,
+            config,
+            supports_line_mode,
+            1, // Observations are downsampled anyway
+            None,
+            Some(offscreen),
+        ))
+    }
+
+    fn from_device(
+        device: Device,
+        queue: Queue,
+        config: SurfaceConfiguration,
+        supports_line_mode: bool,
+        sample_count: u32,
+        windowed: Option<(Arc<Window>, Surface<'static>)>,
+        offscreen: Option<wgpu::Texture>,
+    ) -> Self {
+        let size = winit::dpi::PhysicalSize::new(config.width, config.height);
+        let (window, surface) = windowed.unzip();
 
         use crate::graphics::shader::{FRAGMENT_SHADER, VERTEX_SHADER};
 
@@ -104,30 +393,100 @@ impl Renderer {
             mapped_at_creation: false,
         });
 
+        let light = LightUniform::default();
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Light Buffer"),
+            contents: bytemuck::cast_slice(&[light]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(
+                                std::mem::size_of::<LightUniform>() as u64,
+                            ),
+                        },
+                        count: None,
+                    },
+                ],
                 label: Some("camera_bind_group_layout"),
             });
 
         let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                },
+            ],
             label: Some("camera_bind_group"),
         });
 
+        let hud_camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("HUD Camera Buffer"),
+            contents: bytemuck::cast_slice(&[crate::graphics::camera::CameraUniform::new()]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let hud_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: hud_camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("hud_bind_group"),
+        });
+
+        let minimap_camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Minimap Camera Buffer"),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            size: std::mem::size_of::<crate::graphics::camera::CameraUniform>() as u64,
+            mapped_at_creation: false,
+        });
+
+        let minimap_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &camera_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: minimap_camera_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("minimap_bind_group"),
+        });
+
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
@@ -138,148 +497,119 @@ impl Renderer {
                 }],
             });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &vs_module,
-                entry_point: "vs_main",
-                buffers: &[crate::graphics::vertex::Vertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &fs_module,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState {
-                        color: wgpu::BlendComponent {
-                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                            operation: wgpu::BlendOperation::Add,
-                        },
-                        alpha: wgpu::BlendComponent::OVER,
-                    }),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Cw, // was ccw
-                // cull_mode: Some(wgpu::Face::Back),
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
+        let render_pipeline = Self::create_solid_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &vs_module,
+            &fs_module,
+            config.format,
+            CubePipeline::Fill,
+            sample_count,
+        );
+        let transparent_pipeline = Self::create_solid_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &vs_module,
+            &fs_module,
+            config.format,
+            CubePipeline::Transparent,
+            sample_count,
+        );
+        let line_mode_pipeline = supports_line_mode.then(|| {
+            Self::create_solid_pipeline(
+                &device,
+                &render_pipeline_layout,
+                &vs_module,
+                &fs_module,
+                config.format,
+                CubePipeline::Line,
+                sample_count,
+            )
         });
 
         // Create line pipeline for grid
-        let line_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Line Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &vs_module,
-                entry_point: "vs_main",
-                buffers: &[crate::graphics::vertex::Vertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &fs_module,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
+        let line_pipeline = Self::create_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &vs_module,
+            &fs_module,
+            sample_count,
+            PipelineSpec {
+                label: "Line Pipeline",
+                instanced: false,
+                fs_entry: "fs_flat",
+                target: wgpu::ColorTargetState {
                     format: config.format,
                     blend: Some(wgpu::BlendState::REPLACE),
                     write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
+                },
                 topology: wgpu::PrimitiveTopology::LineList, // For grid lines
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Cw,
-                cull_mode: None,
                 polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
             },
-            multiview: None,
-        });
+        );
 
         // Create wireframe pipeline for outlines
-        let wireframe_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Wireframe Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &vs_module,
-                entry_point: "vs_main",
-                buffers: &[crate::graphics::vertex::Vertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &fs_module,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
+        let wireframe_pipeline = supports_line_mode.then(|| {
+            Self::create_pipeline(
+                &device,
+                &render_pipeline_layout,
+                &vs_module,
+                &fs_module,
+                sample_count,
+                PipelineSpec {
+                    label: "Wireframe Pipeline",
+                    instanced: true,
+                    fs_entry: "fs_flat",
+                    target: wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    },
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    polygon_mode: wgpu::PolygonMode::Line,
+                    depth_stencil: Some(wgpu::DepthStencilState {
+                        format: DEPTH_FORMAT,
+                        depth_write_enabled: false,
+                        depth_compare: wgpu::CompareFunction::LessEqual,
+                        stencil: wgpu::StencilState::default(),
+                        bias: wgpu::DepthBiasState {
+                            constant: -1,
+                            slope_scale: -1.0,
+                            clamp: 0.0,
+                        },
+                    }),
+                },
+            )
+        });
+
+        // HUD text pipeline, drawn last without depth testing
+        let hud_pipeline = Self::create_pipeline(
+            &device,
+            &render_pipeline_layout,
+            &vs_module,
+            &fs_module,
+            sample_count,
+            PipelineSpec {
+                label: "HUD Pipeline",
+                instanced: false,
+                fs_entry: "fs_flat",
+                target: wgpu::ColorTargetState {
                     format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Cw,
-                cull_mode: None,
-                polygon_mode: wgpu::PolygonMode::Line,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: false,
-                depth_compare: wgpu::CompareFunction::LessEqual,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState {
-                    constant: -1,
-                    slope_scale: -1.0,
-                    clamp: 0.0,
                 },
-            }),
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                depth_stencil: None,
             },
-            multiview: None,
-        });
+        );
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
@@ -295,78 +625,385 @@ impl Renderer {
 
         let num_indices = crate::graphics::CUBE_INDICES.len() as u32;
 
-        // Create grid buffers - 3D volumetric grid
-        let (grid_vertices, grid_indices) =
-            crate::graphics::create_grid_vertices(200.0, 100.0, 200.0, 10.0);
-
-        let grid_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Grid Vertex Buffer"),
-            contents: bytemuck::cast_slice(&grid_vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        let instance_capacity = 64;
+        let instance_buffer = Self::create_instance_buffer(&device, instance_capacity);
 
-        let grid_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Grid Index Buffer"),
-            contents: bytemuck::cast_slice(&grid_indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+        // Create grid buffers - 3D volumetric grid
+        let (grid_vertex_buffer, grid_index_buffer, grid_num_indices) =
+            Self::create_grid_buffers(&device, false);
 
-        let grid_num_indices = grid_indices.len() as u32;
+        let trail_buffer = GrowableBuffer::new(&device, "Trail Vertex Buffer");
+        let aabb_buffer = GrowableBuffer::new(&device, "AABB Debug Vertex Buffer");
+        let minimap_instance_buffer = GrowableBuffer::new(&device, "Minimap Instance Buffer");
+        let hud_buffer = GrowableBuffer::new(&device, "HUD Vertex Buffer");
 
-        let depth_texture = Self::create_depth_texture(&device, &config);
+        let depth_texture = Self::create_depth_texture(&device, &config, sample_count);
+        let msaa_texture = Self::create_msaa_texture(&device, &config, sample_count);
 
         Self {
             window,
             surface,
+            offscreen,
             device,
             queue,
             config,
             size,
             render_pipeline,
+            transparent_pipeline,
             wireframe_pipeline,
+            line_mode_pipeline,
+            wireframe: WireframeMode::new(supports_line_mode),
+            debug_aabb: false,
+            clear_color: wgpu::Color::BLACK,
             line_pipeline,
             vertex_buffer,
             index_buffer,
             num_indices,
+            instance_buffer,
+            instance_capacity,
+            instance_count: 0,
             grid_vertex_buffer,
             grid_index_buffer,
             grid_num_indices,
+            floor_grid: false,
             camera_bind_group,
             camera_buffer,
+            hud_pipeline,
+            hud_bind_group,
+            hud_lines: Vec::new(),
+            trail_vertices: Vec::new(),
+            trail_buffer,
+            aabb_buffer,
+            minimap_instance_buffer,
+            hud_buffer,
+            minimap: None,
+            minimap_camera_buffer,
+            minimap_bind_group,
+            observation: ObservationConfig::default(),
+            observation_camera: Self::default_observation_camera(&ObservationConfig::default()),
+            light_buffer,
+            light,
             depth_texture,
+            sample_count,
+            msaa_texture,
         }
     }
 
-    fn create_depth_texture(
+    fn create_solid_pipeline(
         device: &wgpu::Device,
-        config: &wgpu::SurfaceConfiguration,
-    ) -> wgpu::TextureView {
-        let size = wgpu::Extent3d {
-            width: config.width,
-            height: config.height,
-            depth_or_array_layers: 1,
-        };
-        let desc = wgpu::TextureDescriptor {
-            label: Some("depth_texture"),
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth32Float,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
-            view_formats: &[],
-        };
-        let texture = device.create_texture(&desc);
+        layout: &wgpu::PipelineLayout,
+        vs_module: &wgpu::ShaderModule,
+        fs_module: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        kind: CubePipeline,
+        sample_count: u32,
+    ) -> wgpu::RenderPipeline {
+        let transparent = kind == CubePipeline::Transparent;
+        Self::create_pipeline(
+            device,
+            layout,
+            vs_module,
+            fs_module,
+            sample_count,
+            PipelineSpec {
+                label: match kind {
+                    CubePipeline::Fill => "Render Pipeline",
+                    CubePipeline::Line => "Line Mode Render Pipeline",
+                    CubePipeline::Transparent => "Transparent Render Pipeline",
+                },
+                instanced: true,
+                fs_entry: "fs_main",
+                target: wgpu::ColorTargetState {
+                    format,
+                    blend: transparent.then_some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::OVER,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                },
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                polygon_mode: match kind {
+                    CubePipeline::Line => wgpu::PolygonMode::Line,
+                    _ => wgpu::PolygonMode::Fill,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: !transparent, // Sorted instead, later layers stay visible
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+            },
+        )
+    }
+
+    // Every pipeline shares the camera layout and shader modules, and draws with cull_mode
+    // None and clockwise fronts; they differ in what PipelineSpec holds
+    fn create_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        vs_module: &wgpu::ShaderModule,
+        fs_module: &wgpu::ShaderModule,
+        sample_count: u32,
+        spec: PipelineSpec,
+    ) -> wgpu::RenderPipeline {
+        let instanced_buffers = [
+            crate::graphics::vertex::Vertex::desc(),
+            crate::graphics::vertex::InstanceRaw::desc(),
+        ];
+        let (entry_point, buffers) = if spec.instanced {
+            ("vs_instanced", &instanced_buffers[..])
+        } else {
+            ("vs_main", &instanced_buffers[..1])
+        };
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(spec.label),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: vs_module,
+                entry_point,
+                buffers,
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: fs_module,
+                entry_point: spec.fs_entry,
+                targets: &[Some(spec.target)],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: spec.topology,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Cw, // was ccw
+                // cull_mode: Some(wgpu::Face::Back),
+                cull_mode: None,
+                polygon_mode: spec.polygon_mode,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: spec.depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        })
+    }
+
+    // Wireframe mode draws bodies with PolygonMode::Line, which requires the
+    // POLYGON_MODE_LINE device feature. Without it the request is ignored.
+    pub fn set_wireframe(&mut self, enabled: bool) {
+        self.wireframe.set(enabled);
+    }
+
+    pub fn is_wireframe(&self) -> bool {
+        self.wireframe.enabled
+    }
+
+    pub fn polygon_mode(&self) -> wgpu::PolygonMode {
+        self.wireframe.polygon_mode()
+    }
+
+    pub fn set_clear_color(&mut self, color: wgpu::Color) {
+        self.clear_color = color;
+    }
+
+    pub fn clear_color(&self) -> wgpu::Color {
+        self.clear_color
+    }
+
+    // Draw every body's AABB as yellow lines over the scene
+    pub fn set_debug_aabb(&mut self, enabled: bool) {
+        self.debug_aabb = enabled;
+    }
+
+    pub fn is_debug_aabb(&self) -> bool {
+        self.debug_aabb
+    }
+
+    fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Instance Buffer"),
+            size: (capacity * std::mem::size_of::<crate::graphics::vertex::InstanceRaw>())
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    // Replace the instances drawn by the cube passes, growing the buffer as needed
+    pub fn upload_instances(&mut self, instances: &[crate::graphics::vertex::InstanceRaw]) {
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len().next_power_of_two();
+            self.instance_buffer =
+                Self::create_instance_buffer(&self.device, self.instance_capacity);
+        }
+        self.queue
+            .write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(instances));
+        self.instance_count = instances.len() as u32;
+    }
+
+    // Lines of text drawn in the top-left corner every frame
+    pub fn set_hud_text(&mut self, lines: &[String]) {
+        self.hud_lines = lines.to_vec();
+    }
+
+    // Fading line through the trail's points, drawn every frame until replaced
+    pub fn set_trail(&mut self, trail: &crate::graphics::trail::TrailBuffer) {
+        let points: Vec<crate::physics::Vector3> = trail.points().copied().collect();
+        self.trail_vertices = crate::graphics::trail::create_trail_line_vertices(
+            &points,
+            crate::graphics::trail::TRAIL_COLOR,
+        );
+    }
+
+    pub fn set_minimap(&mut self, minimap: Option<crate::graphics::minimap::MinimapConfig>) {
+        self.minimap = minimap;
+    }
+
+    pub fn minimap(&self) -> Option<&crate::graphics::minimap::MinimapConfig> {
+        self.minimap.as_ref()
+    }
+
+    // Rebuilds the grid buffers, so only call it on a toggle rather than every frame
+    pub fn set_floor_grid(&mut self, floor_grid: bool) {
+        if self.floor_grid == floor_grid {
+            return;
+        }
+        let (vertex_buffer, index_buffer, num_indices) =
+            Self::create_grid_buffers(&self.device, floor_grid);
+        self.grid_vertex_buffer = vertex_buffer;
+        self.grid_index_buffer = index_buffer;
+        self.grid_num_indices = num_indices;
+        self.floor_grid = floor_grid;
+    }
+
+    pub fn is_floor_grid(&self) -> bool {
+        self.floor_grid
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    fn create_grid_buffers(
+        device: &wgpu::Device,
+        floor_only: bool,
+    ) -> (wgpu::Buffer, wgpu::Buffer, u32) {
+        let (width, height, depth, spacing) = (200.0, 100.0, 200.0, 10.0);
+        let (grid_vertices, grid_indices) = if floor_only {
+            crate::graphics::create_floor_grid(width, depth, spacing, -height / 2.0)
+        } else {
+            crate::graphics::create_grid_vertices(width, height, depth, spacing)
+        };
+
+        let grid_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grid Vertex Buffer"),
+            contents: bytemuck::cast_slice(&grid_vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let grid_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Grid Index Buffer"),
+            contents: bytemuck::cast_slice(&grid_indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        (
+            grid_vertex_buffer,
+            grid_index_buffer,
+            grid_indices.len() as u32,
+        )
+    }
+
+    fn create_depth_texture(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+    ) -> wgpu::TextureView {
+        let texture = device.create_texture(&Self::depth_texture_desc(config, sample_count));
         texture.create_view(&wgpu::TextureViewDescriptor::default())
     }
 
+    // Always the size of the color target, so resize has to recreate it
+    fn depth_texture_desc(
+        config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+    ) -> wgpu::TextureDescriptor<'static> {
+        wgpu::TextureDescriptor {
+            label: Some("depth_texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        }
+    }
+
+    // Multisampled color target, None when MSAA is off
+    fn create_msaa_texture(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        sample_count: u32,
+    ) -> Option<wgpu::TextureView> {
+        if sample_count <= 1 {
+            return None;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("msaa_texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    pub fn set_light_dir(&mut self, direction: glam::Vec3) {
+        self.light = LightUniform::new(direction, self.light.ambient);
+        self.queue
+            .write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&[self.light]));
+    }
+
+    pub fn set_ambient(&mut self, ambient: f32) {
+        self.light.ambient = ambient;
+        self.queue
+            .write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&[self.light]));
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
-            self.depth_texture = Self::create_depth_texture(&self.device, &self.config);
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.config);
+            }
+            if self.offscreen.is_some() {
+                self.offscreen = Some(Self::create_offscreen_texture(&self.device, &self.config));
+            }
+            self.depth_texture =
+                Self::create_depth_texture(&self.device, &self.config, self.sample_count);
+            self.msaa_texture =
+                Self::create_msaa_texture(&self.device, &self.config, self.sample_count);
         }
     }
 
@@ -375,10 +1012,16 @@ impl Renderer {
         camera: &crate::graphics::Camera,
         game_objects: &[crate::game::game_engine::GameObject],
     ) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let output = match &self.surface {
+            Some(surface) => Some(surface.get_current_texture()?),
+            None => None,
+        };
+        let target = match (&output, &self.offscreen) {
+            (Some(output), _) => &output.texture,
+            (None, Some(offscreen)) => offscreen,
+            (None, None) => return Ok(()),
+        };
+        let view = target.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder = self
             .device
@@ -386,6 +1029,34 @@ impl Renderer {
                 label: Some("Render Encoder"),
             });
 
+        // Skip inactive bodies and those entirely outside the view frustum
+        let frustum = camera.build_frustum_planes();
+        let visible_objects: Vec<&crate::game::game_engine::GameObject> = game_objects
+            .iter()
+            .filter(|obj| obj.body.active)
+            .filter(|obj| {
+                let aabb = &obj.body.aabb;
+                crate::graphics::camera::aabb_in_frustum(
+                    &frustum,
+                    glam::Vec3::new(aabb.min.x, aabb.min.y, aabb.min.z),
+                    glam::Vec3::new(aabb.max.x, aabb.max.y, aabb.max.z),
+                )
+            })
+            .collect();
+
+        // Opaque objects first, then translucent ones back to front so they blend correctly
+        let (opaque, mut transparent): (Vec<_>, Vec<_>) = visible_objects
+            .into_iter()
+            .partition(|obj| !is_transparent(obj));
+        sort_back_to_front(&mut transparent, camera.position);
+        let opaque_count = opaque.len() as u32;
+        let instances: Vec<crate::graphics::vertex::InstanceRaw> = opaque
+            .iter()
+            .chain(transparent.iter())
+            .map(|obj| crate::graphics::vertex::InstanceRaw::from_game_object(obj))
+            .collect();
+        self.upload_instances(&instances);
+
         let mut camera_uniform = crate::graphics::camera::CameraUniform::new();
         camera_uniform.update_view_proj(camera);
         self.queue.write_buffer(
@@ -394,21 +1065,16 @@ impl Renderer {
             bytemuck::cast_slice(&[camera_uniform]),
         );
 
+        // With MSAA every pass draws into the multisampled target, resolved at the end
+        let color_view = self.msaa_texture.as_ref().unwrap_or(&view);
+
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Grid Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: color_view,
                     resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.0,
-                            g: 0.0,
-                            b: 0.0,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
+                    ops: clear_ops(self.clear_color),
                 })],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                     view: &self.depth_texture,
@@ -452,7 +1118,7 @@ impl Renderer {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: color_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Load, // Don't clear, keep grid
@@ -471,37 +1137,44 @@ impl Renderer {
                 timestamp_writes: None,
             });
 
-            render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-            for game_object in game_objects {
-                let model_matrix = game_object.get_model_matrix();
-                let model_matrix_data = model_matrix.to_cols_array();
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
 
-                render_pass.set_push_constants(
-                    wgpu::ShaderStages::VERTEX,
-                    0,
-                    bytemuck::cast_slice(&model_matrix_data),
-                );
+            // Outlines don't blend, so in wireframe mode everything goes through the line
+            // pipeline. Push constants need a pipeline set first.
+            let line_mode_pipeline = self
+                .line_mode_pipeline
+                .as_ref()
+                .filter(|_| self.wireframe.enabled);
+            render_pass.set_pipeline(line_mode_pipeline.unwrap_or(&self.render_pipeline));
 
-                render_pass.set_push_constants(
-                    wgpu::ShaderStages::VERTEX,
-                    64,
-                    bytemuck::cast_slice(&game_object.color),
-                );
+            let white = [1.0f32, 1.0f32, 1.0f32, 1.0f32]; // Instances carry their own color
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                64,
+                bytemuck::cast_slice(&white),
+            );
 
-                render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+            if line_mode_pipeline.is_some() {
+                render_pass.draw_indexed(0..self.num_indices, 0, 0..self.instance_count);
+            } else {
+                render_pass.draw_indexed(0..self.num_indices, 0, 0..opaque_count);
+                render_pass.set_pipeline(&self.transparent_pipeline);
+                render_pass.draw_indexed(0..self.num_indices, 0, opaque_count..self.instance_count);
             }
         }
 
-        // Second pass for wireframe outlines
+        // Second pass for wireframe outlines, redundant in wireframe mode
+        if let (Some(wireframe_pipeline), false) =
+            (&self.wireframe_pipeline, self.wireframe.enabled)
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Wireframe Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: color_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Load, // Don't clear, draw on top
@@ -520,37 +1193,727 @@ impl Renderer {
                 timestamp_writes: None,
             });
 
-            render_pass.set_pipeline(&self.wireframe_pipeline);
+            render_pass.set_pipeline(wireframe_pipeline);
             render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
 
-            for game_object in game_objects {
-                let model_matrix = game_object.get_model_matrix();
-                let model_matrix_data = model_matrix.to_cols_array();
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+
+            let outline_color = [0.0f32, 0.0f32, 0.0f32, 1.0f32];
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                64,
+                bytemuck::cast_slice(&outline_color),
+            );
 
-                render_pass.set_push_constants(
-                    wgpu::ShaderStages::VERTEX,
-                    0,
-                    bytemuck::cast_slice(&model_matrix_data),
-                );
+            render_pass.draw_indexed(0..self.num_indices, 0, 0..self.instance_count);
+        }
 
-                let outline_color = [0.0f32, 0.0f32, 0.0f32, 1.0f32];
-                render_pass.set_push_constants(
-                    wgpu::ShaderStages::VERTEX,
-                    64,
-                    bytemuck::cast_slice(&outline_color),
-                );
+        // Ball trail, reuses the grid line pipeline
+        if !self.trail_vertices.is_empty() {
+            self.trail_buffer.write(
+                &self.device,
+                &self.queue,
+                bytemuck::cast_slice(&self.trail_vertices),
+            );
 
-                render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
-            }
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Trail Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.line_pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.trail_buffer.buffer.slice(..));
+
+            let identity_data = glam::Mat4::IDENTITY.to_cols_array();
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                0,
+                bytemuck::cast_slice(&identity_data),
+            );
+            let white = [1.0f32, 1.0f32, 1.0f32, 1.0f32]; // Keep the faded vertex colors
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                64,
+                bytemuck::cast_slice(&white),
+            );
+
+            render_pass.draw(0..self.trail_vertices.len() as u32, 0..1);
+        }
+
+        // Debug pass for AABBs, reuses the grid line pipeline
+        if self.debug_aabb && !game_objects.is_empty() {
+            let aabb_vertices: Vec<crate::graphics::vertex::Vertex> = game_objects
+                .iter()
+                .filter(|obj| obj.body.active)
+                .flat_map(|obj| {
+                    crate::graphics::debug::create_aabb_line_vertices(
+                        &obj.body.aabb,
+                        crate::graphics::debug::AABB_DEBUG_COLOR,
+                    )
+                })
+                .collect();
+            self.aabb_buffer.write(
+                &self.device,
+                &self.queue,
+                bytemuck::cast_slice(&aabb_vertices),
+            );
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("AABB Debug Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.line_pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.aabb_buffer.buffer.slice(..));
+
+            let identity_data = glam::Mat4::IDENTITY.to_cols_array();
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                0,
+                bytemuck::cast_slice(&identity_data),
+            );
+            let white = [1.0f32, 1.0f32, 1.0f32, 1.0f32]; // Keep the vertex color as is
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                64,
+                bytemuck::cast_slice(&white),
+            );
+
+            render_pass.draw(0..aabb_vertices.len() as u32, 0..1);
+        }
+
+        // Top-down minimap in its own corner viewport, skipped if the window is too small
+        let minimap_viewport = self.minimap.as_ref().and_then(|minimap| {
+            let viewport = minimap.viewport(self.config.width, self.config.height);
+            let (x, y, width, height) = viewport;
+            (x + width <= self.config.width as f32 && y + height <= self.config.height as f32)
+                .then_some((minimap, viewport))
+        });
+        if let Some((minimap, (x, y, width, height))) = minimap_viewport {
+            let instances = crate::graphics::minimap::minimap_instances(minimap, game_objects);
+            self.minimap_instance_buffer.write(
+                &self.device,
+                &self.queue,
+                bytemuck::cast_slice(&instances),
+            );
+            let minimap_uniform = crate::graphics::camera::CameraUniform {
+                view_proj: minimap.view_proj().to_cols_array_2d(),
+            };
+            self.queue.write_buffer(
+                &self.minimap_camera_buffer,
+                0,
+                bytemuck::cast_slice(&[minimap_uniform]),
+            );
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Minimap Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                // Later passes don't test depth, so the scene's depth can be dropped here
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+            render_pass.set_scissor_rect(x as u32, y as u32, width as u32, height as u32);
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.minimap_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.set_vertex_buffer(1, self.minimap_instance_buffer.buffer.slice(..));
+
+            let white = [1.0f32, 1.0f32, 1.0f32, 1.0f32];
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                64,
+                bytemuck::cast_slice(&white),
+            );
+
+            render_pass.draw_indexed(0..self.num_indices, 0, 0..instances.len() as u32);
+        }
+
+        // HUD overlay pass
+        let hud_vertices = crate::graphics::hud::create_text_vertices(
+            &self.hud_lines,
+            self.config.width,
+            self.config.height,
+            4.0,
+            [1.0, 1.0, 1.0],
+        );
+        if !hud_vertices.is_empty() {
+            self.hud_buffer.write(
+                &self.device,
+                &self.queue,
+                bytemuck::cast_slice(&hud_vertices),
+            );
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("HUD Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            render_pass.set_pipeline(&self.hud_pipeline);
+            render_pass.set_bind_group(0, &self.hud_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.hud_buffer.buffer.slice(..));
+
+            let identity_data = glam::Mat4::IDENTITY.to_cols_array();
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                0,
+                bytemuck::cast_slice(&identity_data),
+            );
+            let white = [1.0f32, 1.0f32, 1.0f32, 1.0f32];
+            render_pass.set_push_constants(
+                wgpu::ShaderStages::VERTEX,
+                64,
+                bytemuck::cast_slice(&white),
+            );
+
+            render_pass.draw(0..hud_vertices.len() as u32, 0..1);
+        }
+
+        if let Some(msaa_view) = &self.msaa_texture {
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("MSAA Resolve Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: msaa_view,
+                    resolve_target: Some(&view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Discard,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+        if let Some(output) = output {
+            output.present();
+        }
 
         Ok(())
     }
+
+    // Fixed overview of the whole arena
+    fn default_observation_camera(config: &ObservationConfig) -> crate::graphics::Camera {
+        let mut camera = crate::graphics::Camera::new(config.width, config.height);
+        camera.follow(glam::Vec3::ZERO, glam::Vec3::new(0.0, 30.0, 45.0));
+        camera
+    }
+
+    pub fn set_observation_config(&mut self, config: ObservationConfig) {
+        self.observation = config;
+    }
+
+    // The camera's aspect is replaced by the observation's, see render_observation
+    pub fn set_observation_camera(&mut self, camera: crate::graphics::Camera) {
+        self.observation_camera = camera;
+    }
+
+    // Render `state` offscreen and return it downsampled to the observation size.
+    // Only headless renderers can read back pixels, windowed ones return no data.
+    pub fn render_observation(&mut self, state: &crate::server::models::WorldState) -> Frame {
+        let game_objects: Vec<crate::game::game_engine::GameObject> = state
+            .bodies
+            .iter()
+            .cloned()
+            .map(crate::game::game_engine::GameObject::from_body)
+            .collect();
+
+        // Projected at the observation's aspect, so downsampling the frame to the observation
+        // size undoes the stretch instead of adding one. No HUD text, trail or minimap.
+        let config = self.observation;
+        let mut camera = self.observation_camera.clone();
+        camera.resize(config.width, config.height);
+        let hud_lines = std::mem::take(&mut self.hud_lines);
+        let trail_vertices = std::mem::take(&mut self.trail_vertices);
+        let minimap = self.minimap.take();
+        let rendered = self.render(&camera, &game_objects);
+        self.hud_lines = hud_lines;
+        self.trail_vertices = trail_vertices;
+        self.minimap = minimap;
+
+        let channels = if config.grayscale { 1 } else { 3 };
+        let rgba = if rendered.is_ok() {
+            self.capture_frame()
+        } else {
+            Vec::new()
+        };
+
+        Frame {
+            width: config.width,
+            height: config.height,
+            channels,
+            data: downsample_rgba(
+                &rgba,
+                self.config.width,
+                self.config.height,
+                config.width,
+                config.height,
+                config.grayscale,
+            ),
+        }
+    }
+
+    fn create_offscreen_texture(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+            view_formats: &[],
+        })
+    }
+
+    // Tightly packed RGBA8 rows of the last headless frame, empty for windowed renderers
+    pub fn capture_frame(&self) -> Vec<u8> {
+        let Some(texture) = &self.offscreen else {
+            return Vec::new();
+        };
+
+        let (width, height) = (self.config.width, self.config.height);
+        let unpadded_bytes_per_row = width * 4;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let readback = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        if !matches!(receiver.recv(), Ok(Ok(()))) {
+            return Vec::new();
+        }
+
+        let data = slice.get_mapped_range();
+        let pixels = strip_row_padding(
+            &data,
+            padded_bytes_per_row as usize,
+            unpadded_bytes_per_row as usize,
+        );
+        drop(data);
+        readback.unmap();
+
+        pixels
+    }
+}
+
+// Highest of SUPPORTED_SAMPLE_COUNTS not above `requested` that `supported` accepts, 1 if none
+fn pick_sample_count(requested: u32, supported: impl Fn(u32) -> bool) -> u32 {
+    SUPPORTED_SAMPLE_COUNTS
+        .iter()
+        .copied()
+        .filter(|&count| count <= requested && supported(count))
+        .max()
+        .unwrap_or(1)
+}
+
+// Texture copies pad each row to COPY_BYTES_PER_ROW_ALIGNMENT, keep the leading
+// `row_bytes` of every `padded_row_bytes`
+fn strip_row_padding(data: &[u8], padded_row_bytes: usize, row_bytes: usize) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(data.len() / padded_row_bytes * row_bytes);
+    for row in data.chunks(padded_row_bytes) {
+        pixels.extend_from_slice(&row[..row_bytes]);
+    }
+    pixels
+}
+
+// Ops for the first pass of a frame, which clears the target to the clear color
+fn clear_ops(clear_color: wgpu::Color) -> wgpu::Operations<wgpu::Color> {
+    wgpu::Operations {
+        load: wgpu::LoadOp::Clear(clear_color),
+        store: wgpu::StoreOp::Store,
+    }
+}
+
+// Objects with alpha below 1 go through the blended pipeline
+pub fn is_transparent(object: &crate::game::game_engine::GameObject) -> bool {
+    object.color[3] < 1.0
+}
+
+// Farthest from `camera_position` first, by body centre
+pub fn sort_back_to_front(
+    objects: &mut [&crate::game::game_engine::GameObject],
+    camera_position: glam::Vec3,
+) {
+    let distance = |obj: &crate::game::game_engine::GameObject| {
+        let p = obj.body.position;
+        camera_position.distance_squared(glam::Vec3::new(p.x, p.y, p.z))
+    };
+    objects.sort_by(|a, b| distance(b).total_cmp(&distance(a)));
+}
+
+// Box-filter RGBA pixels down to the target size, dropping alpha
+fn downsample_rgba(
+    rgba: &[u8],
+    src_width: u32,
+    src_height: u32,
+    dst_width: u32,
+    dst_height: u32,
+    grayscale: bool,
+) -> Vec<u8> {
+    if rgba.len() < (src_width * src_height * 4) as usize || dst_width == 0 || dst_height == 0 {
+        return Vec::new();
+    }
+
+    let channels = if grayscale { 1 } else { 3 };
+    let mut out = Vec::with_capacity((dst_width * dst_height * channels) as usize);
+    for dy in 0..dst_height {
+        let y0 = dy * src_height / dst_height;
+        let y1 = ((dy + 1) * src_height / dst_height).max(y0 + 1);
+        for dx in 0..dst_width {
+            let x0 = dx * src_width / dst_width;
+            let x1 = ((dx + 1) * src_width / dst_width).max(x0 + 1);
+
+            let mut sum = [0u32; 3];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let i = ((y * src_width + x) * 4) as usize;
+                    sum[0] += rgba[i] as u32;
+                    sum[1] += rgba[i + 1] as u32;
+                    sum[2] += rgba[i + 2] as u32;
+                }
+            }
+            let count = (y1 - y0) * (x1 - x0);
+            let [r, g, b] = sum.map(|c| c / count);
+
+            if grayscale {
+                // ITU-R BT.601 luma
+                out.push(((r * 299 + g * 587 + b * 114) / 1000) as u8);
+            } else {
+                out.extend_from_slice(&[r as u8, g as u8, b as u8]);
+            }
+        }
+    }
+    out
 }
 
 use wgpu::util::DeviceExt;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_rows_drop_their_copy_padding() {
+        // 3 pixels wide: 12 bytes per row padded to the 256-byte copy alignment
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+        let mut data = vec![0xAA; align * 2];
+        data[..12].copy_from_slice(&[1; 12]);
+        data[align..align + 12].copy_from_slice(&[2; 12]);
+
+        let pixels = strip_row_padding(&data, align, 12);
+        assert_eq!(pixels.len(), 24);
+        assert_eq!(&pixels[..12], &[1; 12]);
+        assert_eq!(&pixels[12..], &[2; 12]);
+    }
+
+    #[test]
+    fn wireframe_mode_follows_the_toggle_when_supported() {
+        let mut mode = WireframeMode::new(true);
+        assert_eq!(mode.polygon_mode(), wgpu::PolygonMode::Fill);
+        mode.set(true);
+        assert!(mode.enabled);
+        assert_eq!(mode.polygon_mode(), wgpu::PolygonMode::Line);
+        mode.set(false);
+        assert_eq!(mode.polygon_mode(), wgpu::PolygonMode::Fill);
+
+        // Without POLYGON_MODE_LINE it stays filled
+        let mut mode = WireframeMode::new(false);
+        mode.set(true);
+        assert!(!mode.enabled);
+        assert_eq!(mode.polygon_mode(), wgpu::PolygonMode::Fill);
+    }
+
+    #[test]
+    fn downsampled_frames_have_one_value_per_pixel_and_channel() {
+        let rgba = vec![200u8; 64 * 48 * 4];
+        for (width, height) in [(84, 84), (32, 24), (64, 48)] {
+            for (grayscale, channels) in [(true, 1), (false, 3)] {
+                let data = downsample_rgba(&rgba, 64, 48, width, height, grayscale);
+                assert_eq!(data.len(), (width * height * channels) as usize);
+            }
+        }
+
+        // Each output pixel averages its block: black and white columns become mid gray
+        let stripes: Vec<u8> = (0..4 * 2)
+            .flat_map(|i| if i % 2 == 0 { [0, 0, 0, 255] } else { [255; 4] })
+            .collect();
+        assert_eq!(downsample_rgba(&stripes, 4, 2, 2, 1, false), [127; 6]);
+        assert!(downsample_rgba(&stripes[..4], 4, 2, 2, 1, false).is_empty());
+    }
+
+    #[test]
+    fn sample_count_falls_back_to_the_highest_supported() {
+        let all = |_: u32| true;
+        let up_to_4 = |count: u32| count <= 4;
+        assert_eq!(pick_sample_count(4, all), 4);
+        assert_eq!(pick_sample_count(8, all), 8);
+        assert_eq!(pick_sample_count(8, up_to_4), 4);
+        assert_eq!(pick_sample_count(1, all), 1);
+        // Not one of 1, 4 or 8, so the next count down
+        assert_eq!(pick_sample_count(6, all), 4);
+        assert_eq!(pick_sample_count(3, all), 1);
+        assert_eq!(pick_sample_count(4, |_| false), 1);
+    }
+
+    #[test]
+    fn depth_texture_matches_the_target_size() {
+        let mut config = wgpu::SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: HEADLESS_FORMAT,
+            width: 800,
+            height: 600,
+            present_mode: PresentMode::AutoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let desc = Renderer::depth_texture_desc(&config, 4);
+        assert_eq!(desc.format, DEPTH_FORMAT);
+        assert_eq!(desc.sample_count, 4);
+        assert_eq!((desc.size.width, desc.size.height), (800, 600));
+
+        // As resize does before recreating it
+        config.width = 1280;
+        config.height = 720;
+        let desc = Renderer::depth_texture_desc(&config, 4);
+        assert_eq!((desc.size.width, desc.size.height), (1280, 720));
+    }
+
+    #[test]
+    fn translucent_objects_sort_back_to_front() {
+        use crate::game::game_engine::GameObject;
+        use crate::physics::{RigidBody, Vector3};
+
+        let object = |id: &str, z: f32, alpha: f32| {
+            let mut object = GameObject::from_body(RigidBody::from_extents_with_id(
+                id.to_string(),
+                Vector3::new(0.0, 0.0, z),
+                Vector3::zero(),
+                Vector3::new(0.5, 0.5, 0.5),
+                1.0,
+                1.0,
+                false,
+            ));
+            object.color[3] = alpha;
+            object
+        };
+        let objects = [
+            object("near", 5.0, 0.5),
+            object("solid", 0.0, 1.0),
+            object("far", -20.0, 0.5),
+            object("middle", -5.0, 0.25),
+        ];
+        let (opaque, mut transparent): (Vec<_>, Vec<_>) =
+            objects.iter().partition(|obj| !is_transparent(obj));
+        assert_eq!(opaque.len(), 1);
+        assert_eq!(opaque[0].body.id, "solid");
+
+        sort_back_to_front(&mut transparent, glam::Vec3::new(0.0, 0.0, 10.0));
+        let ids: Vec<&str> = transparent.iter().map(|o| o.body.id.as_str()).collect();
+        assert_eq!(ids, ["far", "middle", "near"]);
+    }
+
+    // A headless renderer looking at a red cube, None without a GPU adapter
+    fn red_cube_scene(
+        width: u32,
+        height: u32,
+    ) -> Option<(
+        Renderer,
+        crate::graphics::Camera,
+        crate::game::game_engine::GameObject,
+    )> {
+        use crate::game::game_engine::GameObject;
+        use crate::physics::{RigidBody, Vector3};
+
+        let mut renderer = pollster::block_on(Renderer::try_new_headless(width, height))?;
+        // Keep the grid lines away from the cube so the centre pixel is the cube's face
+        renderer.set_floor_grid(true);
+        let mut camera = crate::graphics::Camera::new(width, height);
+        camera.orbit(glam::Vec3::ZERO, 5.0, 0.3, -0.3);
+
+        let mut cube = GameObject::from_body(RigidBody::from_extents_with_id(
+            "cube".to_string(),
+            Vector3::zero(),
+            Vector3::zero(),
+            Vector3::new(1.0, 1.0, 1.0),
+            1.0,
+            1.0,
+            false,
+        ));
+        cube.color = [1.0, 0.0, 0.0, 1.0];
+        Some((renderer, camera, cube))
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn headless_frame_shows_the_cube_in_rgba_order() {
+        let Some((mut renderer, camera, cube)) = red_cube_scene(64, 48) else {
+            return;
+        };
+        renderer.render(&camera, &[cube]).unwrap();
+
+        let frame = renderer.capture_frame();
+        assert_eq!(frame.len(), 64 * 48 * 4);
+        let centre = (24 * 64 + 32) * 4;
+        let pixel = &frame[centre..centre + 4];
+        // Red only in the first byte, so HEADLESS_FORMAT really is RGBA rather than BGRA
+        assert!(pixel[0] > 0, "centre pixel is background: {pixel:?}");
+        assert_eq!((pixel[1], pixel[2], pixel[3]), (0, 0, 255));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn observations_hold_width_height_channels_bytes() {
+        let Some((mut renderer, camera, cube)) = red_cube_scene(64, 48) else {
+            return;
+        };
+        renderer.set_observation_camera(camera);
+        let state = crate::server::models::WorldState {
+            bodies: vec![cube.body],
+            time: 0.0,
+            score_player1: 0,
+            score_player2: 0,
+            tick_id: 0,
+            timestamp_ms: 0,
+            phase: Default::default(),
+            tick_count: 0,
+            sim_time: 0.0,
+        };
+
+        for (width, height, grayscale) in [(84, 84, true), (32, 24, false)] {
+            renderer.set_observation_config(ObservationConfig {
+                width,
+                height,
+                grayscale,
+            });
+            let frame = renderer.render_observation(&state);
+            let channels = if grayscale { 1 } else { 3 };
+            assert_eq!(
+                (frame.width, frame.height, frame.channels),
+                (width, height, channels)
+            );
+            assert_eq!(frame.data.len(), (width * height * channels) as usize);
+        }
+    }
+
+    #[test]
+    fn grid_pass_clears_to_the_clear_color() {
+        let transparent = wgpu::Color {
+            r: 0.0,
+            g: 0.0,
+            b: 0.2,
+            a: 0.0,
+        };
+        let ops = clear_ops(transparent);
+        assert_eq!(ops.load, wgpu::LoadOp::Clear(transparent));
+        assert_eq!(ops.store, wgpu::StoreOp::Store);
+    }
+}
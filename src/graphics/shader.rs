@@ -10,11 +10,14 @@ var<uniform> camera: CameraUniform;
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
+    @location(2) normal: vec3<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) normal: vec3<f32>,
 };
 
 struct PushConstants {
@@ -23,24 +26,82 @@ struct PushConstants {
 };
 var<push_constant> push_constants: PushConstants;
 
+// Inverse-transpose of the model's upper 3x3. Models are translate * rotate * scale, so
+// the columns are orthogonal and dividing each by its squared length inverts the scale
+// while keeping the rotation; normals stay perpendicular under non-uniform scale.
+fn normal_matrix(model: mat4x4<f32>) -> mat3x3<f32> {
+    let x = model[0].xyz;
+    let y = model[1].xyz;
+    let z = model[2].xyz;
+    return mat3x3<f32>(x / dot(x, x), y / dot(y, y), z / dot(z, z));
+}
+
 @vertex
 fn vs_main(model: VertexInput) -> VertexOutput {
     var out: VertexOutput;
-    out.clip_position = camera.view_proj * push_constants.model * vec4<f32>(model.position, 1.0);
+    let world_position = push_constants.model * vec4<f32>(model.position, 1.0);
+    out.clip_position = camera.view_proj * world_position;
+    out.world_position = world_position.xyz;
+    out.normal = normalize(normal_matrix(push_constants.model) * model.normal);
     // Blend vertex color with push constant color
     out.color = vec4<f32>(model.color, 1.0) * push_constants.color;
     return out;
 }
+
+// Per-instance model matrix (4 columns) and color, see `InstanceRaw`
+struct InstanceInput {
+    @location(3) model_0: vec4<f32>,
+    @location(4) model_1: vec4<f32>,
+    @location(5) model_2: vec4<f32>,
+    @location(6) model_3: vec4<f32>,
+    @location(7) color: vec4<f32>,
+};
+
+@vertex
+fn vs_instanced(model: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let instance_model = mat4x4<f32>(
+        instance.model_0,
+        instance.model_1,
+        instance.model_2,
+        instance.model_3,
+    );
+    var out: VertexOutput;
+    let world_position = instance_model * vec4<f32>(model.position, 1.0);
+    out.clip_position = camera.view_proj * world_position;
+    out.world_position = world_position.xyz;
+    out.normal = normalize(normal_matrix(instance_model) * model.normal);
+    // The object color replaces the mesh's baked vertex colors, the push constant color
+    // tints the whole batch (white for solids, black for outlines)
+    out.color = instance.color * push_constants.color;
+    return out;
+}
 "#;
 
 pub const FRAGMENT_SHADER: &str = r#"
+struct LightUniform {
+    direction: vec3<f32>,
+    ambient: f32,
+};
+@group(0) @binding(1)
+var<uniform> light: LightUniform;
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) normal: vec3<f32>,
 };
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let normal = normalize(in.normal);
+    let diffuse = max(dot(normal, -light.direction), light.ambient);
+    return vec4<f32>(in.color.rgb * diffuse, in.color.a);
+}
+
+// Unlit variant for grid lines and wireframe outlines
+@fragment
+fn fs_flat(in: VertexOutput) -> @location(0) vec4<f32> {
     return in.color;
 }
 "#;
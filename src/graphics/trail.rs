@@ -0,0 +1,105 @@
+use crate::graphics::vertex::Vertex;
+use crate::physics::Vector3;
+use std::collections::VecDeque;
+
+pub const TRAIL_COLOR: [f32; 3] = [1.0, 0.6, 0.1];
+pub const DEFAULT_TRAIL_LENGTH: usize = 48;
+
+// Most recent positions of a body, oldest dropped first once full
+pub struct TrailBuffer {
+    pub capacity: usize,
+    points: VecDeque<Vector3>,
+}
+
+impl TrailBuffer {
+    pub fn new(capacity: usize) -> Self {
+        TrailBuffer {
+            capacity,
+            points: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, point: Vector3) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.points.len() == self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    // Oldest first
+    pub fn points(&self) -> impl Iterator<Item = &Vector3> {
+        self.points.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+// Line-list vertices joining consecutive points, fading from black at the oldest to `color`
+pub fn create_trail_line_vertices(points: &[Vector3], color: [f32; 3]) -> Vec<Vertex> {
+    if points.len() < 2 {
+        return Vec::new();
+    }
+    let last = (points.len() - 1) as f32;
+    let vertex = |i: usize| {
+        let fade = i as f32 / last;
+        let p = &points[i];
+        Vertex {
+            position: [p.x, p.y, p.z],
+            color: [color[0] * fade, color[1] * fade, color[2] * fade],
+            normal: [0.0, 1.0, 0.0],
+        }
+    };
+
+    (1..points.len())
+        .flat_map(|i| [vertex(i - 1), vertex(i)])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32) -> Vector3 {
+        Vector3::new(x, 0.0, 0.0)
+    }
+
+    #[test]
+    fn full_trail_drops_the_oldest_point() {
+        let mut trail = TrailBuffer::new(3);
+        for x in 0..5 {
+            trail.push(point(x as f32));
+        }
+        assert_eq!(trail.len(), 3);
+        let xs: Vec<f32> = trail.points().map(|p| p.x).collect();
+        assert_eq!(xs, [2.0, 3.0, 4.0]);
+
+        let mut empty = TrailBuffer::new(0);
+        empty.push(point(1.0));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn trail_lines_fade_in_towards_the_newest_point() {
+        assert!(create_trail_line_vertices(&[point(0.0)], TRAIL_COLOR).is_empty());
+
+        let vertices =
+            create_trail_line_vertices(&[point(0.0), point(1.0), point(2.0)], [1.0, 1.0, 1.0]);
+        let xs: Vec<f32> = vertices.iter().map(|v| v.position[0]).collect();
+        assert_eq!(xs, [0.0, 1.0, 1.0, 2.0]);
+        let fades: Vec<f32> = vertices.iter().map(|v| v.color[0]).collect();
+        assert_eq!(fades, [0.0, 0.5, 0.5, 1.0]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::game::game_engine::GameObject;
 use bytemuck::{Pod, Zeroable};
 
 #[repr(C)]
@@ -5,6 +6,7 @@ use bytemuck::{Pod, Zeroable};
 pub struct Vertex {
     pub position: [f32; 3],
     pub color: [f32; 3],
+    pub normal: [f32; 3],
 }
 
 impl Vertex {
@@ -23,27 +25,181 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x3,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
             ],
         }
     }
 }
 
+// Per-instance data for instanced cube draws, 80 bytes
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct InstanceRaw {
+    pub model: [[f32; 4]; 4],
+    pub color: [f32; 4],
+}
+
+impl InstanceRaw {
+    pub fn new(model: glam::Mat4, color: [f32; 4]) -> Self {
+        Self {
+            model: model.to_cols_array_2d(),
+            color,
+        }
+    }
+
+    // Drawn with the object's own color, see GameObject::new
+    pub fn from_game_object(object: &GameObject) -> Self {
+        Self::new(object.get_model_matrix(), object.color)
+    }
+
+    // Shader locations 3-6 hold the model matrix columns and 7 holds the color
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+            3 => Float32x4,
+            4 => Float32x4,
+            5 => Float32x4,
+            6 => Float32x4,
+            7 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+// 4 vertices per face so each face carries its own outward normal
 pub const CUBE_VERTICES: &[Vertex] = &[
-    Vertex { position: [-0.5, -0.5, -0.5], color: [1.0, 0.0, 0.0] },
-    Vertex { position: [ 0.5, -0.5, -0.5], color: [0.0, 1.0, 0.0] },
-    Vertex { position: [ 0.5,  0.5, -0.5], color: [0.0, 0.0, 1.0] },
-    Vertex { position: [-0.5,  0.5, -0.5], color: [1.0, 1.0, 0.0] },
-    Vertex { position: [-0.5, -0.5,  0.5], color: [1.0, 0.0, 1.0] },
-    Vertex { position: [ 0.5, -0.5,  0.5], color: [0.0, 1.0, 1.0] },
-    Vertex { position: [ 0.5,  0.5,  0.5], color: [1.0, 1.0, 1.0] },
-    Vertex { position: [-0.5,  0.5,  0.5], color: [0.5, 0.5, 0.5] },
+    // Back (-Z)
+    Vertex { position: [-0.5, -0.5, -0.5], color: [1.0, 0.0, 0.0], normal: [ 0.0,  0.0, -1.0] },
+    Vertex { position: [ 0.5, -0.5, -0.5], color: [0.0, 1.0, 0.0], normal: [ 0.0,  0.0, -1.0] },
+    Vertex { position: [ 0.5,  0.5, -0.5], color: [0.0, 0.0, 1.0], normal: [ 0.0,  0.0, -1.0] },
+    Vertex { position: [-0.5,  0.5, -0.5], color: [1.0, 1.0, 0.0], normal: [ 0.0,  0.0, -1.0] },
+    // Right (+X)
+    Vertex { position: [ 0.5, -0.5, -0.5], color: [0.0, 1.0, 0.0], normal: [ 1.0,  0.0,  0.0] },
+    Vertex { position: [ 0.5, -0.5,  0.5], color: [0.0, 1.0, 1.0], normal: [ 1.0,  0.0,  0.0] },
+    Vertex { position: [ 0.5,  0.5,  0.5], color: [1.0, 1.0, 1.0], normal: [ 1.0,  0.0,  0.0] },
+    Vertex { position: [ 0.5,  0.5, -0.5], color: [0.0, 0.0, 1.0], normal: [ 1.0,  0.0,  0.0] },
+    // Front (+Z)
+    Vertex { position: [ 0.5, -0.5,  0.5], color: [0.0, 1.0, 1.0], normal: [ 0.0,  0.0,  1.0] },
+    Vertex { position: [-0.5, -0.5,  0.5], color: [1.0, 0.0, 1.0], normal: [ 0.0,  0.0,  1.0] },
+    Vertex { position: [-0.5,  0.5,  0.5], color: [0.5, 0.5, 0.5], normal: [ 0.0,  0.0,  1.0] },
+    Vertex { position: [ 0.5,  0.5,  0.5], color: [1.0, 1.0, 1.0], normal: [ 0.0,  0.0,  1.0] },
+    // Left (-X)
+    Vertex { position: [-0.5, -0.5,  0.5], color: [1.0, 0.0, 1.0], normal: [-1.0,  0.0,  0.0] },
+    Vertex { position: [-0.5, -0.5, -0.5], color: [1.0, 0.0, 0.0], normal: [-1.0,  0.0,  0.0] },
+    Vertex { position: [-0.5,  0.5, -0.5], color: [1.0, 1.0, 0.0], normal: [-1.0,  0.0,  0.0] },
+    Vertex { position: [-0.5,  0.5,  0.5], color: [0.5, 0.5, 0.5], normal: [-1.0,  0.0,  0.0] },
+    // Top (+Y)
+    Vertex { position: [-0.5,  0.5, -0.5], color: [1.0, 1.0, 0.0], normal: [ 0.0,  1.0,  0.0] },
+    Vertex { position: [ 0.5,  0.5, -0.5], color: [0.0, 0.0, 1.0], normal: [ 0.0,  1.0,  0.0] },
+    Vertex { position: [ 0.5,  0.5,  0.5], color: [1.0, 1.0, 1.0], normal: [ 0.0,  1.0,  0.0] },
+    Vertex { position: [-0.5,  0.5,  0.5], color: [0.5, 0.5, 0.5], normal: [ 0.0,  1.0,  0.0] },
+    // Bottom (-Y)
+    Vertex { position: [-0.5, -0.5,  0.5], color: [1.0, 0.0, 1.0], normal: [ 0.0, -1.0,  0.0] },
+    Vertex { position: [ 0.5, -0.5,  0.5], color: [0.0, 1.0, 1.0], normal: [ 0.0, -1.0,  0.0] },
+    Vertex { position: [ 0.5, -0.5, -0.5], color: [0.0, 1.0, 0.0], normal: [ 0.0, -1.0,  0.0] },
+    Vertex { position: [-0.5, -0.5, -0.5], color: [1.0, 0.0, 0.0], normal: [ 0.0, -1.0,  0.0] },
 ];
 
 pub const CUBE_INDICES: &[u16] = &[
     0, 1, 2, 2, 3, 0,
-    1, 5, 6, 6, 2, 1,
-    5, 4, 7, 7, 6, 5,
-    4, 0, 3, 3, 7, 4,
-    3, 2, 6, 6, 7, 3,
-    4, 5, 1, 1, 0, 4,
-];
\ No newline at end of file
+    4, 5, 6, 6, 7, 4,
+    8, 9, 10, 10, 11, 8,
+    12, 13, 14, 14, 15, 12,
+    16, 17, 18, 18, 19, 16,
+    20, 21, 22, 22, 23, 20,
+];
+// CUBE_VERTICES sized to span +-half_extents in one flat color, for shapes known at build
+// time. Drawn with an unscaled model matrix the face normals stay exact, unlike the unit
+// cube under a non-uniform scale.
+pub fn create_box_mesh(half_extents: glam::Vec3, color: [f32; 3]) -> (Vec<Vertex>, Vec<u16>) {
+    let size = half_extents * 2.0;
+    let vertices = CUBE_VERTICES
+        .iter()
+        .map(|v| Vertex {
+            position: [
+                v.position[0] * size.x,
+                v.position[1] * size.y,
+                v.position[2] * size.z,
+            ],
+            color,
+            normal: v.normal,
+        })
+        .collect();
+    (vertices, CUBE_INDICES.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+
+    #[test]
+    fn cube_faces_have_one_outward_unit_normal() {
+        assert_eq!(CUBE_VERTICES.len(), 24);
+        for face in CUBE_VERTICES.chunks_exact(4) {
+            let normal = Vec3::from(face[0].normal);
+            assert!((normal.length() - 1.0).abs() < 1e-6);
+            for vertex in face {
+                assert_eq!(vertex.normal, face[0].normal);
+                // Every corner of the face sits half a unit out along its normal
+                assert_eq!(Vec3::from(vertex.position).dot(normal), 0.5);
+            }
+        }
+    }
+
+    #[test]
+    fn box_mesh_spans_the_half_extents_in_one_color() {
+        let half_extents = Vec3::new(1.0, 3.0, 0.5);
+        let (vertices, indices) = create_box_mesh(half_extents, [0.2, 0.4, 0.6]);
+        assert_eq!(vertices.len(), CUBE_VERTICES.len());
+        assert_eq!(indices, CUBE_INDICES);
+
+        for (vertex, unit) in vertices.iter().zip(CUBE_VERTICES) {
+            let position = Vec3::from(vertex.position);
+            assert_eq!(position.abs(), half_extents);
+            assert_eq!(position.signum(), Vec3::from(unit.position).signum());
+            assert_eq!(vertex.normal, unit.normal);
+            assert_eq!(vertex.color, [0.2, 0.4, 0.6]);
+        }
+    }
+
+    #[test]
+    fn instance_layout_matches_the_shader_stride() {
+        // 4 model columns and a color, each a vec4<f32> at locations 3-7
+        let layout = InstanceRaw::desc();
+        assert_eq!(std::mem::size_of::<InstanceRaw>(), 80);
+        assert_eq!(layout.array_stride, 80);
+        assert_eq!(layout.step_mode, wgpu::VertexStepMode::Instance);
+        for (i, attribute) in layout.attributes.iter().enumerate() {
+            assert_eq!(attribute.shader_location, 3 + i as u32);
+            assert_eq!(attribute.offset, 16 * i as u64);
+            assert_eq!(attribute.format, wgpu::VertexFormat::Float32x4);
+        }
+        assert_eq!(layout.attributes.len(), 5);
+        assert_eq!(std::mem::offset_of!(InstanceRaw, color), 64);
+    }
+
+    #[test]
+    fn instances_pack_the_object_transform_and_color() {
+        let arena = crate::game::arena::ArenaConfig::default();
+        let paddle = GameObject::from_body(arena.paddle_body(2));
+        let ball = GameObject::from_body(arena.ball_body());
+
+        let raw = InstanceRaw::from_game_object(&paddle);
+        let position = paddle.body.position;
+        // Column-major: scale on the diagonal, translation in the last column
+        assert_eq!(raw.model[0][0], 2.0);
+        assert_eq!(raw.model[1][1], 6.0);
+        assert_eq!(raw.model[2][2], 6.0);
+        assert_eq!(raw.model[3], [position.x, position.y, position.z, 1.0]);
+        assert_eq!(raw.color, paddle.color);
+        assert_ne!(InstanceRaw::from_game_object(&ball).color, raw.color);
+    }
+}
@@ -0,0 +1,5 @@
+pub mod client;
+pub mod game;
+pub mod graphics;
+pub mod physics;
+pub mod server;
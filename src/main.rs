@@ -1,117 +1,100 @@
-mod game;
-mod graphics;
-mod physics;
-mod server;
+use volley::{client, game, graphics, server};
 
-use crate::game::game_engine::{GameObject, GameObjectType};
+use crate::client::input::{
+    paddle_action, paddle_velocity, PADDLE1_KEYS, PADDLE2_KEYS, PADDLE_SPEED,
+};
+use crate::client::interpolation::{StateBuffer, INTERPOLATION_DELAY_MS};
+use crate::client::subscriber::StateSubscriber;
+use crate::client::sync::StateSync;
+use crate::game::ai::PaddleAi;
+use crate::game::arena::ArenaConfig;
+use crate::game::game_engine::{GameObject, GamePhase};
+use crate::game::scene::Scene;
+use crate::graphics::hud::FpsCounter;
+use crate::graphics::minimap::MinimapConfig;
+use crate::graphics::trail::{TrailBuffer, DEFAULT_TRAIL_LENGTH};
 use crate::graphics::{Camera, Renderer};
-use crate::physics::world::World;
-use crate::physics::{RigidBody, Vector3};
-use crate::server::ipc::IPCChannel;
-use crate::server::models::{Action, WorldState};
-use crate::server::server::Engine;
+use crate::server::ipc::{Heartbeat, IPCChannel};
+use crate::server::models::{unix_time_ms, Command};
+use crate::server::recorder::Recorder;
+use crate::server::server::{ActionLimits, Engine};
 use glam::Vec3;
+use log::{error, info, warn};
 use std::collections::HashSet;
 use std::sync::Arc;
 use std::thread;
 use std::time::Instant;
 use winit::{
-    event::{ElementState, Event, WindowEvent},
+    event::{DeviceEvent, ElementState, Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     keyboard::{KeyCode, PhysicalKey},
-    window::WindowBuilder,
+    window::{CursorGrabMode, Window, WindowBuilder},
 };
 
 fn main() {
     pollster::block_on(run());
 }
 
-async fn run() {
-    env_logger::init();
-
-    // Create the world and initial game objects
-    let mut world = World::new_empty();
-
-    let wall_thickness = 1.0;
-    let arena_width = 60.0;
-    let arena_height = 40.0;
-    let arena_depth = 40.0;
-
-    let walls = vec![
-        (
-            Vector3::new(0.0, -arena_height / 2.0, 0.0),
-            Vector3::new(arena_width / 2.0, wall_thickness, arena_depth / 2.0),
-        ),
-        (
-            Vector3::new(0.0, arena_height / 2.0, 0.0),
-            Vector3::new(arena_width / 2.0, wall_thickness, arena_depth / 2.0),
-        ),
-        (
-            Vector3::new(0.0, 0.0, -arena_depth / 2.0),
-            Vector3::new(arena_width / 2.0, arena_height / 2.0, wall_thickness),
-        ),
-        (
-            Vector3::new(0.0, 0.0, arena_depth / 2.0),
-            Vector3::new(arena_width / 2.0, arena_height / 2.0, wall_thickness),
-        ),
-    ];
-
-    for (i, (position, half_extents)) in walls.iter().enumerate() {
-        let wall = RigidBody::from_extents_with_id(
-            format!("wall_{}", i),
-            *position,
-            Vector3::zero(),
-            *half_extents,
-            0.0,
-            1.0,
-            true,
-        );
-        world.add_body(wall);
+// Grab and hide the cursor for mouse look, returns whether it is now grabbed
+fn set_cursor_grab(window: &Window, grab: bool) -> bool {
+    if !grab {
+        let _ = window.set_cursor_grab(CursorGrabMode::None);
+        window.set_cursor_visible(true);
+        return false;
     }
+    // Not every platform supports both modes
+    let grabbed = window
+        .set_cursor_grab(CursorGrabMode::Locked)
+        .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+        .is_ok();
+    window.set_cursor_visible(!grabbed);
+    grabbed
+}
 
-    let paddle1 = RigidBody::from_extents_with_id(
-        "paddle1".to_string(),
-        Vector3::new(-25.0, 0.0, 0.0),
-        Vector3::zero(),
-        Vector3::new(1.0, 3.0, 3.0),
-        1000.0,
-        1.0,
-        false,
-    );
-    world.add_body(paddle1);
-
-    let paddle2 = RigidBody::from_extents_with_id(
-        "paddle2".to_string(),
-        Vector3::new(25.0, 0.0, 0.0),
-        Vector3::zero(),
-        Vector3::new(1.0, 3.0, 3.0),
-        1000.0,
-        1.0,
-        false,
-    );
-    world.add_body(paddle2);
-
-    let ball = RigidBody::from_extents_with_id(
-        "ball".to_string(),
-        Vector3::new(0.0, 0.0, 0.0),
-        Vector3::new(8.0, 4.0, 0.0),
-        Vector3::new(0.5, 0.5, 0.5),
-        1.0,
-        1.0,
-        false,
-    );
-    world.add_body(ball);
+async fn run() {
+    // Info and above unless RUST_LOG says otherwise
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    // Create the world and initial game objects, from VOLLEY_SCENE if it is set. Scoring,
+    // action limits and the minimap use the scene's arena block, or the default arena.
+    let (arena, (world, mut game_objects)) = match std::env::var("VOLLEY_SCENE") {
+        Ok(path) => {
+            let scene = Scene::load_from_file(&path).expect("Failed to load scene");
+            info!("Loaded scene {}", path);
+            (scene.arena.clone().unwrap_or_default(), scene.build())
+        }
+        Err(_) => {
+            let arena = ArenaConfig::default();
+            let built = arena.pong_world();
+            (arena, built)
+        }
+    };
 
-    println!("World has {} bodies", world.bodies.len());
+    info!("World has {} bodies", world.bodies.len());
 
     // Start the server in a background thread
     let server_world = world.clone();
+    let server_arena = arena.clone();
     thread::spawn(move || {
-        let mut engine =
-            Engine::new_server("tcp://127.0.0.1:5555", "tcp://127.0.0.1:5556", server_world)
-                .expect("Failed to create server");
+        let mut engine = Engine::new_server(
+            "tcp://127.0.0.1:5555",
+            "tcp://127.0.0.1:5556",
+            server_world,
+            server_arena,
+        )
+        .expect("Failed to create server");
+        engine.action_limits = Some(ActionLimits::pong(&engine.arena));
+        engine
+            .bind_commands("tcp://127.0.0.1:5557")
+            .expect("Failed to bind command socket");
+        if let Ok(path) = std::env::var("VOLLEY_RECORD") {
+            engine.recorder = Some(Recorder::new(&path).expect("Failed to open recording"));
+            info!("Recording broadcast state to {}", path);
+        }
+        // Drives paddle2 whenever no client is controlling it (e.g. in camera mode)
+        engine.paddle_ai = Some(PaddleAi::new("paddle2".to_string(), 12.0, 0.15));
 
-        println!("Server started on ports 5555 (actions) and 5556 (state)");
+        info!("Server started on ports 5555 (actions), 5556 (state) and 5557 (commands)");
         engine.run().expect("Server failed");
     });
 
@@ -121,8 +104,10 @@ async fn run() {
     // Create client IPC channels
     let action_channel =
         IPCChannel::new_push("tcp://127.0.0.1:5555").expect("Failed to connect to action channel");
-    let state_channel =
-        IPCChannel::new_sub("tcp://127.0.0.1:5556").expect("Failed to connect to state channel");
+    let mut state_subscriber = StateSubscriber::new("tcp://127.0.0.1:5556", 2000)
+        .expect("Failed to connect to state channel");
+    let mut heartbeat = Heartbeat::new(1000);
+    let mut server_stale = false;
 
     // Create window and renderer
     let event_loop = EventLoop::new().unwrap();
@@ -133,27 +118,29 @@ async fn run() {
             .build(&event_loop)
             .unwrap(),
     );
-    let mut renderer = Renderer::new(window.clone()).await;
-    let window_id = renderer.window().id();
-    let window_size = renderer.window().inner_size();
+    let mut renderer = Renderer::new(window.clone(), 4).await;
+    let window_id = window.id();
+    let window_size = window.inner_size();
     let mut camera = Camera::new(window_size.width, window_size.height);
-
-    // Initialize game objects for rendering
-    let mut game_objects = Vec::new();
-    for body in world.bodies.iter() {
-        let obj_type = match body.id.as_str() {
-            "ball" => GameObjectType::Ball,
-            "paddle1" | "paddle2" => GameObjectType::Paddle,
-            _ => GameObjectType::Wall,
-        };
-        game_objects.push(GameObject::new(body.clone(), obj_type));
+    if let Some(sensitivity) = std::env::var("VOLLEY_MOUSE_SENSITIVITY")
+        .ok()
+        .and_then(|value| value.parse().ok())
+    {
+        camera.mouse_sensitivity = sensitivity;
     }
+    let mut cursor_grabbed = false;
 
     let mut last_time = Instant::now();
     let mut keys_pressed = HashSet::<KeyCode>::new();
     let mut camera_mode = false;
     let mut score_player1 = 0;
     let mut score_player2 = 0;
+    let mut phase = GamePhase::Playing;
+    let mut paused = false;
+    let mut fps_counter = FpsCounter::new(0.1);
+    let mut state_buffer = StateBuffer::new(8);
+    let mut state_sync = StateSync::new(); // Full states or keyframes and deltas
+    let mut ball_trail = TrailBuffer::new(DEFAULT_TRAIL_LENGTH);
 
     let _ = event_loop.run(move |event, event_loop_window_target| {
         event_loop_window_target.set_control_flow(ControlFlow::Poll);
@@ -164,12 +151,21 @@ async fn run() {
                 window_id: window_id_ev,
             } if window_id_ev == window_id => match event {
                 WindowEvent::CloseRequested => event_loop_window_target.exit(),
+                WindowEvent::Focused(focused) => {
+                    cursor_grabbed = set_cursor_grab(&window, *focused);
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    ..
+                } if !cursor_grabbed => {
+                    cursor_grabbed = set_cursor_grab(&window, true);
+                }
                 WindowEvent::Resized(physical_size) => {
                     renderer.resize(*physical_size);
                     camera.resize(physical_size.width, physical_size.height);
                 }
                 WindowEvent::ScaleFactorChanged { .. } => {
-                    let new_size = renderer.window().inner_size();
+                    let new_size = window.inner_size();
                     renderer.resize(new_size);
                     camera.resize(new_size.width, new_size.height);
                 }
@@ -177,9 +173,56 @@ async fn run() {
                     if let PhysicalKey::Code(keycode) = event.physical_key {
                         match event.state {
                             ElementState::Pressed => {
+                                if keycode == KeyCode::Escape && cursor_grabbed {
+                                    cursor_grabbed = set_cursor_grab(&window, false);
+                                }
                                 if !event.repeat && keycode == KeyCode::KeyC {
                                     camera_mode = !camera_mode;
                                 }
+                                if !event.repeat && keycode == KeyCode::KeyF {
+                                    camera.set_following(!camera.following);
+                                }
+                                if !event.repeat && keycode == KeyCode::KeyP {
+                                    paused = !paused;
+                                    let command = if paused {
+                                        Command::Pause
+                                    } else {
+                                        Command::Resume
+                                    };
+                                    if let Ok(bytes) = command.to_msgpack() {
+                                        let _ = action_channel.send_bytes(&bytes);
+                                    }
+                                }
+                                // New match from the initial layout: scores 0, paddles and
+                                // ball back where they started
+                                if !event.repeat && keycode == KeyCode::KeyR {
+                                    if let Ok(bytes) = Command::Reset.to_msgpack() {
+                                        let _ = action_channel.send_bytes(&bytes);
+                                    }
+                                }
+                                if !event.repeat && keycode == KeyCode::KeyB {
+                                    renderer.set_debug_aabb(!renderer.is_debug_aabb());
+                                }
+                                if !event.repeat && keycode == KeyCode::KeyM {
+                                    let minimap = match renderer.minimap() {
+                                        Some(_) => None,
+                                        None => Some(MinimapConfig::new(
+                                            arena.half_width(),
+                                            arena.half_depth(),
+                                        )),
+                                    };
+                                    renderer.set_minimap(minimap);
+                                }
+                                if !event.repeat && keycode == KeyCode::KeyG {
+                                    renderer.set_floor_grid(!renderer.is_floor_grid());
+                                }
+                                if !event.repeat && keycode == KeyCode::KeyL {
+                                    let enabled = !renderer.is_wireframe();
+                                    renderer.set_wireframe(enabled);
+                                    if renderer.is_wireframe() != enabled {
+                                        warn!("Wireframe mode unsupported on this GPU");
+                                    }
+                                }
                                 keys_pressed.insert(keycode);
                             }
                             ElementState::Released => {
@@ -190,16 +233,32 @@ async fn run() {
                 }
                 _ => {}
             },
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } if cursor_grabbed => {
+                camera.process_mouse(delta.0, delta.1);
+            }
             Event::AboutToWait => {
                 let now = Instant::now();
                 let dt = (now - last_time).as_secs_f64();
                 last_time = now;
 
-                // Process ALL available state updates (get the latest)
-                let mut latest_state = None;
-                while let Ok(bytes) = state_channel.recv_bytes_nonblocking() {
-                    if let Ok(world_state) = WorldState::from_msgpack(&bytes) {
-                        latest_state = Some(world_state);
+                // Process ALL available state updates into the interpolation buffer
+                for bytes in state_subscriber.drain() {
+                    if let Some(world_state) = state_sync.decode(&bytes) {
+                        heartbeat.observe(world_state.tick_id);
+                        state_buffer.push(world_state);
+                    }
+                }
+                let latest_state =
+                    state_buffer.sample(unix_time_ms().saturating_sub(INTERPOLATION_DELAY_MS));
+                if heartbeat.is_stale() != server_stale {
+                    server_stale = heartbeat.is_stale();
+                    if server_stale {
+                        warn!("No state from server for {:?}", heartbeat.timeout);
+                    } else {
+                        info!("Server state stream resumed");
                     }
                 }
 
@@ -208,89 +267,52 @@ async fn run() {
                     for (i, body) in world_state.bodies.iter().enumerate() {
                         if i < game_objects.len() {
                             game_objects[i].body = body.clone();
+                        } else {
+                            // Bodies spawned by the server, e.g. extra balls
+                            game_objects.push(GameObject::from_body(body.clone()));
                         }
                     }
+                    game_objects.truncate(world_state.bodies.len());
 
                     // Update scores
                     if world_state.score_player1 != score_player1
                         || world_state.score_player2 != score_player2
                     {
+                        // The ball was re-served, don't join the trail across the jump
+                        ball_trail.clear();
                         score_player1 = world_state.score_player1;
                         score_player2 = world_state.score_player2;
-                        println!(
+                        info!(
                             "Score: Player 1: {} - Player 2: {}",
                             score_player1, score_player2
                         );
                     }
-                }
-
-                // Handle paddle movements via IPC
-                let paddle_speed = 15.0;
-                let mut paddle1_vel = Vector3::zero();
-                let mut paddle2_vel = Vector3::zero();
-
-                // Paddle 1 movement
-                if keys_pressed.contains(&KeyCode::KeyW) {
-                    paddle1_vel.x = paddle_speed;
-                } else if keys_pressed.contains(&KeyCode::KeyS) {
-                    paddle1_vel.x = -paddle_speed;
-                }
-
-                if keys_pressed.contains(&KeyCode::Space) {
-                    paddle1_vel.y = paddle_speed;
-                } else if keys_pressed.contains(&KeyCode::ShiftLeft) {
-                    paddle1_vel.y = -paddle_speed;
-                }
+                    if world_state.phase != phase {
+                        phase = world_state.phase;
+                        if let GamePhase::GameOver { winner } = phase {
+                            info!("Game over: Player {} wins", winner);
+                        }
+                    }
 
-                if keys_pressed.contains(&KeyCode::KeyA) {
-                    paddle1_vel.z = -paddle_speed;
-                } else if keys_pressed.contains(&KeyCode::KeyD) {
-                    paddle1_vel.z = paddle_speed;
+                    if let Some(ball) = world_state.bodies.iter().find(|b| b.id == "ball") {
+                        ball_trail.push(ball.position);
+                    }
                 }
 
-                // Always send paddle1 velocity (including zero)
-                if let Some(paddle1) = game_objects.iter().find(|obj| obj.body.id == "paddle1") {
-                    let action = Action {
-                        body_id: "paddle1".to_string(),
-                        velocity: paddle1_vel,
-                        position: paddle1.body.position,
-                        aabb: paddle1.body.aabb.clone(),
-                        mass: paddle1.body.mass,
-                        restitution: paddle1.body.restitution,
-                        dynamic: paddle1.body.dynamic,
-                    };
-                    if let Ok(bytes) = action.to_msgpack() {
+                // Always send paddle velocities (including zero), echoing the newest server
+                // position rather than the interpolated one so the paddle isn't pulled back
+                if let Some(paddle1) = state_buffer.latest_body("paddle1") {
+                    let velocity = paddle_velocity(&keys_pressed, &PADDLE1_KEYS, PADDLE_SPEED);
+                    if let Ok(bytes) = paddle_action(paddle1, velocity).to_msgpack() {
                         let _ = action_channel.send_bytes(&bytes);
                     }
                 }
 
-                // Paddle 2 movement (when not in camera mode)
+                // Arrows turn the camera instead in camera mode
                 if !camera_mode {
-                    if keys_pressed.contains(&KeyCode::ArrowUp) {
-                        paddle2_vel.y = paddle_speed;
-                    } else if keys_pressed.contains(&KeyCode::ArrowDown) {
-                        paddle2_vel.y = -paddle_speed;
-                    }
-
-                    if keys_pressed.contains(&KeyCode::ArrowLeft) {
-                        paddle2_vel.z = paddle_speed;
-                    } else if keys_pressed.contains(&KeyCode::ArrowRight) {
-                        paddle2_vel.z = -paddle_speed;
-                    }
-
-                    // Always send paddle2 velocity (including zero)
-                    if let Some(paddle2) = game_objects.iter().find(|obj| obj.body.id == "paddle2")
-                    {
-                        let action = Action {
-                            body_id: "paddle2".to_string(),
-                            velocity: paddle2_vel,
-                            position: paddle2.body.position,
-                            aabb: paddle2.body.aabb.clone(),
-                            mass: paddle2.body.mass,
-                            restitution: paddle2.body.restitution,
-                            dynamic: paddle2.body.dynamic,
-                        };
-                        if let Ok(bytes) = action.to_msgpack() {
+                    if let Some(paddle2) = state_buffer.latest_body("paddle2") {
+                        let velocity = paddle_velocity(&keys_pressed, &PADDLE2_KEYS, PADDLE_SPEED);
+                        if let Ok(bytes) = paddle_action(paddle2, velocity).to_msgpack() {
                             let _ = action_channel.send_bytes(&bytes);
                         }
                     }
@@ -317,26 +339,50 @@ async fn run() {
                     );
                 }
 
-                // Update camera to follow paddle1
-                if let Some(paddle1) = game_objects.iter().find(|obj| obj.body.id == "paddle1") {
+                // Update camera to follow the ball (F) or ride along with paddle1
+                if camera.following {
+                    if let Some(ball) = game_objects.iter().find(|obj| obj.body.id == "ball") {
+                        let ball_pos = ball.body.position;
+                        camera.follow(
+                            Vec3::new(ball_pos.x, ball_pos.y, ball_pos.z),
+                            Vec3::new(-12.0, 6.0, 0.0),
+                        );
+                    }
+                } else if let Some(paddle1) =
+                    game_objects.iter().find(|obj| obj.body.id == "paddle1")
+                {
                     let paddle1_pos = paddle1.body.position;
                     camera.position =
                         Vec3::new(paddle1_pos.x + 2.0, paddle1_pos.y + 1.0, paddle1_pos.z);
                 }
 
+                let fps = fps_counter.update(dt as f32);
+                let mut hud_lines = vec![
+                    format!("P1 {}  P2 {}", score_player1, score_player2),
+                    format!("FPS {:.0}", fps),
+                ];
+                if let GamePhase::GameOver { winner } = phase {
+                    hud_lines.push(format!("PLAYER {} WINS", winner));
+                }
+                if !state_subscriber.is_connected() {
+                    hud_lines.push("RECONNECTING".to_string());
+                }
+                renderer.set_hud_text(&hud_lines);
+                renderer.set_trail(&ball_trail);
+
                 // Render directly with game_objects - no need to sort for transparency
                 match renderer.render(&camera, &game_objects) {
                     Ok(_) => {}
                     Err(wgpu::SurfaceError::Lost) => {
-                        eprintln!("Surface lost!");
-                        let size = renderer.window().inner_size();
+                        warn!("Surface lost!");
+                        let size = window.inner_size();
                         renderer.resize(size);
                     }
                     Err(wgpu::SurfaceError::OutOfMemory) => {
-                        eprintln!("Out of memory!");
+                        error!("Out of memory!");
                         event_loop_window_target.exit();
                     }
-                    Err(e) => eprintln!("Render error: {:?}", e),
+                    Err(e) => error!("Render error: {:?}", e),
                 }
             }
             _ => {}
@@ -1,10 +0,0 @@
-use crate::physics::object::RigidBody;
-use crate::physics::world::World;
-
-struct Physics {
-    objects: Vec<RigidBody>,
-    world: World,
-}
-impl Physics {
-    pub fn new() {}
-}
@@ -0,0 +1,145 @@
+use super::world::World;
+use crate::game::game_engine::GamePhase;
+use crate::server::models::{unix_time_ms, Action, WorldState};
+use crate::server::server::apply_action;
+use rayon::prelude::*;
+
+// Many independent worlds stepped together, for vectorized RL environments
+pub struct BatchWorld {
+    pub worlds: Vec<World>,
+    initial_worlds: Vec<World>,
+    tick_ids: Vec<u64>, // Per world, like Engine::tick_id it keeps counting across resets
+}
+
+impl BatchWorld {
+    pub fn new(worlds: Vec<World>) -> Self {
+        let tick_ids = vec![0; worlds.len()];
+        BatchWorld {
+            initial_worlds: worlds.clone(),
+            worlds,
+            tick_ids,
+        }
+    }
+
+    // Build `count` copies of the same template world
+    pub fn from_template(template: &World, count: usize) -> Self {
+        Self::new(vec![template.clone(); count])
+    }
+
+    pub fn len(&self) -> usize {
+        self.worlds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.worlds.is_empty()
+    }
+
+    pub fn step_all(&mut self, dt: f64) {
+        self.worlds
+            .par_iter_mut()
+            .zip(self.tick_ids.par_iter_mut())
+            .for_each(|(world, tick_id)| {
+                world.step(dt);
+                *tick_id += 1;
+            });
+    }
+
+    // Route each action to the env at its index, applied as the engine applies them; out
+    // of range indices and unknown bodies are ignored
+    pub fn apply_actions(&mut self, actions: &[(usize, Action)]) {
+        for (env_index, action) in actions {
+            if let Some(world) = self.worlds.get_mut(*env_index) {
+                let _ = apply_action(world, action.clone());
+            }
+        }
+    }
+
+    pub fn reset_all(&mut self) {
+        self.worlds = self.initial_worlds.clone();
+    }
+
+    pub fn states(&self) -> Vec<WorldState> {
+        self.worlds
+            .iter()
+            .zip(&self.tick_ids)
+            .map(|(world, &tick_id)| WorldState {
+                bodies: world.bodies.clone(),
+                time: world.sim_time() as f32,
+                score_player1: 0,
+                score_player2: 0,
+                tick_id,
+                timestamp_ms: unix_time_ms(),
+                phase: GamePhase::Playing,
+                tick_count: world.tick_count(),
+                sim_time: world.sim_time(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::arena::ArenaConfig;
+    use crate::physics::Vector3;
+
+    fn paddle_velocity_action(world: &World, velocity: Vector3) -> Action {
+        let paddle = world.get_body("paddle1").unwrap();
+        Action {
+            body_id: paddle.id.clone(),
+            velocity,
+            position: paddle.position,
+            aabb: paddle.aabb.clone(),
+            mass: paddle.mass,
+            restitution: paddle.restitution,
+            dynamic: true,
+            friction: None,
+        }
+    }
+
+    #[test]
+    fn actions_only_reach_their_own_env() {
+        let (template, _) = ArenaConfig::default().pong_world();
+        let mut batch = BatchWorld::from_template(&template, 64);
+        let mut reference = template.clone();
+
+        let action = paddle_velocity_action(&template, Vector3::new(0.0, 5.0, 0.0));
+        batch.apply_actions(&[(3, action)]);
+        for _ in 0..10 {
+            batch.step_all(1.0 / 60.0);
+            reference.step(1.0 / 60.0);
+        }
+
+        let moved = batch.worlds[3].get_body("paddle1").unwrap();
+        let untouched = batch.worlds[5].get_body("paddle1").unwrap();
+        let expected = reference.get_body("paddle1").unwrap();
+        assert!(moved.position.y > expected.position.y);
+        assert_eq!(untouched.position, expected.position);
+        assert_eq!(untouched.velocity, expected.velocity);
+        for (body, expected) in batch.worlds[5].bodies.iter().zip(&reference.bodies) {
+            assert_eq!(body.position, expected.position, "{}", body.id);
+        }
+    }
+
+    #[test]
+    fn states_carry_each_worlds_own_clock() {
+        let (template, _) = ArenaConfig::default().pong_world();
+        let mut batch = BatchWorld::from_template(&template, 2);
+        for _ in 0..3 {
+            batch.step_all(0.5);
+        }
+        batch.worlds[1].step(0.5); // One extra step outside the batch
+
+        let states = batch.states();
+        assert_eq!(states[0].tick_id, 3);
+        assert_eq!(states[0].time, 1.5);
+        assert_eq!(states[1].time, 2.0);
+        assert_eq!(states[1].tick_count, 4);
+        assert!(states[0].timestamp_ms > 0);
+
+        batch.reset_all();
+        let states = batch.states();
+        assert_eq!(states[0].time, 0.0);
+        assert_eq!(states[0].tick_id, 3);
+    }
+}
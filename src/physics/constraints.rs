@@ -0,0 +1,113 @@
+use super::object::RigidBody;
+use serde::{Deserialize, Serialize};
+
+// Keeps the centres of two bodies `rest_length` apart. Each step removes `stiffness`
+// (0..=1) of the remaining error, so 1.0 is a rigid rod and small values an elastic leash.
+// Static and kinematic bodies never move and act as anchors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DistanceConstraint {
+    pub body_a: String,
+    pub body_b: String,
+    pub rest_length: f32,
+    pub stiffness: f32,
+}
+
+impl DistanceConstraint {
+    pub fn new(body_a: String, body_b: String, rest_length: f32, stiffness: f32) -> Self {
+        DistanceConstraint {
+            body_a,
+            body_b,
+            rest_length,
+            stiffness,
+        }
+    }
+
+    // Impulse along the line between the centres so their relative speed closes
+    // stiffness * error over the next `dt`; false if nothing moved
+    pub fn solve(&self, a: &mut RigidBody, b: &mut RigidBody, dt: f32) -> bool {
+        let (inv_mass_a, inv_mass_b) = (a.inverse_mass(), b.inverse_mass());
+        let total_inv_mass = inv_mass_a + inv_mass_b;
+        if total_inv_mass == 0.0 || dt <= 0.0 {
+            return false;
+        }
+
+        let delta = b.position - a.position;
+        let length = delta.length();
+        if length < 1e-6 {
+            return false; // Coincident centres, no direction to push along
+        }
+        let normal = delta * (1.0 / length);
+
+        // Positive while the bodies are moving apart
+        let relative_speed = (b.velocity.x - a.velocity.x) * normal.x
+            + (b.velocity.y - a.velocity.y) * normal.y
+            + (b.velocity.z - a.velocity.z) * normal.z;
+        let error = length - self.rest_length;
+        if error.abs() < 1e-4 && relative_speed.abs() < 1e-4 {
+            return false; // Satisfied, leave sleeping bodies asleep
+        }
+        let target_speed = -error * self.stiffness.clamp(0.0, 1.0) / dt;
+        let impulse = normal * ((target_speed - relative_speed) / total_inv_mass);
+
+        a.velocity = a.velocity + impulse * -inv_mass_a;
+        b.velocity = b.velocity + impulse * inv_mass_b;
+        if a.is_dynamic() {
+            a.wake();
+        }
+        if b.is_dynamic() {
+            b.wake();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::object::Vec3;
+    use crate::physics::World;
+
+    fn cube(id: &str, position: Vec3, is_static: bool) -> RigidBody {
+        RigidBody::from_extents_with_id(
+            id.to_string(),
+            position,
+            Vec3::zero(),
+            Vec3::new(0.5, 0.5, 0.5),
+            1.0,
+            1.0,
+            is_static,
+        )
+    }
+
+    #[test]
+    fn bob_settles_at_the_rest_length_from_a_static_anchor() {
+        let mut world = World::new_empty();
+        world.add_body(cube("anchor", Vec3::zero(), true));
+        world.add_body(cube("bob", Vec3::new(3.0, 1.0, 0.0), false));
+        world.add_constraint(DistanceConstraint::new(
+            "anchor".to_string(),
+            "bob".to_string(),
+            2.0,
+            0.5,
+        ));
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let anchor = world.get_body("anchor").unwrap();
+        assert_eq!(anchor.position, Vec3::zero());
+        assert_eq!(anchor.velocity, Vec3::zero());
+        let bob = world.get_body("bob").unwrap().position;
+        let length = bob.length();
+        assert!((length - 2.0).abs() < 1e-3, "settled at {length}");
+    }
+
+    #[test]
+    fn two_static_endpoints_are_left_alone() {
+        let constraint = DistanceConstraint::new("a".to_string(), "b".to_string(), 1.0, 1.0);
+        let mut a = cube("a", Vec3::zero(), true);
+        let mut b = cube("b", Vec3::new(5.0, 0.0, 0.0), true);
+        assert!(!constraint.solve(&mut a, &mut b, 1.0 / 60.0));
+        assert_eq!((a.velocity, b.velocity), (Vec3::zero(), Vec3::zero()));
+    }
+}
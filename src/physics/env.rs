@@ -0,0 +1,344 @@
+use super::object::Vec3;
+use super::world::World;
+use crate::game::arena::ArenaConfig;
+use crate::game::game_engine::{GameState, MissingPaddle};
+
+// Gym-style wrapper around a Pong world, the RL agent controls paddle1
+
+#[derive(Debug, Clone, Copy)]
+pub struct PaddleAction {
+    pub velocity_y: f32,
+    pub velocity_z: f32,
+}
+
+// Shape and per-dimension bounds of `PongEnv::observation`, enough to build a gym Box
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObservationSpec {
+    pub low: Vec<f32>,
+    pub high: Vec<f32>,
+}
+
+impl ObservationSpec {
+    pub fn len(&self) -> usize {
+        self.low.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.low.is_empty()
+    }
+}
+
+// Declared per-axis bound on ball velocity, the paddle speed limit of ActionLimits::pong
+pub const OBSERVATION_MAX_SPEED: f32 = 30.0;
+
+// Discrete actions an agent can pick instead of a continuous PaddleAction
+pub const DISCRETE_ACTIONS: u32 = 5; // Stay, up, down, left (-z), right (+z)
+pub const DISCRETE_PADDLE_SPEED: f32 = 15.0;
+
+// Continuous actions are per-axis velocities within [-max_speed, max_speed]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionSpec {
+    pub discrete_actions: u32,
+    pub max_speed: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionDecodeError {
+    OutOfRange(u32),
+}
+
+impl std::fmt::Display for ActionDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ActionDecodeError::OutOfRange(action) => write!(
+                f,
+                "discrete action {} is out of range 0..{}",
+                action, DISCRETE_ACTIONS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ActionDecodeError {}
+
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub observation: Vec<f32>,
+    pub reward: f32,
+    pub done: bool,
+}
+
+pub struct PongEnv {
+    pub world: World,
+    pub arena: ArenaConfig,
+    pub game_state: GameState,
+    pub win_score: u32,
+    pub dt: f64,
+    pub discrete_speed: f32, // Paddle speed for decoded discrete actions
+    initial_world: World,
+}
+
+impl PongEnv {
+    // Fails if either paddle is missing, the agent would otherwise drive some other body
+    pub fn new(world: World, win_score: u32) -> Result<Self, MissingPaddle> {
+        let game_state = GameState::for_world(&world)?;
+
+        Ok(Self {
+            initial_world: world.clone(),
+            world,
+            arena: ArenaConfig::default(),
+            game_state,
+            win_score,
+            dt: 1.0 / 120.0, // Same rate as the server physics
+            discrete_speed: DISCRETE_PADDLE_SPEED,
+        })
+    }
+
+    pub fn reset(&mut self) -> Vec<f32> {
+        self.world = self.initial_world.clone();
+        self.game_state.score_player1 = 0;
+        self.game_state.score_player2 = 0;
+        self.observation()
+    }
+
+    pub fn step(&mut self, action: PaddleAction) -> StepResult {
+        let paddle1 = &mut self.world.bodies[self.game_state.paddle1_index];
+        paddle1.velocity.y = action.velocity_y;
+        paddle1.velocity.z = action.velocity_z;
+        paddle1.wake();
+
+        self.world.step(self.dt);
+
+        // Check for scoring, a world without a ball never scores
+        let mut reward = 0.0;
+        let scorer = self
+            .game_state
+            .ball_index()
+            .and_then(|i| self.arena.scorer_for(&self.world.bodies[i].position));
+        match scorer {
+            Some(2) => {
+                self.game_state.score_player2 += 1;
+                reward = -1.0;
+                self.reset_ball(2);
+            }
+            Some(1) => {
+                self.game_state.score_player1 += 1;
+                reward = 1.0;
+                self.reset_ball(1);
+            }
+            _ => {}
+        }
+
+        let done = self.game_state.score_player1 >= self.win_score
+            || self.game_state.score_player2 >= self.win_score;
+
+        StepResult {
+            observation: self.observation(),
+            reward,
+            done,
+        }
+    }
+
+    // Deterministic serve so episodes are reproducible
+    fn reset_ball(&mut self, scorer: u8) {
+        let Some(ball_index) = self.game_state.ball_index() else {
+            return;
+        };
+        let ball = &mut self.world.bodies[ball_index];
+        ball.update_position(0.0, 0.0, 0.0);
+        ball.velocity = if scorer == 1 {
+            Vec3::new(-7.0, 4.0, 0.0)
+        } else {
+            Vec3::new(7.0, 4.0, 0.0)
+        };
+        ball.wake();
+    }
+
+    pub fn action_spec(&self) -> ActionSpec {
+        ActionSpec {
+            discrete_actions: DISCRETE_ACTIONS,
+            max_speed: OBSERVATION_MAX_SPEED,
+        }
+    }
+
+    // 0 stay, 1 up, 2 down, 3 left, 4 right, each at discrete_speed
+    pub fn decode_action(&self, action: u32) -> Result<PaddleAction, ActionDecodeError> {
+        let (velocity_y, velocity_z) = match action {
+            0 => (0.0, 0.0),
+            1 => (self.discrete_speed, 0.0),
+            2 => (-self.discrete_speed, 0.0),
+            3 => (0.0, -self.discrete_speed),
+            4 => (0.0, self.discrete_speed),
+            _ => return Err(ActionDecodeError::OutOfRange(action)),
+        };
+        Ok(PaddleAction {
+            velocity_y,
+            velocity_z,
+        })
+    }
+
+    // Positions lie inside the arena, velocities within OBSERVATION_MAX_SPEED per axis
+    pub fn observation_spec(&self) -> ObservationSpec {
+        let bounds = self.arena.bounds();
+        let speed = Vec3::new(
+            OBSERVATION_MAX_SPEED,
+            OBSERVATION_MAX_SPEED,
+            OBSERVATION_MAX_SPEED,
+        );
+        let (low, high): (Vec<_>, Vec<_>) = [
+            (bounds.min, bounds.max), // paddle1
+            (bounds.min, bounds.max), // paddle2
+            (bounds.min, bounds.max), // ball
+            (speed * -1.0, speed),    // ball velocity
+        ]
+        .iter()
+        .flat_map(|(low, high)| [(low.x, high.x), (low.y, high.y), (low.z, high.z)])
+        .unzip();
+        ObservationSpec { low, high }
+    }
+
+    // Layout: paddle1 xyz, paddle2 xyz, ball xyz, ball velocity xyz; the ball is all zero
+    // when there is none
+    pub fn observation(&self) -> Vec<f32> {
+        let paddle1 = &self.world.bodies[self.game_state.paddle1_index];
+        let paddle2 = &self.world.bodies[self.game_state.paddle2_index];
+        let (ball_position, ball_velocity) = match self.game_state.ball_index() {
+            Some(i) => (self.world.bodies[i].position, self.world.bodies[i].velocity),
+            None => (Vec3::zero(), Vec3::zero()),
+        };
+
+        [
+            paddle1.position,
+            paddle2.position,
+            ball_position,
+            ball_velocity,
+        ]
+        .iter()
+        .flat_map(|v| [v.x, v.y, v.z])
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> PongEnv {
+        let (world, _) = ArenaConfig::default().pong_world();
+        PongEnv::new(world, 3).unwrap()
+    }
+
+    fn rollout(env: &mut PongEnv) -> Vec<Vec<f32>> {
+        let mut observations = vec![env.reset()];
+        for i in 0..200 {
+            let action = PaddleAction {
+                velocity_y: if i % 40 < 20 { 10.0 } else { -10.0 },
+                velocity_z: 0.0,
+            };
+            observations.push(env.step(action).observation);
+        }
+        observations
+    }
+
+    // Put the ball just past a scoring plane, still heading out of the arena
+    fn send_ball_past(env: &mut PongEnv, x: f32) {
+        let ball_index = env.game_state.ball_index().unwrap();
+        let ball = &mut env.world.bodies[ball_index];
+        ball.update_position(x, 0.0, 0.0);
+        ball.velocity = Vec3::new(x.signum() * 5.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn reset_is_deterministic() {
+        let mut env = env();
+        let first = rollout(&mut env);
+        let second = rollout(&mut env);
+        assert_eq!(first, second);
+        assert_eq!(env.reset(), first[0]);
+        assert_eq!(env.game_state.score_player1, 0);
+        assert_eq!(env.game_state.score_player2, 0);
+    }
+
+    #[test]
+    fn reward_follows_who_scored() {
+        let mut env = env();
+        env.reset();
+        let stay = PaddleAction {
+            velocity_y: 0.0,
+            velocity_z: 0.0,
+        };
+
+        let plane = env.arena.scoring_plane_x();
+
+        send_ball_past(&mut env, plane + 1.0);
+        let result = env.step(stay);
+        assert_eq!(result.reward, 1.0);
+        assert_eq!(env.game_state.score_player1, 1);
+
+        send_ball_past(&mut env, -plane - 1.0);
+        let result = env.step(stay);
+        assert_eq!(result.reward, -1.0);
+        assert_eq!(env.game_state.score_player2, 1);
+        assert!(!result.done);
+    }
+
+    #[test]
+    fn observation_spec_matches_the_observation() {
+        let env = env();
+        let spec = env.observation_spec();
+        let observation = env.observation();
+        assert_eq!(spec.len(), 12);
+        assert_eq!(spec.high.len(), spec.len());
+        assert_eq!(observation.len(), spec.len());
+        for (i, value) in observation.iter().enumerate() {
+            assert!(
+                spec.low[i] <= *value && *value <= spec.high[i],
+                "dimension {i}"
+            );
+        }
+    }
+
+    #[test]
+    fn discrete_actions_decode_to_paddle_velocities() {
+        let mut env = env();
+        env.discrete_speed = 12.0;
+        let expected = [
+            (0.0, 0.0),
+            (12.0, 0.0),
+            (-12.0, 0.0),
+            (0.0, -12.0),
+            (0.0, 12.0),
+        ];
+        assert_eq!(env.action_spec().discrete_actions as usize, expected.len());
+        for (action, (velocity_y, velocity_z)) in expected.into_iter().enumerate() {
+            let decoded = env.decode_action(action as u32).unwrap();
+            assert_eq!(
+                (decoded.velocity_y, decoded.velocity_z),
+                (velocity_y, velocity_z)
+            );
+        }
+    }
+
+    #[test]
+    fn out_of_range_discrete_actions_are_rejected() {
+        let env = env();
+        for action in [DISCRETE_ACTIONS, u32::MAX] {
+            assert_eq!(
+                env.decode_action(action).unwrap_err(),
+                ActionDecodeError::OutOfRange(action)
+            );
+        }
+    }
+
+    #[test]
+    fn world_without_paddles_is_rejected() {
+        let (mut world, _) = ArenaConfig::default().pong_world();
+        let bodies = world.bodies.clone();
+        world.set_bodies(bodies.into_iter().filter(|b| b.id != "paddle2").collect());
+        assert_eq!(
+            PongEnv::new(world, 3).err(),
+            Some(MissingPaddle("paddle2".to_string()))
+        );
+        assert!(PongEnv::new(World::new_empty(), 3).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use super::object::{Vec3, AABB};
+use serde::{Deserialize, Serialize};
+
+// Region-based accelerations applied to awake dynamic bodies before each step's
+// integration. Like gravity they ignore mass, so every body in a field accelerates alike.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ForceField {
+    Uniform(Vec3),
+    // Along the line from `center`, positive pushes outwards and negative pulls in
+    Radial { center: Vec3, strength: f32 },
+    // Only bodies whose centre is inside `region`
+    Box { region: AABB, force: Vec3 },
+}
+
+impl ForceField {
+    // Acceleration on a body centred at `position`, zero outside the field
+    pub fn acceleration_at(&self, position: &Vec3) -> Vec3 {
+        match self {
+            ForceField::Uniform(force) => *force,
+            ForceField::Radial { center, strength } => {
+                let offset = *position - *center;
+                let distance = offset.length();
+                if distance < 1e-6 {
+                    Vec3::zero() // No direction at the centre itself
+                } else {
+                    offset * (strength / distance)
+                }
+            }
+            ForceField::Box { region, force } => {
+                if region.contains_point(position) {
+                    *force
+                } else {
+                    Vec3::zero()
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::{RigidBody, World};
+
+    #[test]
+    fn box_field_cancelling_gravity_makes_bodies_hover() {
+        let gravity = Vec3::new(0.0, -9.81, 0.0);
+        let region = AABB::new(Vec3::new(-2.0, -1.0, -2.0), Vec3::new(2.0, 1.0, 2.0));
+        let lift = ForceField::Box {
+            region,
+            force: Vec3::new(0.0, 9.81, 0.0),
+        };
+        assert_eq!(
+            lift.acceleration_at(&Vec3::new(3.0, 0.0, 0.0)),
+            Vec3::zero()
+        );
+
+        let mut world = World::new_empty();
+        world.sleep_after = 0.0; // Keep the hovering body integrating
+        for (id, x) in [("inside", 0.0), ("outside", 10.0)] {
+            world.add_body(RigidBody::from_extents_with_id(
+                id.to_string(),
+                Vec3::new(x, 0.0, 0.0),
+                Vec3::zero(),
+                Vec3::new(0.5, 0.5, 0.5),
+                1.0,
+                1.0,
+                false,
+            ));
+        }
+        world.add_force_field(ForceField::Uniform(gravity));
+        world.add_force_field(lift);
+
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        let inside = world.get_body("inside").unwrap();
+        assert_eq!(inside.position, Vec3::zero());
+        assert_eq!(inside.velocity, Vec3::zero());
+        assert!(world.get_body("outside").unwrap().position.y < -1.0);
+    }
+}
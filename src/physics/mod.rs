@@ -1,5 +1,9 @@
-pub mod api;
+pub mod batch;
+pub mod constraints;
+pub mod env;
+pub mod force_field;
 pub mod object;
+pub mod plane;
 pub mod world;
 
 // Re-export commonly used types
@@ -1,6 +1,7 @@
+use glam::Quat;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Vec3 {
     pub x: f32,
     pub y: f32,
@@ -22,6 +23,36 @@ impl Vec3 {
         self.y = y;
         self.z = z;
     }
+    // Per-component |a - b| <= epsilon, so -0.0 equals 0.0 and NaN equals nothing
+    pub fn approx_eq(&self, other: &Vec3, epsilon: f32) -> bool {
+        (self.x - other.x).abs() <= epsilon
+            && (self.y - other.y).abs() <= epsilon
+            && (self.z - other.z).abs() <= epsilon
+    }
+    pub fn dot(&self, other: &Vec3) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+    // Straight-line blend, t is clamped to 0..=1 so it never extrapolates
+    pub fn lerp(&self, other: &Vec3, t: f32) -> Vec3 {
+        let t = t.clamp(0.0, 1.0);
+        Vec3::new(
+            self.x + (other.x - self.x) * t,
+            self.y + (other.y - self.y) * t,
+            self.z + (other.z - self.z) * t,
+        )
+    }
+    // Step towards `target` by at most `max_delta`, landing exactly on it when closer
+    pub fn move_toward(&self, target: &Vec3, max_delta: f32) -> Vec3 {
+        let delta = *target - *self;
+        let distance = delta.length();
+        if distance <= max_delta || distance == 0.0 {
+            return *target;
+        }
+        *self + delta * (max_delta / distance)
+    }
 }
 
 impl std::ops::Add for Vec3 {
@@ -31,6 +62,13 @@ impl std::ops::Add for Vec3 {
     }
 }
 
+impl std::ops::Sub for Vec3 {
+    type Output = Vec3;
+    fn sub(self, other: Vec3) -> Vec3 {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
 impl std::ops::Mul<f32> for Vec3 {
     type Output = Vec3;
     fn mul(self, scalar: f32) -> Vec3 {
@@ -38,7 +76,7 @@ impl std::ops::Mul<f32> for Vec3 {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AABB {
     pub min: Vec3,
     pub max: Vec3,
@@ -83,18 +121,271 @@ impl AABB {
         let size = self.get_size();
         *self = AABB::from_center_size(center, &size);
     }
+
+    // Touching faces count as intersecting
+    pub fn intersects(&self, other: &AABB) -> bool {
+        self.max.x >= other.min.x
+            && self.min.x <= other.max.x
+            && self.max.y >= other.min.y
+            && self.min.y <= other.max.y
+            && self.max.z >= other.min.z
+            && self.min.z <= other.max.z
+    }
+
+    // Points on the boundary are inside
+    pub fn contains_point(&self, p: &Vec3) -> bool {
+        p.x >= self.min.x
+            && p.x <= self.max.x
+            && p.y >= self.min.y
+            && p.y <= self.max.y
+            && p.z >= self.min.z
+            && p.z <= self.max.z
+    }
+
+    // Point of the box nearest to `p`, `p` itself when it is inside
+    pub fn closest_point(&self, p: &Vec3) -> Vec3 {
+        Vec3::new(
+            p.x.clamp(self.min.x, self.max.x),
+            p.y.clamp(self.min.y, self.max.y),
+            p.z.clamp(self.min.z, self.max.z),
+        )
+    }
+
+    // Length of the shortest gap between the boxes, 0.0 if they touch or overlap
+    pub fn distance_to(&self, other: &AABB) -> f32 {
+        let gap = |min_a: f32, max_a: f32, min_b: f32, max_b: f32| {
+            (min_b - max_a).max(min_a - max_b).max(0.0)
+        };
+        Vec3::new(
+            gap(self.min.x, self.max.x, other.min.x, other.max.x),
+            gap(self.min.y, self.max.y, other.min.y, other.max.y),
+            gap(self.min.z, self.max.z, other.min.z, other.max.z),
+        )
+        .length()
+    }
+
+    // Smallest box enclosing both
+    pub fn merge(&self, other: &AABB) -> AABB {
+        AABB::new(
+            Vec3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            Vec3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        )
+    }
+}
+// Collision filtering: two bodies collide only if each one's layer is in the other's mask
+pub const LAYER_DEFAULT: u32 = 1;
+pub const MASK_ALL: u32 = u32::MAX;
+
+fn default_layer() -> u32 {
+    LAYER_DEFAULT
+}
+fn default_mask() -> u32 {
+    MASK_ALL
+}
+fn default_active() -> bool {
+    true
+}
+fn default_orientation() -> [f32; 4] {
+    Quat::IDENTITY.to_array()
+}
+
+// Static bodies never move, kinematic bodies integrate their velocity but ignore impulses
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyKind {
+    Static,
+    Dynamic,
+    Kinematic,
+}
+
+impl BodyKind {
+    pub fn from_dynamic(dynamic: bool) -> Self {
+        if dynamic {
+            BodyKind::Dynamic
+        } else {
+            BodyKind::Static
+        }
+    }
 }
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "RigidBodyWire", into = "RigidBodyWire")]
 pub struct RigidBody {
     pub id: String,
     pub position: Vec3,
     pub velocity: Vec3,
-    pub dynamic: bool,
+    pub kind: BodyKind,
     pub aabb: AABB,
     pub mass: f32,
+    pub inv_mass: f32, // Cached 1 / mass, 0 unless dynamic with mass > 0; see set_mass, set_kind
     pub restitution: f32,
+    // Contacts closing slower than this don't bounce; the highest of the two bodies' and
+    // the world's thresholds applies
+    pub restitution_threshold: f32,
+    pub friction: f32, // Coulomb coefficient, 0 keeps contacts frictionless
+    pub layer: u32,
+    pub mask: u32,
+    pub is_sensor: bool, // Reports overlaps as collision events but is never resolved
+    pub sleeping: bool,  // Dynamic bodies at rest skip integration until woken
+    pub active: bool,    // Inactive bodies are skipped by integration, collisions and rendering
+    pub sleep_timer: f32, // Seconds spent below the world's sleep threshold
+    // Orientation is visual and integrated from angular_velocity (rad/s), collisions never
+    // change it and aabb stays axis-aligned around the unrotated extents
+    pub orientation: Quat,
+    pub angular_velocity: Vec3,
+}
+
+// Wire layout keeps the legacy `dynamic` flag (true for anything that moves) for
+// older clients, `kind` is optional on input and derived from `dynamic` when absent
+#[derive(Serialize, Deserialize)]
+struct RigidBodyWire {
+    id: String,
+    position: Vec3,
+    velocity: Vec3,
+    dynamic: bool,
+    #[serde(default)]
+    kind: Option<BodyKind>,
+    aabb: AABB,
+    mass: f32,
+    restitution: f32,
+    #[serde(default)]
+    restitution_threshold: f32,
+    #[serde(default)]
+    friction: f32,
+    #[serde(default = "default_layer")]
+    layer: u32,
+    #[serde(default = "default_mask")]
+    mask: u32,
+    #[serde(default)]
+    is_sensor: bool,
+    #[serde(default)]
+    sleeping: bool,
+    #[serde(default = "default_active")]
+    active: bool,
+    #[serde(default = "default_orientation")]
+    orientation: [f32; 4], // x, y, z, w
+    #[serde(default = "Vec3::zero")]
+    angular_velocity: Vec3,
+}
+
+impl From<RigidBodyWire> for RigidBody {
+    fn from(wire: RigidBodyWire) -> Self {
+        let kind = wire
+            .kind
+            .unwrap_or_else(|| BodyKind::from_dynamic(wire.dynamic));
+        RigidBody {
+            id: wire.id,
+            position: wire.position,
+            velocity: wire.velocity,
+            kind,
+            aabb: wire.aabb,
+            mass: wire.mass,
+            inv_mass: inverse_mass_of(kind, wire.mass),
+            restitution: wire.restitution,
+            restitution_threshold: wire.restitution_threshold,
+            friction: wire.friction,
+            layer: wire.layer,
+            mask: wire.mask,
+            is_sensor: wire.is_sensor,
+            sleeping: wire.sleeping,
+            active: wire.active,
+            sleep_timer: 0.0,
+            orientation: Quat::from_array(wire.orientation),
+            angular_velocity: wire.angular_velocity,
+        }
+    }
+}
+
+impl From<RigidBody> for RigidBodyWire {
+    fn from(body: RigidBody) -> Self {
+        RigidBodyWire {
+            id: body.id,
+            position: body.position,
+            velocity: body.velocity,
+            dynamic: body.kind != BodyKind::Static,
+            kind: Some(body.kind),
+            aabb: body.aabb,
+            mass: body.mass,
+            restitution: body.restitution,
+            restitution_threshold: body.restitution_threshold,
+            friction: body.friction,
+            layer: body.layer,
+            mask: body.mask,
+            is_sensor: body.is_sensor,
+            sleeping: body.sleeping,
+            active: body.active,
+            orientation: body.orientation.to_array(),
+            angular_velocity: body.angular_velocity,
+        }
+    }
+}
+
+// Only dynamic bodies can be pushed; non-positive mass is treated as infinite
+fn inverse_mass_of(kind: BodyKind, mass: f32) -> f32 {
+    if kind == BodyKind::Dynamic && mass > 0.0 {
+        1.0 / mass
+    } else {
+        0.0
+    }
 }
+
 impl RigidBody {
+    // True if anything that goes over the wire differs; sleep_timer is server-side only
+    pub fn differs_from(&self, other: &RigidBody) -> bool {
+        self.id != other.id
+            || self.position != other.position
+            || self.velocity != other.velocity
+            || self.kind != other.kind
+            || self.aabb != other.aabb
+            || self.mass != other.mass
+            || self.restitution != other.restitution
+            || self.restitution_threshold != other.restitution_threshold
+            || self.friction != other.friction
+            || self.layer != other.layer
+            || self.mask != other.mask
+            || self.is_sensor != other.is_sensor
+            || self.sleeping != other.sleeping
+            || self.active != other.active
+            || self.orientation != other.orientation
+            || self.angular_velocity != other.angular_velocity
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.kind == BodyKind::Static
+    }
+    // Only dynamic bodies respond to impulses
+    pub fn is_dynamic(&self) -> bool {
+        self.kind == BodyKind::Dynamic
+    }
+    pub fn set_kind(&mut self, kind: BodyKind) {
+        self.kind = kind;
+        self.inv_mass = inverse_mass_of(kind, self.mass);
+    }
+    pub fn set_mass(&mut self, mass: f32) {
+        self.mass = mass;
+        self.inv_mass = inverse_mass_of(self.kind, mass);
+    }
+    // What the solver uses: only dynamic bodies with a positive mass can be pushed
+    pub fn inverse_mass(&self) -> f32 {
+        if self.is_dynamic() {
+            self.inv_mass
+        } else {
+            0.0
+        }
+    }
+    // Apply a legacy `dynamic` flag without demoting kinematic bodies
+    pub fn apply_dynamic_flag(&mut self, dynamic: bool) {
+        if dynamic == self.is_static() {
+            self.set_kind(BodyKind::from_dynamic(dynamic));
+        }
+    }
     pub fn get_half_extents(&self) -> Vec3 {
         let size = self.aabb.get_size();
         Vec3::new(size.x / 2.0, size.y / 2.0, size.z / 2.0)
@@ -112,10 +403,21 @@ impl RigidBody {
             id,
             position,
             velocity,
-            dynamic,
+            kind: BodyKind::from_dynamic(dynamic),
             aabb,
             mass,
+            inv_mass: inverse_mass_of(BodyKind::from_dynamic(dynamic), mass),
             restitution,
+            restitution_threshold: 0.0,
+            friction: 0.0,
+            layer: LAYER_DEFAULT,
+            mask: MASK_ALL,
+            is_sensor: false,
+            sleeping: false,
+            active: true,
+            sleep_timer: 0.0,
+            orientation: Quat::IDENTITY,
+            angular_velocity: Vec3::zero(),
         }
     }
 
@@ -137,10 +439,21 @@ impl RigidBody {
             id: String::new(),
             position,
             velocity,
-            dynamic: !is_static,
+            kind: BodyKind::from_dynamic(!is_static),
             aabb,
             mass,
+            inv_mass: inverse_mass_of(BodyKind::from_dynamic(!is_static), mass),
             restitution,
+            restitution_threshold: 0.0,
+            friction: 0.0,
+            layer: LAYER_DEFAULT,
+            mask: MASK_ALL,
+            is_sensor: false,
+            sleeping: false,
+            active: true,
+            sleep_timer: 0.0,
+            orientation: Quat::IDENTITY,
+            angular_velocity: Vec3::zero(),
         }
     }
 
@@ -163,18 +476,95 @@ impl RigidBody {
             id,
             position,
             velocity,
-            dynamic: !is_static,
+            kind: BodyKind::from_dynamic(!is_static),
             aabb,
             mass,
+            inv_mass: inverse_mass_of(BodyKind::from_dynamic(!is_static), mass),
             restitution,
+            restitution_threshold: 0.0,
+            friction: 0.0,
+            layer: LAYER_DEFAULT,
+            mask: MASK_ALL,
+            is_sensor: false,
+            sleeping: false,
+            active: true,
+            sleep_timer: 0.0,
+            orientation: Quat::IDENTITY,
+            angular_velocity: Vec3::zero(),
         }
     }
+    // Mass from the AABB volume, so it stays consistent when the body is resized
+    pub fn set_density(&mut self, density: f32) {
+        if self.is_static() {
+            return;
+        }
+        let size = self.aabb.get_size();
+        self.set_mass(size.x * size.y * size.z * density);
+    }
+    pub fn from_extents_with_density(
+        id: String,
+        position: Vec3,
+        velocity: Vec3,
+        half_extents: Vec3,
+        density: f32,
+        restitution: f32,
+        is_static: bool,
+    ) -> Self {
+        let mut body = Self::from_extents_with_id(
+            id,
+            position,
+            velocity,
+            half_extents,
+            1.0,
+            restitution,
+            is_static,
+        );
+        body.set_density(density);
+        body
+    }
+    // Resize around the current position; dynamic bodies keep their density, so mass
+    // scales with the volume
+    pub fn set_half_extents(&mut self, half_extents: Vec3) {
+        let old_size = self.aabb.get_size();
+        let old_volume = old_size.x * old_size.y * old_size.z;
+        let size = half_extents * 2.0;
+        self.aabb = AABB::from_center_size(&self.position, &size);
+        if self.is_dynamic() && old_volume > 0.0 {
+            self.set_mass(self.mass * size.x * size.y * size.z / old_volume);
+        }
+        self.wake();
+    }
     pub fn update_position(&mut self, x: f32, y: f32, z: f32) {
         self.position.update(x, y, z);
         self.compute_aabb();
+        self.wake();
     }
     pub fn update_velocity(&mut self, x: f32, y: f32, z: f32) {
-        self.velocity.update(x, y, z)
+        self.velocity.update(x, y, z);
+        self.wake();
+    }
+    // Change velocity by impulse * inv_mass, only dynamic bodies respond
+    pub fn apply_impulse(&mut self, impulse: Vec3) {
+        if self.is_dynamic() {
+            self.velocity = self.velocity + impulse * self.inv_mass;
+            self.wake();
+        }
+    }
+    pub fn wake(&mut self) {
+        self.sleeping = false;
+        self.sleep_timer = 0.0;
+    }
+    // Awake and able to move, the bodies worth integrating and testing this tick
+    pub fn is_awake(&self) -> bool {
+        !self.is_static() && !self.sleeping
+    }
+    pub fn set_collision_layers(&mut self, layer: u32, mask: u32) {
+        self.layer = layer;
+        self.mask = mask;
+    }
+    // Inactive bodies collide with nothing
+    pub fn can_collide_with(&self, other: &RigidBody) -> bool {
+        self.active && other.active && self.layer & other.mask != 0 && other.layer & self.mask != 0
     }
     pub fn compute_aabb(&mut self) {
         self.aabb.update_from_center(&self.position);
@@ -190,10 +580,21 @@ impl RigidBody {
             id,
             position,
             velocity,
-            dynamic: false,
+            kind: BodyKind::Static,
             aabb,
             mass: 1.0,
+            inv_mass: 0.0,
             restitution,
+            restitution_threshold: 0.0,
+            friction: 0.0,
+            layer: LAYER_DEFAULT,
+            mask: MASK_ALL,
+            is_sensor: false,
+            sleeping: false,
+            active: true,
+            sleep_timer: 0.0,
+            orientation: Quat::IDENTITY,
+            angular_velocity: Vec3::zero(),
         }
     }
     pub fn new_dynamic(
@@ -208,10 +609,275 @@ impl RigidBody {
             id,
             position,
             velocity,
-            dynamic: true,
+            kind: BodyKind::Dynamic,
             aabb,
             mass,
+            inv_mass: inverse_mass_of(BodyKind::Dynamic, mass),
             restitution,
+            restitution_threshold: 0.0,
+            friction: 0.0,
+            layer: LAYER_DEFAULT,
+            mask: MASK_ALL,
+            is_sensor: false,
+            sleeping: false,
+            active: true,
+            sleep_timer: 0.0,
+            orientation: Quat::IDENTITY,
+            angular_velocity: Vec3::zero(),
+        }
+    }
+    // Static trigger volume, e.g. a scoring zone
+    pub fn new_sensor(id: String, position: Vec3, aabb: AABB) -> Self {
+        let mut sensor = Self::new_static(id, position, Vec3::zero(), aabb, 0.0);
+        sensor.is_sensor = true;
+        sensor
+    }
+    pub fn new_kinematic(
+        id: String,
+        position: Vec3,
+        velocity: Vec3,
+        aabb: AABB,
+        restitution: f32,
+    ) -> Self {
+        RigidBody {
+            id,
+            position,
+            velocity,
+            kind: BodyKind::Kinematic,
+            aabb,
+            mass: 1.0,
+            inv_mass: 0.0,
+            restitution,
+            restitution_threshold: 0.0,
+            friction: 0.0,
+            layer: LAYER_DEFAULT,
+            mask: MASK_ALL,
+            is_sensor: false,
+            sleeping: false,
+            active: true,
+            sleep_timer: 0.0,
+            orientation: Quat::IDENTITY,
+            angular_velocity: Vec3::zero(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::world::World;
+
+    fn cube(id: &str, position: Vec3, velocity: Vec3) -> RigidBody {
+        RigidBody::from_extents_with_id(
+            id.to_string(),
+            position,
+            velocity,
+            Vec3::new(0.5, 0.5, 0.5),
+            1.0,
+            1.0,
+            false,
+        )
+    }
+
+    #[test]
+    fn kinematic_paddle_ignores_the_ball() {
+        let start = Vec3::new(2.0, 0.0, 0.0);
+        let size = Vec3::new(1.0, 6.0, 1.0);
+        let paddle = RigidBody::new_kinematic(
+            "paddle".to_string(),
+            start,
+            Vec3::new(0.0, 1.0, 0.0),
+            AABB::from_center_size(&start, &size),
+            1.0,
+        );
+        let mut world = World::new_empty();
+        world.add_body(paddle);
+        world.add_body(cube("ball", Vec3::zero(), Vec3::new(5.0, 0.0, 0.0)));
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
         }
+
+        let paddle = world.get_body("paddle").unwrap();
+        assert!(paddle.position.approx_eq(&Vec3::new(2.0, 1.0, 0.0), 1e-4));
+        assert_eq!(paddle.velocity, Vec3::new(0.0, 1.0, 0.0));
+        assert!(world.get_body("ball").unwrap().velocity.x < 0.0);
+    }
+
+    #[test]
+    fn doubling_half_extents_gives_eight_times_the_mass() {
+        let mut small = cube("small", Vec3::zero(), Vec3::zero());
+        small.set_density(3.0);
+        let mut large = RigidBody::from_extents_with_density(
+            "large".to_string(),
+            Vec3::zero(),
+            Vec3::zero(),
+            Vec3::new(1.0, 1.0, 1.0),
+            3.0,
+            1.0,
+            false,
+        );
+        assert_eq!(small.mass, 3.0);
+        assert_eq!(large.mass, 8.0 * small.mass);
+
+        // Resizing keeps the density
+        large.set_half_extents(Vec3::new(0.5, 0.5, 0.5));
+        assert_eq!(large.mass, small.mass);
+    }
+
+    #[test]
+    fn set_half_extents_resizes_around_the_current_position() {
+        let position = Vec3::new(2.0, -1.0, 3.0);
+        let mut body = cube("box", position, Vec3::zero());
+        body.sleeping = true;
+
+        body.set_half_extents(Vec3::new(1.0, 2.0, 0.25));
+        assert_eq!(body.position, position);
+        assert_eq!(body.aabb.get_center(), position);
+        assert_eq!(body.aabb.min, Vec3::new(1.0, -3.0, 2.75));
+        assert_eq!(body.aabb.max, Vec3::new(3.0, 1.0, 3.25));
+        assert!(body.is_awake());
+    }
+
+    #[test]
+    fn touching_boxes_intersect() {
+        let a = AABB::new(Vec3::zero(), Vec3::new(1.0, 1.0, 1.0));
+        let touching = AABB::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0));
+        let apart = AABB::new(Vec3::new(1.01, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0));
+        assert!(a.intersects(&touching) && touching.intersects(&a));
+        assert!(!a.intersects(&apart));
+        assert_eq!(a.distance_to(&touching), 0.0);
+    }
+
+    #[test]
+    fn contained_box_intersects_and_merges_to_the_outer_box() {
+        let outer = AABB::new(Vec3::new(-2.0, -2.0, -2.0), Vec3::new(2.0, 2.0, 2.0));
+        let inner = AABB::new(Vec3::new(-0.5, 0.0, 0.5), Vec3::new(0.5, 1.0, 1.0));
+        assert!(outer.intersects(&inner) && inner.intersects(&outer));
+
+        let merged = outer.merge(&inner);
+        assert_eq!((merged.min, merged.max), (outer.min, outer.max));
+        let spanning = inner.merge(&AABB::new(
+            Vec3::new(3.0, 3.0, 3.0),
+            Vec3::new(4.0, 4.0, 4.0),
+        ));
+        assert_eq!(spanning.min, inner.min);
+        assert_eq!(spanning.max, Vec3::new(4.0, 4.0, 4.0));
+    }
+
+    #[test]
+    fn boundary_points_are_contained() {
+        let aabb = AABB::new(Vec3::zero(), Vec3::new(1.0, 1.0, 1.0));
+        assert!(aabb.contains_point(&Vec3::new(1.0, 0.5, 0.0)));
+        assert!(aabb.contains_point(&Vec3::new(1.0, 1.0, 1.0)));
+        assert!(!aabb.contains_point(&Vec3::new(1.0001, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn closest_point_clamps_onto_the_box() {
+        let aabb = AABB::new(Vec3::zero(), Vec3::new(1.0, 1.0, 1.0));
+        let inside = Vec3::new(0.25, 0.5, 0.75);
+        assert_eq!(aabb.closest_point(&inside), inside);
+        assert_eq!(
+            aabb.closest_point(&Vec3::new(3.0, 0.5, -2.0)),
+            Vec3::new(1.0, 0.5, 0.0)
+        );
+        assert_eq!(
+            aabb.closest_point(&Vec3::new(-1.0, 5.0, 2.0)),
+            Vec3::new(0.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn set_mass_keeps_the_inverse_mass_in_step() {
+        let mut body = cube("box", Vec3::zero(), Vec3::zero());
+        for mass in [4.0, 0.25, 1.0] {
+            body.set_mass(mass);
+            assert_eq!(body.inverse_mass(), 1.0 / mass);
+        }
+        body.set_mass(4.0);
+        let json = serde_json::to_string(&body).unwrap();
+        let loaded: RigidBody = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.inverse_mass(), 0.25);
+    }
+
+    #[test]
+    fn static_and_kinematic_bodies_have_no_inverse_mass() {
+        let aabb = AABB::from_center_size(&Vec3::zero(), &Vec3::new(1.0, 1.0, 1.0));
+        let wall = RigidBody::new_static(
+            "wall".to_string(),
+            Vec3::zero(),
+            Vec3::zero(),
+            aabb.clone(),
+            1.0,
+        );
+        let paddle =
+            RigidBody::new_kinematic("paddle".to_string(), Vec3::zero(), Vec3::zero(), aabb, 1.0);
+        for body in [&wall, &paddle] {
+            assert_eq!(body.inv_mass, 0.0);
+            assert_eq!(body.inverse_mass(), 0.0);
+        }
+
+        // Switching kind re-derives it from the mass
+        let mut body = wall.clone();
+        body.apply_dynamic_flag(true);
+        assert_eq!(body.inverse_mass(), 1.0);
+        body.set_kind(BodyKind::Kinematic);
+        assert_eq!(body.inv_mass, 0.0);
+    }
+
+    #[test]
+    fn zero_mass_behaves_as_static() {
+        let mut body = cube("box", Vec3::zero(), Vec3::zero());
+        body.set_mass(0.0);
+        assert_eq!(body.inverse_mass(), 0.0);
+        body.apply_impulse(Vec3::new(10.0, 0.0, 0.0));
+        assert_eq!(body.velocity, Vec3::zero());
+
+        // A ball running into it bounces off, and the box stays where it was
+        let mut ball = cube("ball", Vec3::new(-0.9, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+        assert!(World::handle_collision(&mut ball, &mut body));
+        assert!(ball.velocity.x < 0.0);
+        assert_eq!(body.velocity, Vec3::zero());
+        assert_eq!(body.position, Vec3::zero());
+    }
+
+    #[test]
+    fn lerp_hits_both_ends_and_the_middle() {
+        let (a, b) = (Vec3::new(-2.0, 0.0, 4.0), Vec3::new(2.0, 6.0, 0.0));
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 0.5), Vec3::new(0.0, 3.0, 2.0));
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 2.0), b); // Clamped, no extrapolation
+    }
+
+    #[test]
+    fn dot_length_and_sub_work_per_component() {
+        let (a, b) = (Vec3::new(1.0, 2.0, 2.0), Vec3::new(4.0, -1.0, 0.5));
+        assert_eq!(a.dot(&b), 3.0);
+        assert_eq!(a.length(), 3.0);
+        assert_eq!(a - b, Vec3::new(-3.0, 3.0, 1.5));
+        assert_eq!((b - b).length(), 0.0);
+    }
+
+    #[test]
+    fn move_toward_never_overshoots() {
+        let target = Vec3::new(3.0, 4.0, 0.0); // 5 away
+        let mut position = Vec3::zero();
+        position = position.move_toward(&target, 2.0);
+        assert!(position.approx_eq(&Vec3::new(1.2, 1.6, 0.0), 1e-6));
+        position = position.move_toward(&target, 2.0);
+        position = position.move_toward(&target, 2.0);
+        assert_eq!(position, target);
+        assert_eq!(position.move_toward(&target, 2.0), target);
+    }
+
+    #[test]
+    fn approx_eq_uses_the_epsilon_per_component() {
+        let a = Vec3::new(1.0, 2.0, 3.0);
+        assert!(a.approx_eq(&Vec3::new(1.0005, 1.9995, 3.0), 1e-3));
+        assert!(!a.approx_eq(&Vec3::new(1.0, 2.0, 3.002), 1e-3));
+        assert!(Vec3::new(-0.0, 0.0, -0.0).approx_eq(&Vec3::zero(), 0.0));
+        assert!(!Vec3::new(f32::NAN, 0.0, 0.0).approx_eq(&Vec3::new(f32::NAN, 0.0, 0.0), 1.0));
     }
 }
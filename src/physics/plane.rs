@@ -0,0 +1,39 @@
+use super::object::{RigidBody, Vec3};
+use serde::{Deserialize, Serialize};
+
+// Infinite static half-space: everything with dot(p, normal) < distance is solid, so a
+// body can't tunnel through it however fast it moves. Cheaper than a thick box wall.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Plane {
+    pub id: String,   // Reported as body_b in collision events
+    pub normal: Vec3, // Unit length, points out of the solid side
+    pub distance: f32,
+    pub restitution: f32,
+}
+
+impl Plane {
+    // `normal` is normalized here, a zero normal makes a plane nothing collides with
+    pub fn new(id: String, normal: Vec3, distance: f32) -> Self {
+        let length = normal.length();
+        let normal = if length > 0.0 {
+            normal * (1.0 / length)
+        } else {
+            Vec3::zero()
+        };
+        Plane {
+            id,
+            normal,
+            distance,
+            restitution: 1.0,
+        }
+    }
+
+    // How far the body's AABB reaches into the solid side, <= 0 when it is clear of it
+    pub fn penetration(&self, body: &RigidBody) -> f32 {
+        let n = self.normal;
+        let center = body.aabb.get_center();
+        let he = body.get_half_extents();
+        let reach = n.x.abs() * he.x + n.y.abs() * he.y + n.z.abs() * he.z;
+        self.distance - (center.dot(&n) - reach)
+    }
+}
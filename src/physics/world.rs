@@ -1,8 +1,182 @@
+use super::constraints::DistanceConstraint;
+use super::force_field::ForceField;
 use super::object::{RigidBody, Vec3};
+use super::plane::Plane;
 use crate::physics::object::AABB;
+use glam::Quat;
+use log::{log_enabled, trace, Level};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+// Pong collision layers: balls hit paddles and walls, paddles hit walls
+pub const PONG_LAYER_WALL: u32 = 1 << 0;
+pub const PONG_LAYER_PADDLE: u32 = 1 << 1;
+pub const PONG_LAYER_BALL: u32 = 1 << 2;
+pub const PONG_MASK_WALL: u32 = PONG_LAYER_BALL | PONG_LAYER_PADDLE;
+pub const PONG_MASK_PADDLE: u32 = PONG_LAYER_BALL | PONG_LAYER_WALL;
+pub const PONG_MASK_BALL: u32 = PONG_LAYER_PADDLE | PONG_LAYER_WALL;
+
+// A resolved contact or sensor overlap between two bodies during the last step
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionEvent {
+    pub body_a: String,
+    pub body_b: String,
+    pub sensor: bool, // Overlap with a sensor, nothing was resolved
+}
+
+impl CollisionEvent {
+    pub fn involves(&self, id: &str) -> bool {
+        self.body_a == id || self.body_b == id
+    }
+
+    // True if one body id starts with `a` and the other with `b`
+    pub fn is_between(&self, a: &str, b: &str) -> bool {
+        (self.body_a.starts_with(a) && self.body_b.starts_with(b))
+            || (self.body_a.starts_with(b) && self.body_b.starts_with(a))
+    }
+}
+
+// Dynamic bodies slower than this for SLEEP_AFTER seconds are put to sleep
+pub const SLEEP_SPEED_THRESHOLD: f32 = 0.05;
+pub const SLEEP_AFTER: f32 = 0.5;
+
+fn default_sleep_threshold() -> f32 {
+    SLEEP_SPEED_THRESHOLD
+}
+fn default_sleep_after() -> f32 {
+    SLEEP_AFTER
+}
+
+// Penetration up to the slop is left alone, correction_percent of the rest is removed per contact
+pub const PENETRATION_SLOP: f32 = 0.01;
+pub const CORRECTION_PERCENT: f32 = 0.8;
+
+fn default_penetration_slop() -> f32 {
+    PENETRATION_SLOP
+}
+fn default_correction_percent() -> f32 {
+    CORRECTION_PERCENT
+}
+
+// Contacts closing slower than this don't bounce, so resting bodies settle. Off by default;
+// a contact uses the highest of the world's and both bodies' own thresholds.
+pub const RESTITUTION_THRESHOLD: f32 = 0.0;
+
+fn default_restitution_threshold() -> f32 {
+    RESTITUTION_THRESHOLD
+}
+
+// Bodies an island worked on (by world index) and its events tagged with their pair index
+type IslandResult = (Vec<(usize, RigidBody)>, Vec<(usize, CollisionEvent)>);
+
+// Below this many contacts per step, resolution stays on the calling thread
+pub const PARALLEL_RESOLVE_MIN_PAIRS: usize = 64;
+
+// How two bodies' material coefficients combine at a contact
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CombineMode {
+    #[default]
+    Min,
+    Max,
+    Average,
+    Multiply,
+}
+
+impl CombineMode {
+    pub fn combine(self, a: f32, b: f32) -> f32 {
+        match self {
+            CombineMode::Min => a.min(b),
+            CombineMode::Max => a.max(b),
+            CombineMode::Average => (a + b) * 0.5,
+            CombineMode::Multiply => a * b,
+        }
+    }
+}
+
+// Restitution for contacts between a body whose id starts with `prefix_a` and one whose
+// id starts with `prefix_b`, in either order, instead of combining the bodies' own values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestitutionOverride {
+    pub prefix_a: String,
+    pub prefix_b: String,
+    pub restitution: f32,
+}
+
+impl RestitutionOverride {
+    pub fn matches(&self, id_1: &str, id_2: &str) -> bool {
+        (id_1.starts_with(&self.prefix_a) && id_2.starts_with(&self.prefix_b))
+            || (id_1.starts_with(&self.prefix_b) && id_2.starts_with(&self.prefix_a))
+    }
+}
+
+// World-wide contact response settings, see World::contact_settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactSettings {
+    pub restitution_combine: CombineMode,
+    pub friction_combine: CombineMode,
+    pub penetration_slop: f32,
+    pub correction_percent: f32,
+    pub restitution_threshold: f32,
+    pub lock_z: bool, // Never pick z as the contact normal, see World::lock_z
+    pub restitution_overrides: Vec<RestitutionOverride>, // First match wins
+}
+
+impl Default for ContactSettings {
+    fn default() -> Self {
+        ContactSettings {
+            restitution_combine: CombineMode::Min,
+            friction_combine: CombineMode::Min,
+            penetration_slop: PENETRATION_SLOP,
+            correction_percent: CORRECTION_PERCENT,
+            restitution_threshold: RESTITUTION_THRESHOLD,
+            lock_z: false,
+            restitution_overrides: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayHit {
+    pub body_id: String,
+    pub point: Vec3,
+    pub distance: f32,
+    pub normal: Vec3, // Zero when the ray starts inside the body
+}
+
+// Single contact between two overlapping AABBs. Bodies respond to contacts linearly and
+// their boxes never rotate, so a face contact resolves the same as one point at its depth.
+// A multi-point manifold only pays off with an angular response at each point's lever arm;
+// splitting a linear impulse across 4 points sums back to this one. Resting stacks settle
+// through the slop and restitution threshold instead, see box_stack_comes_to_rest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    pub normal: Vec3, // Unit axis, pushes body_1 away from body_2
+    pub penetration: f32,
+}
+
+#[derive(Debug)]
+pub enum WorldFileError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl From<std::io::Error> for WorldFileError {
+    fn from(err: std::io::Error) -> Self {
+        WorldFileError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for WorldFileError {
+    fn from(err: serde_json::Error) -> Self {
+        WorldFileError::Json(err)
+    }
+}
 
 #[derive(Serialize, Clone, Deserialize)]
 pub struct World {
@@ -10,6 +184,36 @@ pub struct World {
     pub tick_rate: f32,
     pub bodies: Vec<RigidBody>,
     pub body_map: HashMap<String, usize>,
+    #[serde(skip)]
+    pub collision_events: Vec<CollisionEvent>, // Cleared at the start of every step
+    #[serde(default = "default_sleep_threshold")]
+    pub sleep_threshold: f32,
+    #[serde(default = "default_sleep_after")]
+    pub sleep_after: f32, // Seconds, 0 disables sleeping
+    #[serde(default)]
+    pub restitution_combine: CombineMode,
+    #[serde(default)]
+    pub friction_combine: CombineMode,
+    #[serde(default = "default_penetration_slop")]
+    pub penetration_slop: f32,
+    #[serde(default = "default_correction_percent")]
+    pub correction_percent: f32, // 0..=1, higher separates faster but jitters
+    #[serde(default = "default_restitution_threshold")]
+    pub restitution_threshold: f32,
+    #[serde(default)]
+    pub tick_count: u64, // Completed steps, see World::tick_count
+    #[serde(default)]
+    pub sim_time: f64, // Seconds, the sum of every dt stepped
+    #[serde(default)]
+    pub constraints: Vec<DistanceConstraint>, // Solved after collisions every step
+    #[serde(default)]
+    pub force_fields: Vec<ForceField>, // Applied before integration every step
+    #[serde(default)]
+    pub planes: Vec<Plane>, // Static half-space boundaries, resolved after body contacts
+    #[serde(default)]
+    pub lock_z: bool, // Classic 2D Pong: dynamic bodies stay on the z = 0 plane
+    #[serde(default)]
+    pub restitution_overrides: Vec<RestitutionOverride>, // Per id-prefix pair, first match wins
 }
 impl World {
     pub fn new(id: String, tick_rate: f32, bodies: Vec<RigidBody>) -> Self {
@@ -23,6 +227,21 @@ impl World {
                 .map(|(i, b)| (b.id.clone(), i))
                 .collect(),
             bodies,
+            collision_events: Vec::new(),
+            sleep_threshold: SLEEP_SPEED_THRESHOLD,
+            sleep_after: SLEEP_AFTER,
+            restitution_combine: CombineMode::Min,
+            friction_combine: CombineMode::Min,
+            penetration_slop: PENETRATION_SLOP,
+            correction_percent: CORRECTION_PERCENT,
+            restitution_threshold: RESTITUTION_THRESHOLD,
+            tick_count: 0,
+            sim_time: 0.0,
+            constraints: Vec::new(),
+            force_fields: Vec::new(),
+            planes: Vec::new(),
+            lock_z: false,
+            restitution_overrides: Vec::new(),
         }
     }
 
@@ -32,9 +251,111 @@ impl World {
             tick_rate: 60.0,
             bodies: Vec::new(),
             body_map: HashMap::new(),
+            collision_events: Vec::new(),
+            sleep_threshold: SLEEP_SPEED_THRESHOLD,
+            sleep_after: SLEEP_AFTER,
+            restitution_combine: CombineMode::Min,
+            friction_combine: CombineMode::Min,
+            penetration_slop: PENETRATION_SLOP,
+            correction_percent: CORRECTION_PERCENT,
+            restitution_threshold: RESTITUTION_THRESHOLD,
+            tick_count: 0,
+            sim_time: 0.0,
+            constraints: Vec::new(),
+            force_fields: Vec::new(),
+            planes: Vec::new(),
+            lock_z: false,
+            restitution_overrides: Vec::new(),
         }
     }
 
+    // `count` unit dynamic cubes scattered in a cube of half-size `extent`, reproducible per seed
+    pub fn with_random_bodies(count: usize, extent: f32, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut world = World::new_empty();
+        for i in 0..count {
+            let mut random_vec = || {
+                Vec3::new(
+                    rng.random_range(-extent..extent),
+                    rng.random_range(-extent..extent),
+                    rng.random_range(-extent..extent),
+                )
+            };
+            let position = random_vec();
+            let velocity = random_vec();
+            world.add_body(RigidBody::from_extents_with_id(
+                format!("body_{}", i),
+                position,
+                velocity,
+                Vec3::new(0.5, 0.5, 0.5),
+                1.0,
+                1.0,
+                false,
+            ));
+        }
+        world
+    }
+
+    // Whole setup as pretty JSON, including tick rate and sleep/combine settings
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), WorldFileError> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    // body_map is rebuilt from bodies, so hand-edited files can't leave it stale
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, WorldFileError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut world: World = serde_json::from_reader(reader)?;
+        world.body_map = world
+            .bodies
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b.id.clone(), i))
+            .collect();
+        Ok(world)
+    }
+
+    pub fn contact_settings(&self) -> ContactSettings {
+        ContactSettings {
+            restitution_combine: self.restitution_combine,
+            friction_combine: self.friction_combine,
+            penetration_slop: self.penetration_slop,
+            correction_percent: self.correction_percent,
+            restitution_threshold: self.restitution_threshold,
+            lock_z: self.lock_z,
+            restitution_overrides: self.restitution_overrides.clone(),
+        }
+    }
+
+    // e.g. ("ball", "wall", 1.0) keeps wall bounces perfectly elastic; the pair is
+    // unordered, so ("wall", "ball", _) updates the same override
+    pub fn set_pair_restitution(&mut self, prefix_a: &str, prefix_b: &str, restitution: f32) {
+        match self.restitution_overrides.iter_mut().find(|o| {
+            (o.prefix_a == prefix_a && o.prefix_b == prefix_b)
+                || (o.prefix_a == prefix_b && o.prefix_b == prefix_a)
+        }) {
+            Some(existing) => existing.restitution = restitution,
+            None => self.restitution_overrides.push(RestitutionOverride {
+                prefix_a: prefix_a.to_string(),
+                prefix_b: prefix_b.to_string(),
+                restitution,
+            }),
+        }
+    }
+
+    // Swap in a whole new set of bodies, e.g. from a saved state; every world setting,
+    // field, plane and constraint is kept and body_map is rebuilt
+    pub fn set_bodies(&mut self, bodies: Vec<RigidBody>) {
+        self.body_map = bodies
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b.id.clone(), i))
+            .collect();
+        self.bodies = bodies;
+        self.collision_events.clear();
+    }
+
     pub fn add_body(&mut self, body: RigidBody) {
         let index = self.bodies.len();
         self.body_map.insert(body.id.clone(), index);
@@ -48,172 +369,577 @@ impl World {
             .get_mut(id)
             .map(|&mut index| &mut self.bodies[index])
     }
+
+    // Bodies whose id starts with `prefix` (e.g. every "ball"), in insertion order
+    pub fn bodies_with_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = &'a RigidBody> + 'a {
+        self.bodies.iter().filter(move |b| b.id.starts_with(prefix))
+    }
+    pub fn bodies_with_prefix_mut<'a>(
+        &'a mut self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = &'a mut RigidBody> + 'a {
+        self.bodies
+            .iter_mut()
+            .filter(move |b| b.id.starts_with(prefix))
+    }
+
+    // Keeps the body and its index but takes it out of the simulation and rendering;
+    // false if the id is unknown. Reactivating wakes it so it integrates straight away.
+    pub fn set_active(&mut self, id: &str, active: bool) -> bool {
+        let Some(body) = self.get_body_mut(id) else {
+            return false;
+        };
+        body.active = active;
+        if active {
+            body.wake();
+        }
+        true
+    }
+
+    // Gap between the two bodies' AABBs, 0.0 if they overlap; None if either id is unknown
+    pub fn distance_between(&self, id_a: &str, id_b: &str) -> Option<f32> {
+        let (a, b) = (self.get_body(id_a)?, self.get_body(id_b)?);
+        Some(a.aabb.distance_to(&b.aabb))
+    }
+
+    // Ids of the bodies `body_id` would overlap if its centre were at `position`, honouring
+    // collision layers; nothing is moved or resolved, unknown ids overlap nothing
+    pub fn overlaps_at(&self, body_id: &str, position: Vec3) -> Vec<String> {
+        let Some(body) = self.get_body(body_id) else {
+            return Vec::new();
+        };
+        let mut aabb = body.aabb.clone();
+        aabb.update_from_center(&position);
+        self.bodies
+            .iter()
+            .filter(|other| other.id != body.id && body.can_collide_with(other))
+            .filter(|other| aabb.intersects(&other.aabb))
+            .map(|other| other.id.clone())
+            .collect()
+    }
+
+    // Ids of the bodies overlapping `id` where it is now, under the same rules as overlaps_at
+    pub fn contacts_for(&self, id: &str) -> Vec<String> {
+        match self.get_body(id) {
+            Some(body) => self.overlaps_at(id, body.position),
+            None => Vec::new(),
+        }
+    }
+
+    // Ids of active bodies whose AABB touches the sphere, so a large box counts as soon as
+    // its nearest point is within `radius` even if its centre is far outside. No broadphase
+    // exists yet, so this is a linear scan; layers are ignored.
+    pub fn query_radius(&self, center: Vec3, radius: f32) -> Vec<String> {
+        let radius_sq = radius * radius;
+        self.bodies
+            .iter()
+            .filter(|body| body.active)
+            .filter(|body| {
+                let offset = body.aabb.closest_point(&center) - center;
+                offset.dot(&offset) <= radius_sq
+            })
+            .map(|body| body.id.clone())
+            .collect()
+    }
+
+    // Sum of 0.5 * m * |v|^2 over dynamic bodies, should never grow with restitution <= 1
+    pub fn total_kinetic_energy(&self) -> f32 {
+        self.bodies
+            .iter()
+            .filter(|b| b.is_dynamic())
+            .map(|b| 0.5 * b.mass * b.velocity.dot(&b.velocity))
+            .sum()
+    }
+
+    pub fn total_momentum(&self) -> Vec3 {
+        self.bodies
+            .iter()
+            .filter(|b| b.is_dynamic())
+            .fold(Vec3::zero(), |sum, b| sum + b.velocity * b.mass)
+    }
+
+    // Nearest body whose AABB the ray enters within max_dist, `dir` need not be normalized
+    pub fn raycast(&self, origin: Vec3, dir: Vec3, max_dist: f32) -> Option<RayHit> {
+        let length = dir.length();
+        if length == 0.0 {
+            return None;
+        }
+        let dir = dir * (1.0 / length);
+
+        self.bodies
+            .iter()
+            .filter(|body| body.active)
+            .filter_map(|body| {
+                let (distance, normal) = Self::ray_aabb(&origin, &dir, &body.aabb)?;
+                (distance <= max_dist).then(|| RayHit {
+                    body_id: body.id.clone(),
+                    point: origin + dir * distance,
+                    distance,
+                    normal,
+                })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    // Slab test, returns the entry distance and the normal of the face that was entered
+    fn ray_aabb(origin: &Vec3, dir: &Vec3, aabb: &AABB) -> Option<(f32, Vec3)> {
+        let axes = [
+            (origin.x, dir.x, aabb.min.x, aabb.max.x),
+            (origin.y, dir.y, aabb.min.y, aabb.max.y),
+            (origin.z, dir.z, aabb.min.z, aabb.max.z),
+        ];
+        let mut t_enter = f32::NEG_INFINITY;
+        let mut t_exit = f32::INFINITY;
+        let mut enter_axis = 0;
+        let mut enter_sign = 0.0;
+
+        for (axis, (o, d, min, max)) in axes.into_iter().enumerate() {
+            if d == 0.0 {
+                // Parallel to this slab, it must already be between the planes
+                if o < min || o > max {
+                    return None;
+                }
+                continue;
+            }
+            let (near, far, sign) = if d > 0.0 {
+                ((min - o) / d, (max - o) / d, -1.0)
+            } else {
+                ((max - o) / d, (min - o) / d, 1.0)
+            };
+            if near > t_enter {
+                t_enter = near;
+                enter_axis = axis;
+                enter_sign = sign;
+            }
+            t_exit = t_exit.min(far);
+        }
+
+        if t_enter > t_exit || t_exit < 0.0 {
+            return None;
+        }
+        if t_enter < 0.0 {
+            return Some((0.0, Vec3::zero()));
+        }
+        let mut normal = Vec3::zero();
+        match enter_axis {
+            0 => normal.x = enter_sign,
+            1 => normal.y = enter_sign,
+            _ => normal.z = enter_sign,
+        }
+        Some((t_enter, normal))
+    }
+
+    // Number of completed calls to step, saved and restored with the world
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    // Simulated seconds, independent of how fast the steps ran in wall-clock time
+    pub fn sim_time(&self) -> f64 {
+        self.sim_time
+    }
+
     pub fn step(&mut self, dt: f64) {
+        self.tick_count += 1;
+        self.sim_time += dt;
+        self.collision_events.clear();
         let dt_f32 = dt as f32;
+        let (threshold, sleep_after) = (self.sleep_threshold, self.sleep_after);
+        let (force_fields, lock_z) = (&self.force_fields, self.lock_z);
         self.bodies.par_iter_mut().for_each(|body| {
-            if body.dynamic {
+            if body.active && body.is_awake() {
+                if body.is_dynamic() {
+                    for field in force_fields {
+                        let acceleration = field.acceleration_at(&body.position);
+                        body.velocity = body.velocity + acceleration * dt_f32;
+                    }
+                    if lock_z {
+                        body.velocity.z = 0.0;
+                        body.position.z = 0.0;
+                    }
+                }
+                // Velocities are per second, so distance travelled doesn't depend on the step size
                 body.position = body.position + (body.velocity * dt_f32);
                 body.compute_aabb();
+                Self::integrate_orientation(body, dt_f32);
+                if body.is_dynamic() && sleep_after > 0.0 {
+                    Self::update_sleep(body, dt_f32, threshold, sleep_after);
+                }
             }
         });
 
         // Run collision detection after updating positions
         self.collide_pong();
+        self.collide_planes();
+        self.solve_constraints(dt_f32);
     }
 
-    pub fn check_collision(body_1: &RigidBody, body_2: &RigidBody) -> bool {
-        let ab2: &AABB = &body_2.aabb;
-        let ab1: &AABB = &body_1.aabb;
-        let collide_x = ab1.max.x >= ab2.min.x && ab1.min.x <= ab2.max.x;
-        let collide_y = ab1.max.y >= ab2.min.y && ab1.min.y <= ab2.max.y;
-        let collide_z = ab1.max.z >= ab2.min.z && ab1.min.z <= ab2.max.z;
-
-        let collide = collide_x && collide_y && collide_z;
-        collide
-    }
-
-    pub fn handle_collision(body_1: &mut RigidBody, body_2: &mut RigidBody) {
-        // Find collision normal (direction to separate objects)
-        // Calculate penetration depths on each axis
-        let penetration_x =
-            (body_1.aabb.max.x.min(body_2.aabb.max.x)) - (body_1.aabb.min.x.max(body_2.aabb.min.x));
-        let penetration_y =
-            (body_1.aabb.max.y.min(body_2.aabb.max.y)) - (body_1.aabb.min.y.max(body_2.aabb.min.y));
-        let penetration_z =
-            (body_1.aabb.max.z.min(body_2.aabb.max.z)) - (body_1.aabb.min.z.max(body_2.aabb.min.z));
-
-        // Find the axis with minimum penetration (collision normal direction)
-        let mut normal = Vec3::zero();
-        let min_penetration;
+    // `dt` split into `substeps` equal steps (at least one), so fast bodies move less per
+    // collision pass and tunnel less. Each substep counts towards tick_count, and
+    // collision_events ends up holding the events of all of them.
+    pub fn step_substeps(&mut self, dt: f64, substeps: usize) {
+        let substeps = substeps.max(1);
+        let sub_dt = dt / substeps as f64;
+        let mut events = Vec::new();
+        for _ in 0..substeps {
+            self.step(sub_dt);
+            events.append(&mut self.collision_events);
+        }
+        self.collision_events = events;
+    }
 
-        if penetration_x <= penetration_y && penetration_x <= penetration_z {
-            // X axis has minimum penetration
-            min_penetration = penetration_x;
-            normal.x = if body_1.position.x < body_2.position.x {
-                -1.0
-            } else {
-                1.0
-            };
-        } else if penetration_y <= penetration_z {
-            // Y axis has minimum penetration
-            min_penetration = penetration_y;
-            normal.y = if body_1.position.y < body_2.position.y {
-                -1.0
-            } else {
-                1.0
+    pub fn add_constraint(&mut self, constraint: DistanceConstraint) {
+        self.constraints.push(constraint);
+    }
+
+    pub fn add_force_field(&mut self, field: ForceField) {
+        self.force_fields.push(field);
+    }
+
+    pub fn add_plane(&mut self, plane: Plane) {
+        self.planes.push(plane);
+    }
+
+    // Awake, active dynamic bodies against every plane; layers don't apply to planes
+    fn collide_planes(&mut self) {
+        if self.planes.is_empty() {
+            return;
+        }
+        let settings = self.contact_settings();
+        for body in self.bodies.iter_mut() {
+            if !(body.active && body.is_dynamic() && body.is_awake()) {
+                continue;
+            }
+            for plane in &self.planes {
+                if Self::handle_plane_collision(body, plane, &settings) {
+                    self.collision_events.push(CollisionEvent {
+                        body_a: body.id.clone(),
+                        body_b: plane.id.clone(),
+                        sensor: false,
+                    });
+                }
+            }
+        }
+    }
+
+    // Constraints naming an unknown or inactive body, or the same body twice, are skipped
+    fn solve_constraints(&mut self, dt: f32) {
+        for constraint in &self.constraints {
+            let (i, j) = match (
+                self.body_map.get(&constraint.body_a),
+                self.body_map.get(&constraint.body_b),
+            ) {
+                (Some(&i), Some(&j)) if i != j => (i, j),
+                _ => continue,
             };
-        } else {
-            // Z axis has minimum penetration
-            min_penetration = penetration_z;
-            normal.z = if body_1.position.z < body_2.position.z {
-                -1.0
+            let (body_a, body_b) = if i < j {
+                let (left, right) = self.bodies.split_at_mut(j);
+                (&mut left[i], &mut right[0])
             } else {
-                1.0
+                let (left, right) = self.bodies.split_at_mut(i);
+                (&mut right[0], &mut left[j])
             };
+            if body_a.active && body_b.active {
+                constraint.solve(body_a, body_b, dt);
+            }
         }
+    }
 
-        // Calculate relative velocity along the collision normal
-        let relative_velocity = Vec3::new(
-            body_1.velocity.x - body_2.velocity.x,
-            body_1.velocity.y - body_2.velocity.y,
-            body_1.velocity.z - body_2.velocity.z,
-        );
-        let velocity_along_normal = relative_velocity.x * normal.x
-            + relative_velocity.y * normal.y
-            + relative_velocity.z * normal.z;
+    fn integrate_orientation(body: &mut RigidBody, dt: f32) {
+        let w = body.angular_velocity;
+        if w.x == 0.0 && w.y == 0.0 && w.z == 0.0 {
+            return;
+        }
+        let rotation = Quat::from_scaled_axis(glam::Vec3::new(w.x, w.y, w.z) * dt);
+        body.orientation = (rotation * body.orientation).normalize();
+    }
 
-        // Don't resolve if velocities are separating
-        if velocity_along_normal > 0.0 {
+    fn update_sleep(body: &mut RigidBody, dt: f32, threshold: f32, sleep_after: f32) {
+        let v = body.velocity;
+        let w = body.angular_velocity;
+        let limit = threshold * threshold;
+        if v.dot(&v) >= limit || w.dot(&w) >= limit {
+            body.sleep_timer = 0.0;
             return;
         }
+        body.sleep_timer += dt;
+        if body.sleep_timer >= sleep_after {
+            body.sleeping = true;
+            body.velocity = Vec3::zero();
+            body.angular_velocity = Vec3::zero();
+        }
+    }
+
+    // Pairs where nothing is awake can't produce new contacts
+    fn should_test_pair(body_1: &RigidBody, body_2: &RigidBody) -> bool {
+        (body_1.is_dynamic() || body_2.is_dynamic())
+            && (body_1.is_awake() || body_2.is_awake())
+            && body_1.can_collide_with(body_2)
+    }
+
+    pub fn check_collision(body_1: &RigidBody, body_2: &RigidBody) -> bool {
+        body_1.aabb.intersects(&body_2.aabb)
+    }
+
+    // Contact for two overlapping AABBs: the axis of least penetration is the normal,
+    // pointing from body_2 towards body_1
+    pub fn contact(body_1: &RigidBody, body_2: &RigidBody) -> Option<Contact> {
+        Self::contact_on(body_1, body_2, true)
+    }
 
-        // Handle static bodies (infinite mass)
-        let inv_mass_1 = if body_1.dynamic {
-            1.0 / body_1.mass
+    // As contact, but with `use_z` false only x or y can become the normal
+    pub fn contact_on(body_1: &RigidBody, body_2: &RigidBody, use_z: bool) -> Option<Contact> {
+        let (a, b) = (&body_1.aabb, &body_2.aabb);
+        let depth = Vec3::new(
+            a.max.x.min(b.max.x) - a.min.x.max(b.min.x),
+            a.max.y.min(b.max.y) - a.min.y.max(b.min.y),
+            a.max.z.min(b.max.z) - a.min.z.max(b.min.z),
+        );
+        // Separated or merely touching boxes have no overlap to resolve, and a negative
+        // depth would otherwise be picked as the "least" penetration axis
+        if depth.x <= 0.0 || depth.y <= 0.0 || depth.z <= 0.0 {
+            return None;
+        }
+
+        let side = |p1: f32, p2: f32| if p1 < p2 { -1.0 } else { 1.0 };
+        let depth_z = if use_z { depth.z } else { f32::INFINITY };
+        let (normal, penetration) = if depth.x <= depth.y && depth.x <= depth_z {
+            (
+                Vec3::new(side(body_1.position.x, body_2.position.x), 0.0, 0.0),
+                depth.x,
+            )
+        } else if depth.y <= depth_z {
+            (
+                Vec3::new(0.0, side(body_1.position.y, body_2.position.y), 0.0),
+                depth.y,
+            )
         } else {
-            0.0
+            (
+                Vec3::new(0.0, 0.0, side(body_1.position.z, body_2.position.z)),
+                depth.z,
+            )
         };
-        let inv_mass_2 = if body_2.dynamic {
-            1.0 / body_2.mass
-        } else {
-            0.0
+        Some(Contact {
+            normal,
+            penetration,
+        })
+    }
+
+    // Returns false if the pair was separating or immovable and nothing was resolved
+    pub fn handle_collision(body_1: &mut RigidBody, body_2: &mut RigidBody) -> bool {
+        Self::handle_collision_with(body_1, body_2, &ContactSettings::default())
+    }
+
+    pub fn handle_collision_with(
+        body_1: &mut RigidBody,
+        body_2: &mut RigidBody,
+        settings: &ContactSettings,
+    ) -> bool {
+        let contact = match Self::contact_on(body_1, body_2, !settings.lock_z) {
+            Some(contact) => contact,
+            None => return false,
         };
+        let normal = contact.normal;
+
+        // Calculate relative velocity along the collision normal
+        let relative_velocity = body_1.velocity - body_2.velocity;
+        let velocity_along_normal = relative_velocity.dot(&normal);
+
+        // Don't resolve if velocities are separating
+        if velocity_along_normal > 0.0 {
+            return false;
+        }
+
+        // Static and kinematic bodies have infinite mass
+        let inv_mass_1 = body_1.inverse_mass();
+        let inv_mass_2 = body_2.inverse_mass();
+        if inv_mass_1 + inv_mass_2 == 0.0 {
+            return false;
+        }
+        // Being hit wakes sleeping bodies
+        if body_1.is_dynamic() {
+            body_1.wake();
+        }
+        if body_2.is_dynamic() {
+            body_2.wake();
+        }
 
         // Compute impulse magnitude using masses and restitution
-        let restitution = body_1.restitution.min(body_2.restitution);
+        let pair_override = settings
+            .restitution_overrides
+            .iter()
+            .find(|o| o.matches(&body_1.id, &body_2.id));
+        let threshold = settings
+            .restitution_threshold
+            .max(body_1.restitution_threshold)
+            .max(body_2.restitution_threshold);
+        let restitution = if -velocity_along_normal < threshold {
+            0.0
+        } else if let Some(pair_override) = pair_override {
+            pair_override.restitution
+        } else {
+            settings
+                .restitution_combine
+                .combine(body_1.restitution, body_2.restitution)
+        };
         let impulse_magnitude =
             -(1.0 + restitution) * velocity_along_normal / (inv_mass_1 + inv_mass_2);
 
         // Apply equal and opposite impulses to both objects
-        let impulse = Vec3::new(
-            impulse_magnitude * normal.x,
-            impulse_magnitude * normal.y,
-            impulse_magnitude * normal.z,
-        );
-
-        // Apply impulse to body_1 (add impulse / mass to velocity)
-        if body_1.dynamic {
-            body_1.velocity.x += impulse.x * inv_mass_1;
-            body_1.velocity.y += impulse.y * inv_mass_1;
-            body_1.velocity.z += impulse.z * inv_mass_1;
+        let impulse = normal * impulse_magnitude;
+        if body_1.is_dynamic() {
+            body_1.velocity = body_1.velocity + impulse * inv_mass_1;
+        }
+        if body_2.is_dynamic() {
+            body_2.velocity = body_2.velocity + impulse * -inv_mass_2;
         }
 
-        // Apply opposite impulse to body_2 (subtract impulse / mass from velocity)
-        if body_2.dynamic {
-            body_2.velocity.x -= impulse.x * inv_mass_2;
-            body_2.velocity.y -= impulse.y * inv_mass_2;
-            body_2.velocity.z -= impulse.z * inv_mass_2;
+        // Friction opposes the remaining tangential sliding, capped at mu * normal impulse
+        let friction = settings
+            .friction_combine
+            .combine(body_1.friction, body_2.friction);
+        if friction > 0.0 {
+            let rv = body_1.velocity - body_2.velocity;
+            let tangent = rv - normal * rv.dot(&normal);
+            let sliding = tangent.length();
+            if sliding > 0.0 {
+                let jt = (sliding / (inv_mass_1 + inv_mass_2)).min(friction * impulse_magnitude);
+                let friction_impulse = tangent * (-jt / sliding);
+                if body_1.is_dynamic() {
+                    body_1.velocity = body_1.velocity + friction_impulse * inv_mass_1;
+                }
+                if body_2.is_dynamic() {
+                    body_2.velocity = body_2.velocity + friction_impulse * -inv_mass_2;
+                }
+            }
         }
 
-        // Position correction to separate overlapping objects
+        // Baumgarte-style position correction: leave the slop, push out a share of the rest
         let total_inv_mass = inv_mass_1 + inv_mass_2;
-        let correction_amount = min_penetration * 0.8; // 80% correction to avoid jitter
+        let correction_amount = (contact.penetration - settings.penetration_slop).max(0.0)
+            * settings.correction_percent;
 
-        if body_1.dynamic {
+        if body_1.is_dynamic() {
             let correction_1 = correction_amount * (inv_mass_1 / total_inv_mass);
-            body_1.position.x += normal.x * correction_1;
-            body_1.position.y += normal.y * correction_1;
-            body_1.position.z += normal.z * correction_1;
+            body_1.position = body_1.position + normal * correction_1;
             body_1.compute_aabb(); // Update AABB after position change
         }
 
-        if body_2.dynamic {
+        if body_2.is_dynamic() {
             let correction_2 = correction_amount * (inv_mass_2 / total_inv_mass);
-            body_2.position.x -= normal.x * correction_2;
-            body_2.position.y -= normal.y * correction_2;
-            body_2.position.z -= normal.z * correction_2;
+            body_2.position = body_2.position + normal * -correction_2;
             body_2.compute_aabb(); // Update AABB after position change
         }
+        true
     }
-    //note: this is only for pong, complete physics sim works for all
-    pub fn collide_pong(&mut self) {
+    // Body against a static half-space: the body is pushed all the way out, however deep it
+    // got, and its velocity into the plane is reflected with the combined restitution.
+    // False if the body was clear of the plane.
+    pub fn handle_plane_collision(
+        body: &mut RigidBody,
+        plane: &Plane,
+        settings: &ContactSettings,
+    ) -> bool {
+        let penetration = plane.penetration(body);
+        if penetration <= 0.0 || !body.is_dynamic() {
+            return false;
+        }
+        let normal = plane.normal;
+
+        body.position = body.position + normal * penetration;
+        body.compute_aabb();
+
+        let velocity_along_normal = body.velocity.dot(&normal);
+        if velocity_along_normal < 0.0 {
+            let threshold = settings
+                .restitution_threshold
+                .max(body.restitution_threshold);
+            let restitution = if -velocity_along_normal < threshold {
+                0.0
+            } else {
+                settings
+                    .restitution_combine
+                    .combine(body.restitution, plane.restitution)
+            };
+            body.velocity = body.velocity + normal * (-(1.0 + restitution) * velocity_along_normal);
+        }
+        true
+    }
+
+    // Index pairs (i < j) of bodies that should collide and currently overlap
+    pub fn collision_pairs(&self) -> Vec<(usize, usize)> {
         let mut collision_pairs = Vec::new();
 
         for i in 0..self.bodies.len() {
-            if self.bodies[i].id.starts_with("ball") {
-                for j in 0..self.bodies.len() {
-                    if i != j
-                        && (self.bodies[j].id.starts_with("paddle")
-                            || self.bodies[j].id.starts_with("wall"))
-                    {
-                        if Self::check_collision(&self.bodies[i], &self.bodies[j]) {
-                            collision_pairs.push((i, j));
-                        }
-                    }
+            for j in (i + 1)..self.bodies.len() {
+                if Self::should_test_pair(&self.bodies[i], &self.bodies[j])
+                    && Self::check_collision(&self.bodies[i], &self.bodies[j])
+                {
+                    collision_pairs.push((i, j));
                 }
-            } else if self.bodies[i].id.starts_with("paddle") {
-                for j in 0..self.bodies.len() {
-                    if i != j && self.bodies[j].id.starts_with("wall") {
-                        if Self::check_collision(&self.bodies[i], &self.bodies[j]) {
-                            collision_pairs.push((i, j));
-                        }
-                    }
+            }
+        }
+        collision_pairs
+    }
+
+    // Id pairs overlapping right now, sleeping bodies included; unlike collision_events
+    // this needs no step and changes nothing. Pairs of non-dynamic bodies are left out.
+    pub fn current_contacts(&self) -> Vec<(String, String)> {
+        let mut contacts = Vec::new();
+        for (i, body_1) in self.bodies.iter().enumerate() {
+            for body_2 in &self.bodies[i + 1..] {
+                if (body_1.is_dynamic() || body_2.is_dynamic())
+                    && body_1.can_collide_with(body_2)
+                    && Self::check_collision(body_1, body_2)
+                {
+                    contacts.push((body_1.id.clone(), body_2.id.clone()));
                 }
             }
         }
+        contacts
+    }
 
-        // Handle collisions
-        for (i, j) in collision_pairs {
+    // Group pairs into islands: pairs in different islands share no dynamic body.
+    // Static and kinematic bodies never change during resolution, so they don't join
+    // islands together. Islands hold indices into `pairs`, in increasing order.
+    pub fn islands(&self, pairs: &[(usize, usize)]) -> Vec<Vec<usize>> {
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        let mut parent: Vec<usize> = (0..self.bodies.len()).collect();
+        for &(i, j) in pairs {
+            if self.bodies[i].is_dynamic() && self.bodies[j].is_dynamic() {
+                let (root_i, root_j) = (find(&mut parent, i), find(&mut parent, j));
+                parent[root_i] = root_j;
+            }
+        }
+
+        let mut island_of_root: HashMap<usize, usize> = HashMap::new();
+        let mut islands: Vec<Vec<usize>> = Vec::new();
+        for (pair_index, &(i, j)) in pairs.iter().enumerate() {
+            let dynamic = if self.bodies[i].is_dynamic() { i } else { j };
+            let root = find(&mut parent, dynamic);
+            let island = *island_of_root.entry(root).or_insert_with(|| {
+                islands.push(Vec::new());
+                islands.len() - 1
+            });
+            islands[island].push(pair_index);
+        }
+        islands
+    }
+
+    // Resolve pairs in order, recording events for sensor overlaps and resolved contacts
+    fn resolve_pairs_serial(&mut self, pairs: &[(usize, usize)]) {
+        let settings = self.contact_settings();
+        for &(i, j) in pairs {
             // Need to split borrow to avoid borrow checker issues
             let (body1, body2) = if i < j {
                 let (left, right) = self.bodies.split_at_mut(j);
@@ -223,8 +949,94 @@ impl World {
                 (&mut right[0], &mut left[j])
             };
 
-            Self::handle_collision(body1, body2);
+            let sensor = body1.is_sensor || body2.is_sensor;
+            if sensor || Self::handle_collision_with(body1, body2, &settings) {
+                self.collision_events.push(CollisionEvent {
+                    body_a: body1.id.clone(),
+                    body_b: body2.id.clone(),
+                    sensor,
+                });
+            }
+        }
+    }
+
+    // Same result as resolve_pairs_serial, with each island solved on its own thread.
+    // Islands work on copies of their bodies, the dynamic ones are written back after.
+    fn resolve_pairs_islands(&mut self, pairs: &[(usize, usize)]) {
+        let settings = self.contact_settings();
+        let islands = self.islands(pairs);
+        let bodies = &self.bodies;
+
+        let solved: Vec<IslandResult> = islands
+            .par_iter()
+            .map(|island| {
+                let mut local: Vec<(usize, RigidBody)> = Vec::new();
+                let slot = |index: usize, local: &mut Vec<(usize, RigidBody)>| {
+                    local
+                        .iter()
+                        .position(|(i, _)| *i == index)
+                        .unwrap_or_else(|| {
+                            local.push((index, bodies[index].clone()));
+                            local.len() - 1
+                        })
+                };
+
+                let mut events = Vec::new();
+                for &pair_index in island {
+                    let (i, j) = pairs[pair_index];
+                    let (a, b) = (slot(i, &mut local), slot(j, &mut local));
+                    let (body1, body2) = if a < b {
+                        let (left, right) = local.split_at_mut(b);
+                        (&mut left[a].1, &mut right[0].1)
+                    } else {
+                        let (left, right) = local.split_at_mut(a);
+                        (&mut right[0].1, &mut left[b].1)
+                    };
+
+                    let sensor = body1.is_sensor || body2.is_sensor;
+                    if sensor || Self::handle_collision_with(body1, body2, &settings) {
+                        let event = CollisionEvent {
+                            body_a: body1.id.clone(),
+                            body_b: body2.id.clone(),
+                            sensor,
+                        };
+                        events.push((pair_index, event));
+                    }
+                }
+                (local, events)
+            })
+            .collect();
+
+        // Events in pair order, as the serial loop would have produced them
+        let mut events = Vec::new();
+        for (local, island_events) in solved {
+            for (index, body) in local {
+                if body.is_dynamic() {
+                    self.bodies[index] = body;
+                }
+            }
+            events.extend(island_events);
         }
+        events.sort_by_key(|(pair_index, _)| *pair_index);
+        self.collision_events
+            .extend(events.into_iter().map(|(_, event)| event));
+    }
+
+    // Island setup costs more than it saves for a handful of contacts
+    fn resolve_pairs(&mut self, pairs: &[(usize, usize)]) {
+        if pairs.len() >= PARALLEL_RESOLVE_MIN_PAIRS {
+            self.resolve_pairs_islands(pairs);
+        } else {
+            self.resolve_pairs_serial(pairs);
+        }
+    }
+
+    //note: this is only for pong, complete physics sim works for all
+    // Pairs are filtered by collision layers (see PONG_LAYER_*), static pairs are skipped
+    // and sensor pairs only produce events
+    pub fn collide_pong(&mut self) {
+        let collision_pairs = self.collision_pairs();
+        self.resolve_pairs(&collision_pairs);
     }
     pub fn collide(&mut self) {
         let collision_pairs: Vec<(usize, usize)> = (0..self.bodies.len())
@@ -233,10 +1045,13 @@ impl World {
                 ((i + 1)..self.bodies.len())
                     .into_par_iter()
                     .filter_map(|j| {
-                        if Self::check_collision(&self.bodies[i], &self.bodies[j]) {
-                            println!(
+                        if Self::should_test_pair(&self.bodies[i], &self.bodies[j])
+                            && Self::check_collision(&self.bodies[i], &self.bodies[j])
+                        {
+                            trace!(
                                 "🔥 COLLISION DETECTED: {} and {}",
-                                self.bodies[i].id, self.bodies[j].id
+                                self.bodies[i].id,
+                                self.bodies[j].id
                             );
                             Some((i, j))
                         } else {
@@ -248,43 +1063,747 @@ impl World {
             .collect();
 
         if collision_pairs.is_empty() {
-            println!("No collisions detected this tick");
+            trace!("No collisions detected this tick");
         }
 
-        for (i, j) in collision_pairs {
-            // Need to split borrow to avoid borrow checker issues
-            let (body1, body2) = if i < j {
-                let (left, right) = self.bodies.split_at_mut(j);
-                (&mut left[i], &mut right[0])
-            } else {
-                let (left, right) = self.bodies.split_at_mut(i);
-                (&mut right[0], &mut left[j])
+        self.resolve_pairs(&collision_pairs);
+    }
+    pub fn tick(&mut self) {
+        let dt: f64 = 1.0 / self.tick_rate as f64;
+        // Per-body dump only when tracing, skipping the loop entirely otherwise
+        if log_enabled!(Level::Trace) {
+            for body in &self.bodies {
+                trace!(
+                    "Body '{}': position ({:.2}, {:.2}, {:.2})",
+                    body.id,
+                    body.position.x,
+                    body.position.y,
+                    body.position.z
+                );
+                trace!(
+                    "Body '{}': AABB min({:.2}, {:.2}, {:.2}) max({:.2}, {:.2}, {:.2})",
+                    body.id,
+                    body.aabb.min.x,
+                    body.aabb.min.y,
+                    body.aabb.min.z,
+                    body.aabb.max.x,
+                    body.aabb.max.y,
+                    body.aabb.max.z
+                );
+            }
+        }
+        self.step(dt);
+
+        //TODO: change this to colldie_pong when we start workign on the pong sim
+        self.collide();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::arena::ArenaConfig;
+    use crate::physics::object::BodyKind;
+
+    // Unit dynamic cube, mass 1 and fully elastic
+    fn cube(id: &str, position: Vec3, velocity: Vec3) -> RigidBody {
+        RigidBody::from_extents_with_id(
+            id.to_string(),
+            position,
+            velocity,
+            Vec3::new(0.5, 0.5, 0.5),
+            1.0,
+            1.0,
+            false,
+        )
+    }
+
+    fn world_of(bodies: Vec<RigidBody>) -> World {
+        let mut world = World::new_empty();
+        for body in bodies {
+            world.add_body(body);
+        }
+        world
+    }
+
+    fn pair_ids(world: &World) -> Vec<(&str, &str)> {
+        world
+            .collision_pairs()
+            .into_iter()
+            .map(|(i, j)| (world.bodies[i].id.as_str(), world.bodies[j].id.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn pong_layers_filter_pairs() {
+        // Everything overlaps everything, only the layers decide
+        let mut ball = cube("ball", Vec3::zero(), Vec3::zero());
+        ball.set_collision_layers(PONG_LAYER_BALL, PONG_MASK_BALL);
+        let mut paddle1 = cube("paddle1", Vec3::new(0.2, 0.0, 0.0), Vec3::zero());
+        paddle1.set_collision_layers(PONG_LAYER_PADDLE, PONG_MASK_PADDLE);
+        let mut paddle2 = cube("paddle2", Vec3::new(-0.2, 0.0, 0.0), Vec3::zero());
+        paddle2.set_collision_layers(PONG_LAYER_PADDLE, PONG_MASK_PADDLE);
+        let mut wall = cube("wall", Vec3::new(0.0, 0.2, 0.0), Vec3::zero());
+        wall.set_collision_layers(PONG_LAYER_WALL, PONG_MASK_WALL);
+        let world = world_of(vec![ball, paddle1, paddle2, wall]);
+
+        assert_eq!(
+            pair_ids(&world),
+            [
+                ("ball", "paddle1"),
+                ("ball", "paddle2"),
+                ("ball", "wall"),
+                ("paddle1", "wall"),
+                ("paddle2", "wall"),
+            ]
+        );
+    }
+
+    #[test]
+    fn elastic_head_on_collision_conserves_energy_and_momentum() {
+        let mut heavy = cube("heavy", Vec3::new(2.0, 0.0, 0.0), Vec3::zero());
+        heavy.set_mass(3.0);
+        let mut world = world_of(vec![
+            cube("light", Vec3::new(-2.0, 0.0, 0.0), Vec3::new(4.0, 0.0, 0.0)),
+            heavy,
+        ]);
+        let (energy, momentum) = (world.total_kinetic_energy(), world.total_momentum());
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+
+        // 1D elastic result for masses 1 and 3 with the heavy body at rest
+        let light = world.get_body("light").unwrap().velocity;
+        let heavy = world.get_body("heavy").unwrap().velocity;
+        assert!(light.approx_eq(&Vec3::new(-2.0, 0.0, 0.0), 1e-4));
+        assert!(heavy.approx_eq(&Vec3::new(2.0, 0.0, 0.0), 1e-4));
+        assert!((world.total_kinetic_energy() - energy).abs() < 1e-3);
+        assert!(world.total_momentum().approx_eq(&momentum, 1e-4));
+    }
+
+    #[test]
+    fn raycast_hits_the_nearest_box() {
+        let world = world_of(vec![
+            cube("far", Vec3::new(10.0, 0.0, 0.0), Vec3::zero()),
+            cube("near", Vec3::new(5.0, 0.0, 0.0), Vec3::zero()),
+        ]);
+
+        let hit = world
+            .raycast(Vec3::zero(), Vec3::new(2.0, 0.0, 0.0), 100.0)
+            .unwrap();
+        assert_eq!(hit.body_id, "near");
+        assert_eq!(hit.distance, 4.5);
+        assert_eq!(hit.point, Vec3::new(4.5, 0.0, 0.0));
+        assert_eq!(hit.normal, Vec3::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn raycast_miss_returns_none() {
+        let world = world_of(vec![cube("box", Vec3::new(5.0, 0.0, 0.0), Vec3::zero())]);
+
+        assert_eq!(
+            world.raycast(Vec3::zero(), Vec3::new(0.0, 1.0, 0.0), 100.0),
+            None
+        );
+        assert_eq!(
+            world.raycast(Vec3::zero(), Vec3::new(1.0, 0.0, 0.0), 4.0),
+            None
+        );
+    }
+
+    #[test]
+    fn sensor_overlap_reports_without_resolving() {
+        let zone = AABB::from_center_size(&Vec3::zero(), &Vec3::new(4.0, 4.0, 4.0));
+        let velocity = Vec3::new(1.0, 0.5, 0.0);
+        let mut world = world_of(vec![
+            RigidBody::new_sensor("goal".to_string(), Vec3::zero(), zone),
+            cube("ball", Vec3::zero(), velocity),
+        ]);
+
+        world.step(1.0 / 60.0);
+
+        assert_eq!(
+            world.collision_events,
+            [CollisionEvent {
+                body_a: "goal".to_string(),
+                body_b: "ball".to_string(),
+                sensor: true,
+            }]
+        );
+        assert_eq!(world.get_body("ball").unwrap().velocity, velocity);
+    }
+
+    #[test]
+    fn idle_body_sleeps_and_wakes_on_impulse() {
+        let mut world = world_of(vec![cube("box", Vec3::zero(), Vec3::new(0.01, 0.0, 0.0))]);
+        let dt = 0.1;
+        let steps_to_sleep = (SLEEP_AFTER / dt as f32).ceil() as usize;
+
+        for _ in 0..steps_to_sleep - 1 {
+            world.step(dt);
+        }
+        assert!(world.get_body("box").unwrap().is_awake());
+        world.step(dt);
+        let body = world.get_body("box").unwrap();
+        assert!(!body.is_awake());
+        assert_eq!(body.velocity, Vec3::zero());
+
+        // Asleep it doesn't integrate, an impulse gets it moving again
+        let position = body.position;
+        world.step(dt);
+        assert_eq!(world.get_body("box").unwrap().position, position);
+        let body = world.get_body_mut("box").unwrap();
+        body.apply_impulse(Vec3::new(2.0, 0.0, 0.0));
+        assert!(body.is_awake());
+        world.step(dt);
+        let body = world.get_body("box").unwrap();
+        assert!(body.position.x > position.x);
+    }
+
+    #[test]
+    fn combine_modes() {
+        assert_eq!(CombineMode::Min.combine(0.2, 0.8), 0.2);
+        assert_eq!(CombineMode::Max.combine(0.2, 0.8), 0.8);
+        assert_eq!(CombineMode::Average.combine(0.2, 0.8), 0.5);
+        assert_eq!(CombineMode::Multiply.combine(0.5, 0.8), 0.4);
+        assert_eq!(CombineMode::default(), CombineMode::Min);
+    }
+
+    #[test]
+    fn restitution_combine_mode_applies_to_contacts() {
+        let bounce = |mode: CombineMode| {
+            let mut a = cube("a", Vec3::new(-0.45, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+            let mut b = cube("b", Vec3::new(0.45, 0.0, 0.0), Vec3::new(-2.0, 0.0, 0.0));
+            a.restitution = 0.5;
+            b.restitution = 1.0;
+            let settings = ContactSettings {
+                restitution_combine: mode,
+                ..ContactSettings::default()
             };
+            assert!(World::handle_collision_with(&mut a, &mut b, &settings));
+            a.velocity.x
+        };
+        // Equal masses closing at 4, so each leaves at 2 * the combined restitution
+        assert!((bounce(CombineMode::Min) + 1.0).abs() < 1e-5);
+        assert!((bounce(CombineMode::Max) + 2.0).abs() < 1e-5);
+        assert!((bounce(CombineMode::Average) + 1.5).abs() < 1e-5);
+        assert!((bounce(CombineMode::Multiply) + 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn islands_resolve_like_the_serial_loop() {
+        // Far below PARALLEL_RESOLVE_MIN_PAIRS, so both paths are called directly: one
+        // closing pair, and a chain of three a long way off that forms a second island
+        let bodies = vec![
+            cube("a", Vec3::new(-0.4, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0)),
+            cube("b", Vec3::new(0.4, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)),
+            cube("c", Vec3::new(10.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0)),
+            cube("d", Vec3::new(10.9, 0.1, 0.0), Vec3::zero()),
+            cube("e", Vec3::new(11.8, 0.0, 0.0), Vec3::new(-1.0, 0.5, 0.0)),
+        ];
+        let mut serial = world_of(bodies.clone());
+        let mut islands = world_of(bodies);
+        let pairs = serial.collision_pairs();
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(serial.islands(&pairs).len(), 2);
+
+        serial.resolve_pairs_serial(&pairs);
+        islands.resolve_pairs_islands(&pairs);
 
-            Self::handle_collision(body1, body2);
+        assert_eq!(serial.collision_events, islands.collision_events);
+        assert_eq!(serial.collision_events.len(), 3);
+        for (s, i) in serial.bodies.iter().zip(&islands.bodies) {
+            assert_eq!(s.id, i.id);
+            assert_eq!(s.aabb.get_center(), i.aabb.get_center());
+            assert_eq!(s.velocity, i.velocity);
         }
     }
-    pub fn tick(&mut self) {
-        let dt: f64 = 1.0 / self.tick_rate as f64;
-        for body in &self.bodies {
-            println!(
-                "Body '{}': position ({:.2}, {:.2}, {:.2})",
-                body.id, body.position.x, body.position.y, body.position.z
+
+    #[test]
+    fn box_resting_on_a_box_settles() {
+        let floor = RigidBody::from_extents_with_id(
+            "floor".to_string(),
+            Vec3::new(0.0, -0.5, 0.0),
+            Vec3::zero(),
+            Vec3::new(5.0, 0.5, 5.0),
+            0.0,
+            1.0,
+            true,
+        );
+        let mut world = world_of(vec![
+            floor,
+            cube("box", Vec3::new(0.0, 0.5, 0.0), Vec3::zero()),
+        ]);
+        world.add_force_field(ForceField::Uniform(Vec3::new(0.0, -9.81, 0.0)));
+
+        // Let it sink into the slop first, then it should hold still
+        for _ in 0..60 {
+            world.tick();
+        }
+        let contact = World::contact(&world.bodies[1], &world.bodies[0]).unwrap();
+        assert_eq!(contact.normal, Vec3::new(0.0, 1.0, 0.0));
+
+        let (mut lowest, mut highest) = (f32::INFINITY, f32::NEG_INFINITY);
+        for _ in 0..600 {
+            world.tick();
+            let body = &world.bodies[1];
+            lowest = lowest.min(body.position.y);
+            highest = highest.max(body.position.y);
+            assert_eq!(body.position.x, 0.0);
+            assert_eq!(body.position.z, 0.0);
+        }
+        assert!(lowest > 0.5 - 2.0 * PENETRATION_SLOP, "sank to {lowest}");
+        assert!(
+            highest - lowest < PENETRATION_SLOP,
+            "moved {}",
+            highest - lowest
+        );
+    }
+
+    #[test]
+    fn restitution_threshold_stops_micro_bounces() {
+        // Highest upward speed over the last second of a 0.9 restitution box dropped 5 cm
+        let bounce_after_settling = |threshold: f32| {
+            let floor = RigidBody::from_extents_with_id(
+                "floor".to_string(),
+                Vec3::new(0.0, -0.5, 0.0),
+                Vec3::zero(),
+                Vec3::new(5.0, 0.5, 5.0),
+                0.0,
+                1.0,
+                true,
+            );
+            let mut body = cube("box", Vec3::new(0.0, 0.55, 0.0), Vec3::zero());
+            body.restitution = 0.9;
+            body.restitution_threshold = threshold;
+            let mut world = world_of(vec![floor, body]);
+            world.add_force_field(ForceField::Uniform(Vec3::new(0.0, -9.81, 0.0)));
+            for _ in 0..240 {
+                world.step(1.0 / 60.0);
+            }
+            let mut highest = f32::NEG_INFINITY;
+            for _ in 0..60 {
+                world.step(1.0 / 60.0);
+                highest = highest.max(world.bodies[1].velocity.y);
+            }
+            highest
+        };
+        assert_eq!(RESTITUTION_THRESHOLD, 0.0);
+        // Left at 0.0 it never stops, every step reflects the speed gravity just added
+        assert!(bounce_after_settling(0.0) > SLEEP_SPEED_THRESHOLD);
+        assert!(bounce_after_settling(1.5).abs() < 1e-4);
+
+        // The world-wide threshold applies to every body
+        let (mut a, mut b) = (
+            cube("a", Vec3::new(-0.45, 0.0, 0.0), Vec3::new(0.2, 0.0, 0.0)),
+            cube("b", Vec3::new(0.45, 0.0, 0.0), Vec3::new(-0.2, 0.0, 0.0)),
+        );
+        let settings = ContactSettings {
+            restitution_threshold: 0.5,
+            ..ContactSettings::default()
+        };
+        assert!(World::handle_collision_with(&mut a, &mut b, &settings));
+        assert!(a.velocity.x.abs() < 1e-6 && b.velocity.x.abs() < 1e-6);
+    }
+
+    #[test]
+    fn box_stack_comes_to_rest() {
+        let floor = RigidBody::from_extents_with_id(
+            "floor".to_string(),
+            Vec3::new(0.0, -0.5, 0.0),
+            Vec3::zero(),
+            Vec3::new(5.0, 0.5, 5.0),
+            0.0,
+            1.0,
+            true,
+        );
+        let mut bodies = vec![floor];
+        for i in 0..3 {
+            let mut body = cube(
+                &format!("box{i}"),
+                Vec3::new(0.0, 0.5 + i as f32, 0.0),
+                Vec3::zero(),
             );
-            println!(
-                "Body '{}': AABB min({:.2}, {:.2}, {:.2}) max({:.2}, {:.2}, {:.2})",
+            body.restitution = 0.0;
+            bodies.push(body);
+        }
+        let mut world = world_of(bodies);
+        world.add_force_field(ForceField::Uniform(Vec3::new(0.0, -9.81, 0.0)));
+
+        for _ in 0..120 {
+            world.tick();
+        }
+        let settled: Vec<f32> = world.bodies[1..].iter().map(|b| b.position.y).collect();
+        let mut drift = vec![0.0f32; settled.len()];
+        for _ in 0..600 {
+            world.tick();
+            for (i, body) in world.bodies[1..].iter().enumerate() {
+                drift[i] = drift[i].max((body.position.y - settled[i]).abs());
+            }
+        }
+        for (i, body) in world.bodies[1..].iter().enumerate() {
+            // Each box sits on the one below, sunk by no more than the slop per contact
+            let resting = 0.5 + i as f32;
+            assert!(
+                body.position.y > resting - (i + 1) as f32 * 2.0 * PENETRATION_SLOP,
+                "{} sank to {}",
+                body.id,
+                body.position.y
+            );
+            assert!(
+                drift[i] < PENETRATION_SLOP,
+                "{} moved {}",
                 body.id,
-                body.aabb.min.x,
-                body.aabb.min.y,
-                body.aabb.min.z,
-                body.aabb.max.x,
-                body.aabb.max.y,
-                body.aabb.max.z
+                drift[i]
             );
+            assert_eq!((body.position.x, body.position.z), (0.0, 0.0));
         }
-        self.step(dt);
+    }
 
-        //TODO: change this to colldie_pong when we start workign on the pong sim
-        self.collide();
+    #[test]
+    fn lock_z_keeps_bodies_on_the_plane() {
+        let mut world = world_of(vec![cube("ball", Vec3::zero(), Vec3::new(1.0, 2.0, 3.0))]);
+        world.lock_z = true;
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        let ball = world.get_body("ball").unwrap();
+        assert_eq!(ball.position.z, 0.0);
+        assert_eq!(ball.velocity.z, 0.0);
+        assert!(ball.position.x > 0.9 && ball.position.y > 1.9);
+
+        // Shallowest along z, but z is never picked as the normal
+        let a = cube("a", Vec3::zero(), Vec3::zero());
+        let b = cube("b", Vec3::new(0.7, 0.0, 0.9), Vec3::zero());
+        let normal = |use_z| World::contact_on(&a, &b, use_z).unwrap().normal;
+        assert_eq!(normal(true), Vec3::new(0.0, 0.0, -1.0));
+        assert_eq!(normal(false), Vec3::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn pair_restitution_overrides_the_bodies() {
+        let mut world = World::new_empty();
+        world.set_pair_restitution("ball", "wall", 1.0);
+        world.set_pair_restitution("paddle", "ball", 0.8);
+        let settings = world.contact_settings();
+
+        // Bounce off a static box with id `other`, everything else at restitution 0.2
+        let bounce = |other: &str| {
+            let mut ball = cube("ball", Vec3::zero(), Vec3::new(4.0, 0.0, 0.0));
+            let mut target = cube(other, Vec3::new(0.9, 0.0, 0.0), Vec3::zero());
+            target.set_kind(BodyKind::Static);
+            ball.restitution = 0.2;
+            target.restitution = 0.2;
+            assert!(World::handle_collision_with(
+                &mut ball,
+                &mut target,
+                &settings
+            ));
+            ball.velocity.x
+        };
+        assert!((bounce("wall_0") + 4.0).abs() < 1e-5);
+        assert!((bounce("paddle1") + 3.2).abs() < 1e-5);
+        assert!((bounce("box") + 0.8).abs() < 1e-5);
+    }
+
+    #[test]
+    fn bodies_with_prefix_keep_insertion_order() {
+        let mut world = world_of(vec![
+            cube("ball_1", Vec3::zero(), Vec3::zero()),
+            cube("paddle1", Vec3::new(5.0, 0.0, 0.0), Vec3::zero()),
+            cube("ball", Vec3::new(-5.0, 0.0, 0.0), Vec3::zero()),
+        ]);
+        let ids: Vec<&str> = world
+            .bodies_with_prefix("ball")
+            .map(|b| b.id.as_str())
+            .collect();
+        assert_eq!(ids, ["ball_1", "ball"]);
+        assert_eq!(world.bodies_with_prefix("wall").count(), 0);
+
+        for ball in world.bodies_with_prefix_mut("ball") {
+            ball.velocity = Vec3::new(1.0, 0.0, 0.0);
+        }
+        assert_eq!(world.get_body("paddle1").unwrap().velocity, Vec3::zero());
+        assert!(world
+            .bodies_with_prefix("ball")
+            .all(|b| b.velocity == Vec3::new(1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn pair_restitution_updates_in_either_order() {
+        let mut world = World::new_empty();
+        world.set_pair_restitution("ball", "wall", 1.0);
+        world.set_pair_restitution("wall", "ball", 0.4);
+        assert_eq!(
+            world.restitution_overrides,
+            [RestitutionOverride {
+                prefix_a: "ball".to_string(),
+                prefix_b: "wall".to_string(),
+                restitution: 0.4,
+            }]
+        );
+        world.set_pair_restitution("ball", "paddle", 0.8);
+        assert_eq!(world.restitution_overrides.len(), 2);
+    }
+
+    #[test]
+    fn current_contacts_follow_the_overlap() {
+        let mut world = world_of(vec![
+            cube("a", Vec3::zero(), Vec3::zero()),
+            cube("b", Vec3::new(0.8, 0.0, 0.0), Vec3::zero()),
+        ]);
+        assert_eq!(
+            world.current_contacts(),
+            [("a".to_string(), "b".to_string())]
+        );
+
+        world
+            .get_body_mut("b")
+            .unwrap()
+            .update_position(2.0, 0.0, 0.0);
+        assert!(world.current_contacts().is_empty());
+    }
+
+    #[test]
+    fn separated_pair_is_left_alone() {
+        // Closing, but still 0.1 apart on x
+        let mut a = cube("a", Vec3::zero(), Vec3::new(1.0, 0.0, 0.0));
+        let mut b = cube("b", Vec3::new(1.1, 0.2, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        let (before_a, before_b) = (a.clone(), b.clone());
+
+        assert!(!World::handle_collision(&mut a, &mut b));
+        for (body, before) in [(&a, &before_a), (&b, &before_b)] {
+            assert_eq!(body.position, before.position);
+            assert_eq!(body.velocity, before.velocity);
+        }
+    }
+
+    #[test]
+    fn overlapping_pair_separates_along_the_shallowest_axis() {
+        // Overlaps 0.2 on x, 0.7 on y and 0.9 on z
+        let mut a = cube("a", Vec3::zero(), Vec3::new(1.0, 0.0, 0.0));
+        let mut b = cube("b", Vec3::new(0.8, 0.3, 0.1), Vec3::new(-1.0, 0.0, 0.0));
+
+        assert!(World::handle_collision(&mut a, &mut b));
+        assert!(a.velocity.x < 0.0 && b.velocity.x > 0.0);
+        assert!(a.position.x < 0.0 && b.position.x > 0.8);
+        assert_eq!((a.position.y, a.position.z), (0.0, 0.0));
+        assert_eq!((b.position.y, b.position.z), (0.3, 0.1));
+        assert_eq!((a.velocity.y, b.velocity.y), (0.0, 0.0));
+    }
+
+    #[test]
+    fn inactive_body_is_skipped_until_reactivated() {
+        let mut world = world_of(vec![
+            cube("ball", Vec3::zero(), Vec3::new(6.0, 0.0, 0.0)),
+            cube("box", Vec3::new(0.8, 0.0, 0.0), Vec3::zero()),
+        ]);
+        assert!(!world.set_active("missing", false));
+        assert!(world.set_active("ball", false));
+
+        for _ in 0..10 {
+            world.tick();
+        }
+        let ball = world.get_body("ball").unwrap();
+        assert_eq!(ball.position, Vec3::zero());
+        assert!(world.collision_events.is_empty());
+        assert!(!world
+            .query_radius(Vec3::zero(), 0.1)
+            .contains(&"ball".to_string()));
+
+        assert!(world.set_active("ball", true));
+        world.tick();
+        assert!(world.get_body("ball").unwrap().position.x != 0.0);
+        assert!(world
+            .collision_events
+            .iter()
+            .any(|e| e.is_between("ball", "box")));
+    }
+
+    #[test]
+    fn distance_between_measures_the_gap_between_aabbs() {
+        let world = world_of(vec![
+            cube("a", Vec3::zero(), Vec3::zero()),
+            cube("b", Vec3::new(0.8, 0.0, 0.0), Vec3::zero()),
+            cube("c", Vec3::new(4.0, 4.0, 0.0), Vec3::zero()),
+        ]);
+        assert_eq!(world.distance_between("a", "b"), Some(0.0));
+        // 3 apart on both x and y once the half extents are taken off
+        let gap = world.distance_between("a", "c").unwrap();
+        assert!((gap - 18.0f32.sqrt()).abs() < 1e-6);
+        assert_eq!(world.distance_between("c", "a"), Some(gap));
+        assert_eq!(world.distance_between("a", "missing"), None);
+        assert_eq!(world.distance_between("missing", "a"), None);
+    }
+
+    #[test]
+    fn save_and_load_round_trip_bodies_and_settings() {
+        let mut world = world_of(vec![
+            cube("ball", Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 0.0, 0.0)),
+            cube("box", Vec3::new(-2.0, 0.0, 0.0), Vec3::zero()),
+        ]);
+        world.restitution_combine = CombineMode::Max;
+        world.penetration_slop = 0.05;
+        world.lock_z = true;
+        world.set_pair_restitution("ball", "box", 0.3);
+        world.step(0.5);
+
+        let path = std::env::temp_dir().join(format!("volley_world_{}.json", std::process::id()));
+        world.save_to_file(&path).unwrap();
+        let loaded = World::load_from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.body_map, world.body_map);
+        for (l, w) in loaded.bodies.iter().zip(&world.bodies) {
+            assert_eq!(
+                (&l.id, l.position, l.velocity),
+                (&w.id, w.position, w.velocity)
+            );
+        }
+        assert_eq!(loaded.contact_settings(), world.contact_settings());
+        assert_eq!(loaded.tick_count(), 1);
+        assert_eq!(loaded.sim_time(), 0.5);
+        assert!(matches!(
+            World::load_from_file(&path),
+            Err(WorldFileError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn query_radius_counts_the_nearest_point_of_the_aabb() {
+        // The wall's centre is 3 away but its near face is at x = 1, the cube's at x = 1.2
+        let wall = RigidBody::from_extents_with_id(
+            "wall".to_string(),
+            Vec3::new(3.0, 0.0, 0.0),
+            Vec3::zero(),
+            Vec3::new(2.0, 2.0, 2.0),
+            0.0,
+            1.0,
+            true,
+        );
+        let world = world_of(vec![
+            wall,
+            cube("cube", Vec3::new(-1.7, 0.0, 0.0), Vec3::zero()),
+        ]);
+        assert_eq!(world.query_radius(Vec3::zero(), 1.1), ["wall"]);
+        assert_eq!(world.query_radius(Vec3::zero(), 1.25), ["wall", "cube"]);
+    }
+
+    #[test]
+    fn tick_count_counts_steps_and_substeps() {
+        let mut world = world_of(vec![cube("ball", Vec3::zero(), Vec3::zero())]);
+        assert_eq!(world.tick_count(), 0);
+        for _ in 0..3 {
+            world.step(1.0 / 60.0);
+        }
+        assert_eq!(world.tick_count(), 3);
+        world.step_substeps(1.0 / 60.0, 4);
+        assert_eq!(world.tick_count(), 7);
+        // Zero substeps still runs one step
+        world.step_substeps(1.0 / 60.0, 0);
+        assert_eq!(world.tick_count(), 8);
+    }
+
+    #[test]
+    fn sim_time_sums_the_stepped_dt() {
+        let mut world = world_of(vec![cube("ball", Vec3::zero(), Vec3::zero())]);
+        assert_eq!(world.sim_time(), 0.0);
+        world.step(0.25);
+        world.step(0.5);
+        assert_eq!(world.sim_time(), 0.75);
+        // Substeps split the dt without adding to it
+        world.step_substeps(0.5, 4);
+        assert_eq!(world.sim_time(), 1.25);
+    }
+
+    #[test]
+    fn substeps_reduce_wall_penetration() {
+        // 1.5 units per step at 30 units/s, the wall's face starts 1.1 past the ball's
+        let penetration_with = |substeps| {
+            let wall = RigidBody::from_extents_with_id(
+                "wall".to_string(),
+                Vec3::new(2.6, 0.0, 0.0),
+                Vec3::zero(),
+                Vec3::new(1.0, 5.0, 5.0),
+                0.0,
+                1.0,
+                true,
+            );
+            let ball = cube("ball", Vec3::zero(), Vec3::new(30.0, 0.0, 0.0));
+            let mut world = world_of(vec![wall, ball]);
+            world.step_substeps(1.0 / 20.0, substeps);
+            let (wall, ball) = (&world.bodies[0], &world.bodies[1]);
+            (ball.aabb.max.x - wall.aabb.min.x).max(0.0)
+        };
+        let (one, eight) = (penetration_with(1), penetration_with(8));
+        assert!(one > 0.0);
+        assert!(eight < one, "8 substeps {eight} vs 1 substep {one}");
+    }
+
+    #[test]
+    fn overlaps_at_tests_a_position_without_moving_anything() {
+        let mut ball = cube("ball", Vec3::zero(), Vec3::zero());
+        ball.set_collision_layers(PONG_LAYER_BALL, PONG_MASK_BALL);
+        let mut paddle = cube("paddle1", Vec3::new(3.0, 0.0, 0.0), Vec3::zero());
+        paddle.set_collision_layers(PONG_LAYER_PADDLE, PONG_MASK_PADDLE);
+        let mut other_ball = cube("ball_1", Vec3::new(3.0, 0.5, 0.0), Vec3::zero());
+        other_ball.set_collision_layers(PONG_LAYER_BALL, PONG_MASK_BALL);
+        let world = world_of(vec![ball, paddle, other_ball]);
+
+        // Balls don't collide with balls, and the ball never overlaps itself
+        assert_eq!(
+            world.overlaps_at("ball", Vec3::new(2.5, 0.0, 0.0)),
+            ["paddle1"]
+        );
+        assert!(world.overlaps_at("ball", Vec3::zero()).is_empty());
+        assert!(world
+            .overlaps_at("missing", Vec3::new(3.0, 0.0, 0.0))
+            .is_empty());
+        assert_eq!(world.get_body("ball").unwrap().position, Vec3::zero());
+    }
+
+    #[test]
+    fn ball_between_paddles_contacts_both_and_no_walls() {
+        let (mut world, _) = ArenaConfig::default().pong_world();
+        let ball = world.get_body("ball").unwrap();
+        let reach = ball.get_half_extents().x
+            + world.get_body("paddle1").unwrap().get_half_extents().x
+            - 0.1;
+        world
+            .get_body_mut("paddle1")
+            .unwrap()
+            .update_position(-reach, 0.0, 0.0);
+        world
+            .get_body_mut("paddle2")
+            .unwrap()
+            .update_position(reach, 0.0, 0.0);
+
+        let mut contacts = world.contacts_for("ball");
+        contacts.sort();
+        assert_eq!(contacts, ["paddle1", "paddle2"]);
+        assert!(world.contacts_for("paddle1").iter().all(|id| id == "ball"));
+    }
+
+    #[test]
+    fn plane_bounce_reflects_the_normal_velocity() {
+        let floor = Plane::new("floor".to_string(), Vec3::new(0.0, 2.0, 0.0), 0.0);
+        let settings = ContactSettings::default();
+        let mut ball = cube("ball", Vec3::new(0.0, 0.4, 0.0), Vec3::new(3.0, -5.0, 0.0));
+
+        assert!(World::handle_plane_collision(&mut ball, &floor, &settings));
+        assert_eq!(ball.velocity, Vec3::new(3.0, 5.0, 0.0));
+        assert_eq!(ball.aabb.min.y, 0.0);
+
+        // Restitution combines with the plane's 1.0 under Min
+        ball.restitution = 0.5;
+        ball.update_position(0.0, 0.4, 0.0);
+        ball.velocity = Vec3::new(3.0, -5.0, 0.0);
+        assert!(World::handle_plane_collision(&mut ball, &floor, &settings));
+        assert_eq!(ball.velocity, Vec3::new(3.0, 2.5, 0.0));
+
+        // Clear of the plane, nothing happens
+        assert!(!World::handle_plane_collision(&mut ball, &floor, &settings));
     }
 }
@@ -1,3 +1,4 @@
+use std::time::{Duration, Instant};
 use zmq::{Context, Socket};
 
 pub struct IPCChannel {
@@ -55,6 +56,67 @@ impl IPCChannel {
         Ok(Self { context, socket })
     }
 
+    // SUB socket that only receives frames published under `topic`
+    pub fn new_sub_topic(endpoint: &str, topic: &str) -> Result<Self, zmq::Error> {
+        let context = Context::new();
+        let socket = context.socket(zmq::SUB)?;
+        socket.connect(endpoint)?;
+        socket.set_subscribe(topic.as_bytes())?;
+        Ok(Self { context, socket })
+    }
+
+    // Unbound socket for the with_* setters below, finish with bind or connect.
+    // Options must be set first, most only apply to connections made after them.
+    pub fn new(socket_type: zmq::SocketType) -> Result<Self, zmq::Error> {
+        let context = Context::new();
+        let socket = context.socket(socket_type)?;
+        Ok(Self { context, socket })
+    }
+
+    // Keep only the newest message per peer. Breaks multipart frames and delta
+    // broadcasts, which need every message to apply.
+    pub fn with_conflate(self, conflate: bool) -> Result<Self, zmq::Error> {
+        self.socket.set_conflate(conflate)?;
+        Ok(self)
+    }
+
+    // Send and receive high-water mark in messages, 0 means unbounded
+    pub fn with_hwm(self, hwm: i32) -> Result<Self, zmq::Error> {
+        self.socket.set_sndhwm(hwm)?;
+        self.socket.set_rcvhwm(hwm)?;
+        Ok(self)
+    }
+
+    // How long unsent messages hold up closing the socket, 0 drops them immediately
+    pub fn with_linger(self, linger_ms: i32) -> Result<Self, zmq::Error> {
+        self.socket.set_linger(linger_ms)?;
+        Ok(self)
+    }
+
+    pub fn bind(self, endpoint: &str) -> Result<Self, zmq::Error> {
+        self.socket.bind(endpoint)?;
+        Ok(self)
+    }
+
+    pub fn connect(self, endpoint: &str) -> Result<Self, zmq::Error> {
+        self.socket.connect(endpoint)?;
+        Ok(self)
+    }
+
+    // SUB sockets only, an empty topic receives everything
+    pub fn subscribe(self, topic: &[u8]) -> Result<Self, zmq::Error> {
+        self.socket.set_subscribe(topic)?;
+        Ok(self)
+    }
+
+    // Publish with the topic prepended to the same frame, subscribers strip topic.len() bytes
+    pub fn send_topic(&self, topic: &str, data: &[u8]) -> Result<(), zmq::Error> {
+        let mut frame = Vec::with_capacity(topic.len() + data.len());
+        frame.extend_from_slice(topic.as_bytes());
+        frame.extend_from_slice(data);
+        self.socket.send(frame, 0)
+    }
+
     // Raw send bytes
     pub fn send_bytes(&self, data: &[u8]) -> Result<(), zmq::Error> {
         self.socket.send(data, 0)
@@ -76,3 +138,90 @@ impl IPCChannel {
         self.socket.recv_bytes(0)
     }
 }
+
+// Topic prefix for the main world's state broadcasts, so subscribers never see room frames
+pub const WORLD_TOPIC: &str = "world/";
+
+// Topic prefix for a room's state broadcasts, the trailing '/' keeps "a" from matching "ab"
+pub fn room_topic(room_id: &str) -> String {
+    format!("room/{}/", room_id)
+}
+
+// Client-side liveness tracking for the PUB state stream
+pub struct Heartbeat {
+    pub timeout: Duration,
+    pub last_tick_id: Option<u64>,
+    last_seen: Instant,
+}
+
+impl Heartbeat {
+    pub fn new(timeout_ms: u64) -> Self {
+        Self {
+            timeout: Duration::from_millis(timeout_ms),
+            last_tick_id: None,
+            last_seen: Instant::now(),
+        }
+    }
+
+    // Record a received tick, only newer ticks count as a sign of life
+    pub fn observe(&mut self, tick_id: u64) {
+        if self.last_tick_id.is_none_or(|last| tick_id > last) {
+            self.last_tick_id = Some(tick_id);
+            self.last_seen = Instant::now();
+        }
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.last_seen.elapsed() > self.timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hwm_and_linger_are_applied() {
+        let channel = IPCChannel::new(zmq::PUB)
+            .unwrap()
+            .with_hwm(16)
+            .unwrap()
+            .with_linger(0)
+            .unwrap();
+        assert_eq!(channel.socket.get_sndhwm().unwrap(), 16);
+        assert_eq!(channel.socket.get_rcvhwm().unwrap(), 16);
+        assert_eq!(channel.socket.get_linger().unwrap(), 0);
+    }
+
+    #[test]
+    fn conflated_sub_keeps_only_the_newest_message() {
+        let publisher = IPCChannel::new(zmq::PUB)
+            .unwrap()
+            .bind("tcp://127.0.0.1:*")
+            .unwrap();
+        let endpoint = publisher.socket.get_last_endpoint().unwrap().unwrap();
+        let subscriber = IPCChannel::new(zmq::SUB)
+            .unwrap()
+            .with_conflate(true)
+            .unwrap()
+            .connect(&endpoint)
+            .unwrap()
+            .subscribe(b"")
+            .unwrap();
+
+        // Wait out the subscription handshake
+        let connected = (0..100).any(|_| {
+            publisher.send_bytes(b"ready").unwrap();
+            subscriber.recv_bytes_timeout(10).is_ok()
+        });
+        assert!(connected);
+        while subscriber.recv_bytes_nonblocking().is_ok() {}
+
+        for i in 0..5u8 {
+            publisher.send_bytes(&[i]).unwrap();
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(subscriber.recv_bytes_nonblocking().unwrap(), [4]);
+        assert!(subscriber.recv_bytes_nonblocking().is_err());
+    }
+}
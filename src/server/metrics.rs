@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// Engine totals since it was created (or since reset_metrics), see Engine::metrics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EngineMetrics {
+    pub steps: u64,      // Main world ticks, none while paused or after game over
+    pub actions: u64,    // Actions that passed the limits and were applied, rooms included
+    pub collisions: u64, // Resolved contacts, sensor overlaps not included
+    pub broadcasts: u64,
+    pub physics_time: Duration, // Spent in ticks, rooms and the tick hook included
+    pub serialization_time: Duration, // Spent encoding and sending state
+}
+
+impl EngineMetrics {
+    pub fn average_step_time(&self) -> Duration {
+        if self.steps == 0 {
+            Duration::ZERO
+        } else {
+            self.physics_time / self.steps as u32
+        }
+    }
+
+    pub fn average_broadcast_time(&self) -> Duration {
+        if self.broadcasts == 0 {
+            Duration::ZERO
+        } else {
+            self.serialization_time / self.broadcasts as u32
+        }
+    }
+}
+
+impl std::fmt::Display for EngineMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} steps ({:.3} ms avg), {} broadcasts ({:.3} ms avg), {} actions, {} collisions",
+            self.steps,
+            self.average_step_time().as_secs_f64() * 1000.0,
+            self.broadcasts,
+            self.average_broadcast_time().as_secs_f64() * 1000.0,
+            self.actions,
+            self.collisions
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::arena::ArenaConfig;
+    use crate::physics::Vector3;
+    use crate::server::models::Action;
+    use crate::server::server::Engine;
+
+    #[test]
+    fn steps_and_actions_are_counted() {
+        let arena = ArenaConfig::default();
+        let (world, _) = arena.pong_world();
+        let mut engine =
+            Engine::new_server("inproc://actions", "inproc://state", world, arena).unwrap();
+        assert_eq!(engine.metrics().steps, 0);
+
+        for i in 0..5 {
+            let paddle = engine.world.get_body("paddle1").unwrap();
+            let action = Action {
+                body_id: paddle.id.clone(),
+                velocity: Vector3::new(0.0, 1.0, 0.0),
+                position: paddle.position,
+                aabb: paddle.aabb.clone(),
+                mass: paddle.mass,
+                restitution: paddle.restitution,
+                dynamic: true,
+                friction: None,
+            };
+            engine.post_action(action).unwrap();
+            engine.step(1.0 / 60.0);
+            assert_eq!(engine.metrics().actions, i + 1);
+        }
+        let metrics = engine.metrics();
+        assert_eq!(metrics.steps, 5);
+        assert!(metrics.physics_time > std::time::Duration::ZERO);
+
+        engine.reset_metrics();
+        assert_eq!(engine.metrics().steps, 0);
+    }
+}
@@ -1,3 +1,8 @@
 pub mod ipc;
+pub mod metrics;
 pub mod models;
+pub mod recorder;
+pub mod room;
+// Engine lives in server::server, renaming it would break every downstream import
+#[allow(clippy::module_inception)]
 pub mod server;
@@ -1,6 +1,19 @@
+use crate::game::arena::ArenaConfig;
+use crate::game::game_engine::{GamePhase, GameState};
+use crate::physics::force_field::ForceField;
 use crate::physics::object::{RigidBody, Vec3, AABB};
 use bincode;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Wall-clock used for WorldState timestamps, ms since UNIX epoch
+pub fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
 
 #[derive(Debug)]
 pub enum SerializationError {
@@ -8,6 +21,9 @@ pub enum SerializationError {
     DecodeError(bincode::error::DecodeError),
     MsgPackEncode(rmp_serde::encode::Error),
     MsgPackDecode(rmp_serde::decode::Error),
+    Json(serde_json::Error),
+    Decompress(lz4_flex::block::DecompressError),
+    UnknownEncoding(u8), // Byte after WorldState::FRAME_MAGIC was not a known encoding
 }
 
 impl From<bincode::error::EncodeError> for SerializationError {
@@ -34,12 +50,27 @@ impl From<rmp_serde::decode::Error> for SerializationError {
     }
 }
 
+impl From<serde_json::Error> for SerializationError {
+    fn from(err: serde_json::Error) -> Self {
+        SerializationError::Json(err)
+    }
+}
+
+impl From<lz4_flex::block::DecompressError> for SerializationError {
+    fn from(err: lz4_flex::block::DecompressError) -> Self {
+        SerializationError::Decompress(err)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Command {
     GetState,
     PostAction,
     Step,
     Reset,
+    Pause,
+    Resume,
+    GetConfig, // Answered with a SimConfig instead of the state on the command socket
 }
 impl Command {
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
@@ -50,17 +81,33 @@ impl Command {
         let bytes = bincode::serde::encode_to_vec(self, bincode::config::standard())?;
         Ok(bytes)
     }
+    pub fn from_json(json: &str) -> Result<Self, SerializationError> {
+        Ok(serde_json::from_str(json)?)
+    }
+    pub fn to_json(&self) -> Result<String, SerializationError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, SerializationError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, SerializationError> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Action {
     pub body_id: String,
+    // World units per second: World::step moves bodies by velocity * dt, so the same
+    // velocity covers the same distance per second at any physics rate
     pub velocity: Vec3,
     pub position: Vec3,
     pub aabb: AABB,
     pub mass: f32,
     pub restitution: f32,
-    pub dynamic: bool,
+    pub dynamic: bool, // false makes the body static, kinematic bodies stay kinematic
+    #[serde(default)]
+    pub friction: Option<f32>, // None leaves the body's friction as it is
 } //update based on any rigidbody properties
 
 impl Action {
@@ -78,6 +125,28 @@ impl Action {
     pub fn to_msgpack(&self) -> Result<Vec<u8>, SerializationError> {
         Ok(rmp_serde::to_vec_named(self)?)
     }
+    pub fn from_json(json: &str) -> Result<Self, SerializationError> {
+        Ok(serde_json::from_str(json)?)
+    }
+    pub fn to_json(&self) -> Result<String, SerializationError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+// Action addressed to one of the engine's extra rooms
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoomAction {
+    pub room_id: String,
+    pub action: Action,
+}
+
+impl RoomAction {
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, SerializationError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, SerializationError> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -86,14 +155,57 @@ pub struct WorldState {
     pub time: f32,
     pub score_player1: u32,
     pub score_player2: u32,
+    pub tick_id: u64,      // Monotonically increasing per snapshot
+    pub timestamp_ms: u64, // Server wall-clock, ms since UNIX epoch
+    #[serde(default)]
+    pub phase: GamePhase,
+    #[serde(default)]
+    pub tick_count: u64, // World::tick_count, physics steps rather than broadcasts
+    #[serde(default)]
+    pub sim_time: f64, // World::sim_time, unlike `time` it never drifts from the steps taken
 }
 impl WorldState {
+    // Bincode frames are FRAME_MAGIC, one of the ENCODING_* bytes, then the body, so
+    // receivers don't need to know the sender's setting. 0xFF never starts a bincode
+    // varint, so anything else is read as a headerless frame from before the header.
+    pub const FRAME_MAGIC: u8 = 0xFF;
+    pub const ENCODING_RAW: u8 = 0;
+    pub const ENCODING_LZ4: u8 = 1;
+
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, SerializationError> {
-        let (state, _) = bincode::serde::decode_from_slice(bytes, bincode::config::standard())?;
+        let (state, _) = match bytes {
+            [Self::FRAME_MAGIC, Self::ENCODING_RAW, body @ ..] => {
+                bincode::serde::decode_from_slice(body, bincode::config::standard())?
+            }
+            [Self::FRAME_MAGIC, Self::ENCODING_LZ4, body @ ..] => {
+                let body = lz4_flex::decompress_size_prepended(body)?;
+                bincode::serde::decode_from_slice(&body, bincode::config::standard())?
+            }
+            [Self::FRAME_MAGIC, header, ..] => {
+                return Err(SerializationError::UnknownEncoding(*header))
+            }
+            _ => bincode::serde::decode_from_slice(bytes, bincode::config::standard())?,
+        };
         Ok(state)
     }
     pub fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
-        let bytes = bincode::serde::encode_to_vec(self, bincode::config::standard())?;
+        self.to_bytes_with_compression(false)
+    }
+    // LZ4 pays off for large worlds, small states can come out slightly bigger
+    pub fn to_bytes_with_compression(
+        &self,
+        compressed: bool,
+    ) -> Result<Vec<u8>, SerializationError> {
+        let body = bincode::serde::encode_to_vec(self, bincode::config::standard())?;
+        let (header, body) = if compressed {
+            (Self::ENCODING_LZ4, lz4_flex::compress_prepend_size(&body))
+        } else {
+            (Self::ENCODING_RAW, body)
+        };
+        let mut bytes = Vec::with_capacity(body.len() + 2);
+        bytes.push(Self::FRAME_MAGIC);
+        bytes.push(header);
+        bytes.extend_from_slice(&body);
         Ok(bytes)
     }
     pub fn from_msgpack(bytes: &[u8]) -> Result<Self, SerializationError> {
@@ -102,4 +214,428 @@ impl WorldState {
     pub fn to_msgpack(&self) -> Result<Vec<u8>, SerializationError> {
         Ok(rmp_serde::to_vec_named(self)?)
     }
+    pub fn from_json(json: &str) -> Result<Self, SerializationError> {
+        Ok(serde_json::from_str(json)?)
+    }
+    pub fn to_json(&self) -> Result<String, SerializationError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+// What a client needs to run the same World::step locally for prediction, see
+// Engine::sim_config. The world has no separate gravity, it is a uniform force field.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SimConfig {
+    pub tick_rate: f32, // Physics steps per second
+    pub substeps: usize,
+    pub force_fields: Vec<ForceField>,
+    pub lock_z: bool,
+    pub arena: ArenaConfig,
+}
+impl SimConfig {
+    // Sum of the uniform force fields
+    pub fn gravity(&self) -> Vec3 {
+        self.force_fields
+            .iter()
+            .filter_map(|field| match field {
+                ForceField::Uniform(force) => Some(*force),
+                _ => None,
+            })
+            .fold(Vec3::zero(), |sum, force| sum + force)
+    }
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, SerializationError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, SerializationError> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
+}
+
+// Everything needed to save a match and resume it exactly, see Engine::snapshot
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EngineSnapshot {
+    pub state: WorldState,
+    pub game_state: GameState,
+    pub ball_speedup: f32,
+    pub max_ball_speed_scale: f32,
+    pub ball_speed_scales: HashMap<usize, f32>, // Speed-up reached per ball body index
+}
+impl EngineSnapshot {
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, SerializationError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, SerializationError> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
+    pub fn from_json(json: &str) -> Result<Self, SerializationError> {
+        Ok(serde_json::from_str(json)?)
+    }
+    pub fn to_json(&self) -> Result<String, SerializationError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+// Flat f32 layout for numpy (`np.frombuffer(buf, dtype="<f4")`):
+//   header: [body_count, time, score_player1, score_player2]
+//   then body_count rows of FLAT_BODY_STRIDE columns:
+//   [id_hash, pos x, pos y, pos z, vel x, vel y, vel z, mass, restitution, dynamic (0/1),
+//    friction]
+pub const FLAT_HEADER_LEN: usize = 4;
+pub const FLAT_BODY_STRIDE: usize = 11;
+
+// FNV-1a folded to 24 bits so the hash survives the trip through an f32 exactly
+pub fn body_id_hash(id: &str) -> f32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for byte in id.bytes() {
+        hash ^= byte as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    ((hash >> 24) ^ (hash & 0x00ff_ffff)) as f32
+}
+
+impl WorldState {
+    pub fn to_flat_f32(&self) -> Vec<f32> {
+        let mut flat = Vec::with_capacity(FLAT_HEADER_LEN + self.bodies.len() * FLAT_BODY_STRIDE);
+        flat.extend_from_slice(&[
+            self.bodies.len() as f32,
+            self.time,
+            self.score_player1 as f32,
+            self.score_player2 as f32,
+        ]);
+        for body in &self.bodies {
+            flat.extend_from_slice(&[
+                body_id_hash(&body.id),
+                body.position.x,
+                body.position.y,
+                body.position.z,
+                body.velocity.x,
+                body.velocity.y,
+                body.velocity.z,
+                body.mass,
+                body.restitution,
+                if body.is_static() { 0.0 } else { 1.0 },
+                body.friction,
+            ]);
+        }
+        flat
+    }
+
+    // Ids, extents and anything else not in the layout come from `template`, rows are
+    // matched to its bodies by id hash; None if the buffer is truncated, the body count is
+    // corrupt or a hash is unknown
+    pub fn from_flat_f32(flat: &[f32], template: &WorldState) -> Option<WorldState> {
+        let header = flat.get(..FLAT_HEADER_LEN)?;
+        let body_count = header[0] as usize; // Saturates, NaN and negatives become 0
+        let rows_end = body_count
+            .checked_mul(FLAT_BODY_STRIDE)?
+            .checked_add(FLAT_HEADER_LEN)?;
+        let rows = flat.get(FLAT_HEADER_LEN..rows_end)?;
+
+        let mut state = template.clone();
+        state.time = header[1];
+        state.score_player1 = header[2] as u32;
+        state.score_player2 = header[3] as u32;
+        state.bodies = rows
+            .chunks_exact(FLAT_BODY_STRIDE)
+            .map(|row| {
+                let mut body = template
+                    .bodies
+                    .iter()
+                    .find(|b| body_id_hash(&b.id) == row[0])?
+                    .clone();
+                body.update_position(row[1], row[2], row[3]);
+                body.update_velocity(row[4], row[5], row[6]);
+                body.set_mass(row[7]);
+                body.restitution = row[8];
+                body.apply_dynamic_flag(row[9] != 0.0);
+                body.friction = row[10];
+                Some(body)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(state)
+    }
+}
+
+// Bodies that changed in any sent field since the base snapshot, keyed by body id
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WorldStateDelta {
+    pub base_tick_id: u64,
+    pub tick_id: u64,
+    pub timestamp_ms: u64,
+    pub time: f32,
+    pub score_player1: u32,
+    pub score_player2: u32,
+    #[serde(default)]
+    pub phase: GamePhase,
+    #[serde(default)]
+    pub tick_count: u64,
+    #[serde(default)]
+    pub sim_time: f64,
+    pub bodies: Vec<RigidBody>,
+}
+
+impl WorldState {
+    pub fn diff(&self, prev: &WorldState) -> WorldStateDelta {
+        let previous: HashMap<&str, &RigidBody> =
+            prev.bodies.iter().map(|b| (b.id.as_str(), b)).collect();
+        let bodies = self
+            .bodies
+            .iter()
+            .filter(|body| match previous.get(body.id.as_str()) {
+                Some(p) => body.differs_from(p),
+                None => true, // New body
+            })
+            .cloned()
+            .collect();
+
+        WorldStateDelta {
+            base_tick_id: prev.tick_id,
+            tick_id: self.tick_id,
+            timestamp_ms: self.timestamp_ms,
+            time: self.time,
+            score_player1: self.score_player1,
+            score_player2: self.score_player2,
+            phase: self.phase,
+            tick_count: self.tick_count,
+            sim_time: self.sim_time,
+            bodies,
+        }
+    }
+
+    pub fn apply_delta(&mut self, delta: &WorldStateDelta) {
+        let mut index: HashMap<String, usize> = self
+            .bodies
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b.id.clone(), i))
+            .collect();
+        for changed in &delta.bodies {
+            match index.get(&changed.id) {
+                Some(&i) => self.bodies[i] = changed.clone(),
+                None => {
+                    index.insert(changed.id.clone(), self.bodies.len());
+                    self.bodies.push(changed.clone());
+                }
+            }
+        }
+        self.tick_id = delta.tick_id;
+        self.timestamp_ms = delta.timestamp_ms;
+        self.time = delta.time;
+        self.score_player1 = delta.score_player1;
+        self.score_player2 = delta.score_player2;
+        self.phase = delta.phase;
+        self.tick_count = delta.tick_count;
+        self.sim_time = delta.sim_time;
+    }
+}
+
+// Broadcast envelope used when delta compression is enabled
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum StateMessage {
+    Keyframe(WorldState),
+    Delta(WorldStateDelta),
+}
+
+impl StateMessage {
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, SerializationError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, SerializationError> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
+
+    // Rebuild the full state on the client, deltas are dropped until a keyframe
+    // arrives or when they don't apply on top of the last known tick
+    pub fn resolve(self, last: &mut Option<WorldState>) -> Option<WorldState> {
+        match self {
+            StateMessage::Keyframe(state) => {
+                *last = Some(state.clone());
+                Some(state)
+            }
+            StateMessage::Delta(delta) => {
+                let state = last.as_mut()?;
+                if state.tick_id != delta.base_tick_id {
+                    return None;
+                }
+                state.apply_delta(&delta);
+                Some(state.clone())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::world::World;
+
+    fn pong_state() -> WorldState {
+        let (world, _) = ArenaConfig::default().pong_world();
+        WorldState {
+            bodies: world.bodies,
+            time: 1.5,
+            score_player1: 2,
+            score_player2: 3,
+            tick_id: 10,
+            timestamp_ms: 1_000,
+            phase: GamePhase::Playing,
+            tick_count: 90,
+            sim_time: 1.5,
+        }
+    }
+
+    // WorldState has no PartialEq, the JSON form covers every field
+    fn assert_same(a: &WorldState, b: &WorldState) {
+        assert_eq!(a.to_json().unwrap(), b.to_json().unwrap());
+    }
+
+    #[test]
+    fn delta_after_ball_move_has_only_the_ball() {
+        let prev = pong_state();
+        let mut next = prev.clone();
+        next.tick_id += 1;
+        let ball = next.bodies.iter_mut().find(|b| b.id == "ball").unwrap();
+        ball.update_position(1.0, 2.0, 0.0);
+
+        let delta = next.diff(&prev);
+        let ids: Vec<&str> = delta.bodies.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, ["ball"]);
+        assert_eq!(delta.base_tick_id, prev.tick_id);
+
+        let mut rebuilt = prev.clone();
+        rebuilt.apply_delta(&delta);
+        assert_same(&rebuilt, &next);
+    }
+
+    #[test]
+    fn flat_f32_round_trip_is_lossless() {
+        let template = pong_state();
+        let mut state = template.clone();
+        state.time = 12.25;
+        state.score_player1 = 7;
+        state.score_player2 = 4;
+        for (i, body) in state.bodies.iter_mut().enumerate() {
+            let offset = i as f32 * 0.1;
+            body.update_position(body.position.x + offset, -1.5 + offset, 0.3);
+            body.update_velocity(3.0 - offset, offset, -0.7);
+            body.restitution = 0.9 - offset * 0.5;
+        }
+        let ball = state.bodies.iter_mut().find(|b| b.id == "ball").unwrap();
+        ball.set_mass(2.5);
+
+        let flat = state.to_flat_f32();
+        assert_eq!(
+            flat.len(),
+            FLAT_HEADER_LEN + state.bodies.len() * FLAT_BODY_STRIDE
+        );
+        let rebuilt = WorldState::from_flat_f32(&flat, &template).unwrap();
+        assert_same(&rebuilt, &state);
+    }
+
+    #[test]
+    fn flat_f32_with_a_corrupt_body_count_is_rejected() {
+        let template = pong_state();
+        let mut flat = template.to_flat_f32();
+        for count in [f32::MAX, 1e30, usize::MAX as f32, f32::INFINITY] {
+            flat[0] = count;
+            assert!(WorldState::from_flat_f32(&flat, &template).is_none());
+        }
+        // One body more than the buffer holds
+        flat[0] = (template.bodies.len() + 1) as f32;
+        assert!(WorldState::from_flat_f32(&flat, &template).is_none());
+    }
+
+    #[test]
+    fn bytes_round_trip_raw_lz4_and_legacy() {
+        let state = pong_state();
+        for compressed in [false, true] {
+            let bytes = state.to_bytes_with_compression(compressed).unwrap();
+            assert_same(&WorldState::from_bytes(&bytes).unwrap(), &state);
+        }
+        // Headerless frames from before the encoding header still decode
+        let legacy = bincode::serde::encode_to_vec(&state, bincode::config::standard()).unwrap();
+        assert_same(&WorldState::from_bytes(&legacy).unwrap(), &state);
+
+        let unknown = [WorldState::FRAME_MAGIC, 7, 0];
+        assert!(matches!(
+            WorldState::from_bytes(&unknown),
+            Err(SerializationError::UnknownEncoding(7))
+        ));
+    }
+
+    #[test]
+    fn lz4_is_smaller_for_a_large_world() {
+        let mut state = pong_state();
+        state.bodies = World::with_random_bodies(500, 50.0, 7).bodies;
+        let raw = state.to_bytes_with_compression(false).unwrap();
+        let lz4 = state.to_bytes_with_compression(true).unwrap();
+        assert!(
+            lz4.len() < raw.len(),
+            "lz4 {} vs raw {}",
+            lz4.len(),
+            raw.len()
+        );
+    }
+
+    #[test]
+    fn friction_survives_every_encoding() {
+        let template = pong_state();
+        let mut state = template.clone();
+        for (i, body) in state.bodies.iter_mut().enumerate() {
+            body.friction = 0.15 * (i + 1) as f32;
+        }
+        let frictions: Vec<f32> = state.bodies.iter().map(|b| b.friction).collect();
+        let of =
+            |state: &WorldState| -> Vec<f32> { state.bodies.iter().map(|b| b.friction).collect() };
+
+        let bytes = WorldState::from_bytes(&state.to_bytes().unwrap()).unwrap();
+        assert_eq!(of(&bytes), frictions);
+        let msgpack = WorldState::from_msgpack(&state.to_msgpack().unwrap()).unwrap();
+        assert_eq!(of(&msgpack), frictions);
+        let flat = WorldState::from_flat_f32(&state.to_flat_f32(), &template).unwrap();
+        assert_eq!(of(&flat), frictions);
+
+        let ball = &state.bodies[0];
+        let action = Action {
+            body_id: ball.id.clone(),
+            velocity: ball.velocity,
+            position: ball.position,
+            aabb: ball.aabb.clone(),
+            mass: ball.mass,
+            restitution: ball.restitution,
+            dynamic: true,
+            friction: Some(0.35),
+        };
+        let bytes = Action::from_bytes(&action.to_bytes().unwrap()).unwrap();
+        assert_eq!(bytes.friction, Some(0.35));
+        let msgpack = Action::from_msgpack(&action.to_msgpack().unwrap()).unwrap();
+        assert_eq!(msgpack.friction, Some(0.35));
+    }
+
+    #[test]
+    fn delta_carries_every_sent_field() {
+        let prev = pong_state();
+        let changes: [fn(&mut RigidBody); 6] = [
+            |b| b.active = false,
+            |b| b.sleeping = true,
+            |b| b.orientation = glam::Quat::from_rotation_y(0.5),
+            |b| b.set_mass(3.0),
+            |b| b.restitution = 0.25,
+            |b| b.set_kind(crate::physics::object::BodyKind::Kinematic),
+        ];
+        for change in changes {
+            let mut next = prev.clone();
+            change(&mut next.bodies[0]);
+            let delta = next.diff(&prev);
+            assert_eq!(delta.bodies.len(), 1);
+            let mut rebuilt = prev.clone();
+            rebuilt.apply_delta(&delta);
+            assert_same(&rebuilt, &next);
+        }
+
+        // The timer is never sent, so it doesn't count as a change
+        let mut next = prev.clone();
+        next.bodies[0].sleep_timer = 0.3;
+        assert!(next.diff(&prev).bodies.is_empty());
+    }
 }
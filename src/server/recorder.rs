@@ -0,0 +1,156 @@
+use crate::server::models::{SerializationError, WorldState};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// File layout: repeated [u32 little-endian length][WorldState::to_bytes frame] frames
+
+#[derive(Debug)]
+pub enum RecorderError {
+    Io(io::Error),
+    Serialization(SerializationError),
+}
+
+impl From<io::Error> for RecorderError {
+    fn from(err: io::Error) -> Self {
+        RecorderError::Io(err)
+    }
+}
+
+impl From<SerializationError> for RecorderError {
+    fn from(err: SerializationError) -> Self {
+        RecorderError::Serialization(err)
+    }
+}
+
+pub struct Recorder {
+    writer: BufWriter<File>,
+    pub frames_written: usize,
+    pub compress: bool, // LZ4 each frame, replay detects it per frame
+}
+
+impl Recorder {
+    // Appends to an existing recording instead of truncating it
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, RecorderError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Recorder {
+            writer: BufWriter::new(file),
+            frames_written: 0,
+            compress: false,
+        })
+    }
+
+    pub fn record(&mut self, state: &WorldState) -> Result<(), RecorderError> {
+        let bytes = state.to_bytes_with_compression(self.compress)?;
+        self.writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&bytes)?;
+        self.frames_written += 1;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), RecorderError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+pub struct Replayer {
+    reader: BufReader<File>,
+}
+
+impl Replayer {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RecorderError> {
+        Ok(Replayer {
+            reader: BufReader::new(File::open(path)?),
+        })
+    }
+
+    // None at a clean end of file, a truncated frame is an error
+    pub fn next_frame(&mut self) -> Option<Result<WorldState, RecorderError>> {
+        let mut len_bytes = [0u8; 4];
+        match self.reader.read_exact(&mut len_bytes) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return None,
+            Err(err) => return Some(Err(err.into())),
+        }
+
+        let mut bytes = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+        if let Err(err) = self.reader.read_exact(&mut bytes) {
+            return Some(Err(err.into()));
+        }
+        Some(WorldState::from_bytes(&bytes).map_err(RecorderError::from))
+    }
+
+    // Feed every frame to `on_frame`, sleeping so frames keep their recorded spacing in `time`
+    pub fn play<F: FnMut(&WorldState)>(mut self, mut on_frame: F) -> Result<(), RecorderError> {
+        let started = Instant::now();
+        let mut first_time = None;
+        while let Some(frame) = self.next_frame() {
+            let state = frame?;
+            let offset = state.time - *first_time.get_or_insert(state.time);
+            let due = Duration::from_secs_f32(offset.max(0.0));
+            if let Some(wait) = due.checked_sub(started.elapsed()) {
+                std::thread::sleep(wait);
+            }
+            on_frame(&state);
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for Replayer {
+    type Item = Result<WorldState, RecorderError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::arena::ArenaConfig;
+    use crate::game::game_engine::GamePhase;
+
+    #[test]
+    fn replay_matches_the_recording() {
+        let path = std::env::temp_dir().join(format!("volley_replay_{}.bin", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let (mut world, _) = ArenaConfig::default().pong_world();
+        let mut recorded = Vec::new();
+        let mut recorder = Recorder::new(&path).unwrap();
+        for tick_id in 0..50 {
+            world.step(1.0 / 60.0);
+            let state = WorldState {
+                bodies: world.bodies.clone(),
+                time: world.sim_time() as f32,
+                score_player1: 0,
+                score_player2: 0,
+                tick_id,
+                timestamp_ms: 0,
+                phase: GamePhase::Playing,
+                tick_count: world.tick_count(),
+                sim_time: world.sim_time(),
+            };
+            recorder.compress = tick_id >= 25; // Replay detects the encoding per frame
+            recorder.record(&state).unwrap();
+            recorded.push(state.to_bytes().unwrap());
+        }
+        recorder.flush().unwrap();
+        assert_eq!(recorder.frames_written, 50);
+
+        let replayed: Vec<WorldState> = Replayer::open(&path)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(replayed.len(), 50);
+        for (i, state) in replayed.iter().enumerate() {
+            assert_eq!(state.tick_id, i as u64);
+            assert_eq!(state.to_bytes().unwrap(), recorded[i]);
+        }
+    }
+}
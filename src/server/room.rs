@@ -0,0 +1,82 @@
+use crate::game::arena::ArenaConfig;
+use crate::game::game_engine::{GamePhase, GameState, MissingPaddle};
+use crate::physics::{Vector3, World};
+use crate::server::models::WorldState;
+use log::info;
+use rand::Rng;
+
+// Re-serve from the centre towards the player who scored, scorer 1 sits on the -x side
+pub fn serve_ball(world: &mut World, ball_index: usize, scorer: u8) {
+    let ball = &mut world.bodies[ball_index];
+    let rand_z = rand::rng().random_range(-2.0..2.0);
+    ball.position = Vector3::new(0.0, 0.0, 0.0);
+    ball.velocity = if scorer == 1 {
+        Vector3::new(-7.0, 4.0, rand_z)
+    } else {
+        Vector3::new(7.0, 4.0, rand_z)
+    };
+    ball.wake();
+}
+
+// Centre a ball and stop it, used when a match ends
+pub fn park_ball(world: &mut World, ball_index: usize) {
+    let ball = &mut world.bodies[ball_index];
+    ball.update_position(0.0, 0.0, 0.0);
+    ball.velocity = Vector3::zero();
+}
+
+// An extra game hosted by the engine next to its main world, broadcast on its own topic
+pub struct Room {
+    pub id: String,
+    pub world: World,
+    pub game_state: GameState,
+    pub tick_id: u64,
+}
+
+impl Room {
+    pub fn new(id: String, world: World) -> Result<Self, MissingPaddle> {
+        let game_state = GameState::for_world(&world)?;
+        Ok(Room {
+            id,
+            world,
+            game_state,
+            tick_id: 0,
+        })
+    }
+
+    pub fn step(&mut self, dt: f64, arena: &ArenaConfig) {
+        if self.game_state.is_game_over() {
+            return;
+        }
+        self.world.step(dt);
+
+        for (ball_index, scorer) in self.game_state.scored_balls(&self.world.bodies, arena) {
+            if self.game_state.is_game_over() {
+                break;
+            }
+            if let GamePhase::GameOver { winner } = self.game_state.award_point(scorer) {
+                info!("🏆 Room {}: player {} wins!", self.id, winner);
+                for &i in &self.game_state.ball_indices {
+                    park_ball(&mut self.world, i);
+                }
+            } else {
+                serve_ball(&mut self.world, ball_index, scorer);
+            }
+        }
+    }
+
+    pub fn get_state(&mut self, time: f32, timestamp_ms: u64) -> WorldState {
+        self.tick_id += 1;
+        WorldState {
+            bodies: self.world.bodies.clone(),
+            time,
+            score_player1: self.game_state.score_player1,
+            score_player2: self.game_state.score_player2,
+            tick_id: self.tick_id,
+            timestamp_ms,
+            phase: self.game_state.phase,
+            tick_count: self.world.tick_count(),
+            sim_time: self.world.sim_time(),
+        }
+    }
+}
@@ -1,16 +1,128 @@
-use crate::game::game_engine::GameState;
-use crate::physics::{Vector3, World};
-use crate::server::ipc::IPCChannel;
-use crate::server::models::{Action, WorldState};
-use rand::Rng;
+use crate::game::ai::PaddleAi;
+use crate::game::arena::ArenaConfig;
+use crate::game::game_engine::{GamePhase, GameState, MissingPaddle};
+use crate::physics::object::AABB;
+use crate::physics::world::{PONG_LAYER_BALL, PONG_MASK_BALL};
+use crate::physics::{RigidBody, Vector3, World};
+use crate::server::ipc::{room_topic, IPCChannel, WORLD_TOPIC};
+use crate::server::metrics::EngineMetrics;
+use crate::server::models::{
+    unix_time_ms, Action, Command, EngineSnapshot, RoomAction, SimConfig, StateMessage, WorldState,
+};
+use crate::server::recorder::Recorder;
+use crate::server::room::{park_ball, serve_ball, Room};
+use log::{debug, info, warn};
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
+// Limits on what a client action may do, None on the engine means actions apply verbatim
+#[derive(Debug, Clone)]
+pub struct ActionLimits {
+    pub max_speed: f32,
+    pub bounds: AABB,
+    pub allowed_prefixes: Vec<String>, // Body ids a client may control, e.g. "paddle"
+}
+
+impl ActionLimits {
+    // Clients may only drive the paddles, and only inside the arena walls
+    pub fn pong(arena: &ArenaConfig) -> Self {
+        ActionLimits {
+            max_speed: 30.0,
+            bounds: arena.bounds(),
+            allowed_prefixes: vec!["paddle".to_string()],
+        }
+    }
+
+    pub fn allows_body(&self, body_id: &str) -> bool {
+        self.allowed_prefixes
+            .iter()
+            .any(|prefix| body_id.starts_with(prefix.as_str()))
+    }
+
+    pub fn in_bounds(&self, position: &Vector3) -> bool {
+        self.bounds.contains_point(position)
+    }
+
+    // Scale the velocity down to max_speed, keeping its direction
+    pub fn clamp_velocity(&self, velocity: Vector3) -> Vector3 {
+        let speed = velocity.length();
+        if speed > self.max_speed && speed > 0.0 {
+            velocity * (self.max_speed / speed)
+        } else {
+            velocity
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionError {
+    UnknownBody(String),
+    UnknownRoom(String),
+    BodyNotAllowed(String),
+    OutOfBounds(String),
+}
+
+impl std::fmt::Display for ActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ActionError::UnknownBody(id) => write!(f, "body '{}' not found", id),
+            ActionError::UnknownRoom(id) => write!(f, "room '{}' not found", id),
+            ActionError::BodyNotAllowed(id) => {
+                write!(f, "body '{}' is not client controllable", id)
+            }
+            ActionError::OutOfBounds(id) => write!(f, "position for '{}' is outside the arena", id),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+pub const DEFAULT_PHYSICS_HZ: f64 = 120.0;
+pub const DEFAULT_BROADCAST_HZ: f64 = 60.0;
+// Queued state messages per subscriber, about a second at the default broadcast rate
+pub const STATE_HWM: i32 = 64;
+
+// Custom per-tick game rules, see Engine::set_tick_hook
+pub type TickHook = Box<dyn FnMut(&mut World, &mut GameState) + Send>;
+
+// The AI only takes over a paddle after clients stop sending actions for it
+const AI_TAKEOVER_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Action positions and velocities within this of the body's are float noise, not changes
+const ACTION_EPSILON: f32 = 1e-5;
+
+// How often the run loops log the metrics totals
+const METRICS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
 pub struct Engine {
-    pub action_channel: IPCChannel, // PULL for receiving actions
-    pub state_channel: IPCChannel,  // PUB for broadcasting state
+    pub action_channel: IPCChannel,          // PULL for receiving actions
+    pub state_channel: IPCChannel,           // PUB for broadcasting state
+    pub command_channel: Option<IPCChannel>, // REP for one-off commands, see bind_commands
     pub world: World,
+    initial_world: World,
+    pub arena: ArenaConfig,
     pub game_state: GameState,
     pub start_time: Instant,
+    pub tick_id: u64,
+    pub delta_broadcast: bool,
+    pub keyframe_interval: u64, // Full state every N broadcasts when sending deltas
+    pub action_limits: Option<ActionLimits>,
+    pub paused: bool,               // Physics is frozen but state keeps broadcasting
+    pub recorder: Option<Recorder>, // Every broadcast state is also written here
+    pub paddle_ai: Option<PaddleAi>,
+    pub ball_speedup: f32, // Ball speed multiplier applied on every paddle hit
+    pub max_ball_speed_scale: f32, // Cap on the total speed-up within one rally
+    pub physics_substeps: usize, // World steps per engine tick, see World::step_substeps
+    ball_speed_scales: HashMap<usize, f32>, // Current speed-up per ball body index
+    last_client_action: HashMap<String, Instant>,
+    tick_hook: Option<TickHook>,
+    rooms: HashMap<String, Room>, // Extra games, keyed by room id
+    physics_hz: f64,
+    broadcast_hz: f64, // 0 broadcasts every loop iteration
+    last_broadcast: Option<WorldState>,
+    deltas_since_keyframe: u64, // Counts broadcasts only, command replies also bump tick_id
+    metrics: EngineMetrics,
+    metrics_logged: Instant,
 }
 
 impl Engine {
@@ -19,9 +131,34 @@ impl Engine {
         action_endpoint: &str,
         state_endpoint: &str,
         world: World,
+        arena: ArenaConfig,
+    ) -> Result<Self, zmq::Error> {
+        Self::new_server_with_rates(
+            action_endpoint,
+            state_endpoint,
+            world,
+            arena,
+            DEFAULT_PHYSICS_HZ,
+            DEFAULT_BROADCAST_HZ,
+        )
+    }
+
+    // The world's tick_rate is overwritten with physics_hz so both agree
+    pub fn new_server_with_rates(
+        action_endpoint: &str,
+        state_endpoint: &str,
+        mut world: World,
+        arena: ArenaConfig,
+        physics_hz: f64,
+        broadcast_hz: f64,
     ) -> Result<Self, zmq::Error> {
+        world.tick_rate = physics_hz as f32;
         let action_channel = IPCChannel::new_pull(action_endpoint)?;
-        let state_channel = IPCChannel::new_pub(state_endpoint)?;
+        // Bound the queue per slow subscriber instead of buffering stale states
+        let state_channel = IPCChannel::new(zmq::PUB)?
+            .with_hwm(STATE_HWM)?
+            .with_linger(0)?
+            .bind(state_endpoint)?;
         let start_time = Instant::now();
 
         // Find paddle and ball indices
@@ -35,155 +172,606 @@ impl Engine {
             .iter()
             .position(|b| b.id == "paddle2")
             .unwrap_or(1);
-        let ball_index = world
-            .bodies
-            .iter()
-            .position(|b| b.id == "ball")
-            .unwrap_or(2);
+        let ball_index = world.bodies.iter().position(|b| b.id == "ball");
 
         let game_state = GameState::new(paddle1_index, paddle2_index, ball_index);
 
         Ok(Self {
             action_channel,
             state_channel,
+            command_channel: None,
+            initial_world: world.clone(),
             world,
+            arena,
             game_state,
             start_time,
+            tick_id: 0,
+            delta_broadcast: false,
+            keyframe_interval: 60,
+            action_limits: None,
+            paused: false,
+            recorder: None,
+            paddle_ai: None,
+            ball_speedup: 1.05,
+            max_ball_speed_scale: 2.0,
+            physics_substeps: 1,
+            ball_speed_scales: HashMap::new(),
+            last_client_action: HashMap::new(),
+            tick_hook: None,
+            rooms: HashMap::new(),
+            physics_hz,
+            broadcast_hz,
+            last_broadcast: None,
+            deltas_since_keyframe: 0,
+            metrics: EngineMetrics::default(),
+            metrics_logged: Instant::now(),
         })
     }
 
+    pub fn fixed_timestep(&self) -> f64 {
+        1.0 / self.physics_hz
+    }
+
+    pub fn state_interval(&self) -> Duration {
+        if self.broadcast_hz > 0.0 {
+            Duration::from_secs_f64(1.0 / self.broadcast_hz)
+        } else {
+            Duration::ZERO
+        }
+    }
+
     pub fn step(&mut self, dt: f64) {
-        self.world.step(dt);
+        // Frozen while paused, only an explicit Step command advances
+        if self.paused {
+            return;
+        }
+        self.advance(dt);
+    }
+
+    // One physics tick regardless of pause, timed for the metrics
+    fn advance(&mut self, dt: f64) {
+        let started = Instant::now();
+        self.advance_world(dt);
+        self.metrics.physics_time += started.elapsed();
+    }
+
+    // The main game stays frozen after game over, rooms keep going
+    fn advance_world(&mut self, dt: f64) {
+        for room in self.rooms.values_mut() {
+            room.step(dt, &self.arena);
+        }
+        if self.game_state.is_game_over() {
+            return;
+        }
+
+        self.world.step_substeps(dt, self.physics_substeps);
+        self.metrics.steps += 1;
+        self.metrics.collisions += self
+            .world
+            .collision_events
+            .iter()
+            .filter(|e| !e.sensor)
+            .count() as u64;
 
         // Constrain paddles within arena bounds
-        // self.constrain_paddles();
+        self.constrain_paddles();
+
+        // Speed a ball up on every paddle hit in its rally
+        let hit_balls: Vec<usize> = self
+            .world
+            .collision_events
+            .iter()
+            .filter(|e| !e.sensor && e.is_between("ball", "paddle"))
+            .filter_map(|e| {
+                let ball_id = if e.body_a.starts_with("ball") {
+                    &e.body_a
+                } else {
+                    &e.body_b
+                };
+                self.world.body_map.get(ball_id).copied()
+            })
+            .collect();
+        for ball_index in hit_balls {
+            self.speed_up_ball(ball_index);
+        }
+
+        // Check for scoring, only the ball that crossed a goal is reset
+        let scored = self
+            .game_state
+            .scored_balls(&self.world.bodies, &self.arena);
+        for (ball_index, scorer) in scored {
+            if self.game_state.is_game_over() {
+                break;
+            }
+            if let GamePhase::GameOver { winner } = self.game_state.award_point(scorer) {
+                info!("🏆 Player {} wins!", winner);
+                self.park_balls();
+            } else {
+                self.reset_ball(ball_index, scorer);
+            }
+        }
 
-        // Check for scoring
-        let ball = &self.world.bodies[self.game_state.ball_index];
-        if ball.position.x < -30.0 {
-            self.game_state.score_player2 += 1;
-            self.reset_ball(2);
-        } else if ball.position.x > 30.0 {
-            self.game_state.score_player1 += 1;
-            self.reset_ball(1);
+        if let Some(hook) = self.tick_hook.as_mut() {
+            hook(&mut self.world, &mut self.game_state);
         }
     }
 
+    // Runs once per physics tick of the main world, after the built-in paddle, speed-up
+    // and scoring rules; replaces any previous hook
+    pub fn set_tick_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&mut World, &mut GameState) + Send + 'static,
+    {
+        self.tick_hook = Some(Box::new(hook));
+    }
+
+    pub fn clear_tick_hook(&mut self) {
+        self.tick_hook = None;
+    }
+
+    // Spawn an extra ball at the centre, returns its body index
+    pub fn add_ball(&mut self, id: String, velocity: Vector3) -> usize {
+        let mut ball = RigidBody::from_extents_with_id(
+            id,
+            Vector3::zero(),
+            velocity,
+            Vector3::new(0.5, 0.5, 0.5),
+            1.0,
+            1.0,
+            false,
+        );
+        ball.set_collision_layers(PONG_LAYER_BALL, PONG_MASK_BALL);
+        let index = self.world.bodies.len();
+        self.world.add_body(ball);
+        self.game_state.ball_indices.push(index);
+        index
+    }
+
+    // Inner faces of the wall bodies on y and z, None if the world has no walls
+    fn arena_inner_bounds(&self) -> Option<AABB> {
+        let walls: Vec<&AABB> = self
+            .world
+            .bodies_with_prefix("wall")
+            .map(|b| &b.aabb)
+            .collect();
+        if walls.is_empty() {
+            return None;
+        }
+
+        // Arena centre is the middle of the space spanned by all the walls
+        let outer = walls[1..]
+            .iter()
+            .fold(walls[0].clone(), |acc, aabb| acc.merge(aabb));
+        let center = outer.get_center();
+
+        let mut inner = AABB::new(
+            Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        );
+        for aabb in &walls {
+            if aabb.max.y <= center.y {
+                inner.min.y = inner.min.y.max(aabb.max.y);
+            } else if aabb.min.y >= center.y {
+                inner.max.y = inner.max.y.min(aabb.min.y);
+            }
+            if aabb.max.z <= center.z {
+                inner.min.z = inner.min.z.max(aabb.max.z);
+            } else if aabb.min.z >= center.z {
+                inner.max.z = inner.max.z.min(aabb.min.z);
+            }
+        }
+        Some(inner)
+    }
+
+    // Keep the paddles between the walls, no-op if the paddles or walls are missing
     fn constrain_paddles(&mut self) {
-        let arena_half_height = 20.0;
-        let arena_half_depth = 20.0;
+        let Some(bounds) = self.arena_inner_bounds() else {
+            return;
+        };
 
-        // Constrain paddle1
-        let paddle1 = &mut self.world.bodies[self.game_state.paddle1_index];
-        let paddle1_half = paddle1.get_half_extents();
+        for id in ["paddle1", "paddle2"] {
+            let Some(paddle) = self.world.get_body_mut(id) else {
+                continue;
+            };
+            let half = paddle.get_half_extents();
+            let mut clamped = false;
 
-        if paddle1.position.y - paddle1_half.y < -arena_half_height {
-            paddle1.position.y = -arena_half_height + paddle1_half.y;
-            paddle1.velocity.y = 0.0;
-        } else if paddle1.position.y + paddle1_half.y > arena_half_height {
-            paddle1.position.y = arena_half_height - paddle1_half.y;
-            paddle1.velocity.y = 0.0;
+            if paddle.position.y - half.y < bounds.min.y {
+                paddle.position.y = bounds.min.y + half.y;
+                paddle.velocity.y = 0.0;
+                clamped = true;
+            } else if paddle.position.y + half.y > bounds.max.y {
+                paddle.position.y = bounds.max.y - half.y;
+                paddle.velocity.y = 0.0;
+                clamped = true;
+            }
+
+            if paddle.position.z - half.z < bounds.min.z {
+                paddle.position.z = bounds.min.z + half.z;
+                paddle.velocity.z = 0.0;
+                clamped = true;
+            } else if paddle.position.z + half.z > bounds.max.z {
+                paddle.position.z = bounds.max.z - half.z;
+                paddle.velocity.z = 0.0;
+                clamped = true;
+            }
+
+            if clamped {
+                paddle.compute_aabb();
+            }
         }
+    }
 
-        if paddle1.position.z - paddle1_half.z < -arena_half_depth {
-            paddle1.position.z = -arena_half_depth + paddle1_half.z;
-            paddle1.velocity.z = 0.0;
-        } else if paddle1.position.z + paddle1_half.z > arena_half_depth {
-            paddle1.position.z = arena_half_depth - paddle1_half.z;
-            paddle1.velocity.z = 0.0;
+    fn speed_up_ball(&mut self, ball_index: usize) {
+        let current = self
+            .ball_speed_scales
+            .get(&ball_index)
+            .copied()
+            .unwrap_or(1.0);
+        let scale = (current * self.ball_speedup).min(self.max_ball_speed_scale);
+        if scale <= current {
+            return;
         }
+        let ball = &mut self.world.bodies[ball_index];
+        ball.velocity = ball.velocity * (scale / current);
+        self.ball_speed_scales.insert(ball_index, scale);
+    }
 
-        // Constrain paddle2
-        let paddle2 = &mut self.world.bodies[self.game_state.paddle2_index];
-        let paddle2_half = paddle2.get_half_extents();
+    // Centre every ball and stop it, used when the match ends
+    fn park_balls(&mut self) {
+        self.ball_speed_scales.clear();
+        for &ball_index in &self.game_state.ball_indices {
+            park_ball(&mut self.world, ball_index);
+        }
+    }
 
-        if paddle2.position.y - paddle2_half.y < -arena_half_height {
-            paddle2.position.y = -arena_half_height + paddle2_half.y;
-            paddle2.velocity.y = 0.0;
-        } else if paddle2.position.y + paddle2_half.y > arena_half_height {
-            paddle2.position.y = arena_half_height - paddle2_half.y;
-            paddle2.velocity.y = 0.0;
+    // New match with zeroed scores, serving every ball towards player 1 (-x) as if they scored
+    pub fn restart(&mut self) {
+        self.game_state.restart();
+        for ball_index in self.game_state.ball_indices.clone() {
+            self.reset_ball(ball_index, 1);
         }
+    }
 
-        if paddle2.position.z - paddle2_half.z < -arena_half_depth {
-            paddle2.position.z = -arena_half_depth + paddle2_half.z;
-            paddle2.velocity.z = 0.0;
-        } else if paddle2.position.z + paddle2_half.z > arena_half_depth {
-            paddle2.position.z = arena_half_depth - paddle2_half.z;
-            paddle2.velocity.z = 0.0;
+    fn reset_ball(&mut self, ball_index: usize, scorer: u8) {
+        self.ball_speed_scales.remove(&ball_index);
+        serve_ball(&mut self.world, ball_index, scorer);
+    }
+
+    // Client actions go through the limits and mark the body as human controlled,
+    // rejections are logged in post_action
+    fn post_client_action(&mut self, action: Action) {
+        let body_id = action.body_id.clone();
+        if self.post_action(action).is_ok() {
+            self.last_client_action.insert(body_id, Instant::now());
         }
     }
 
-    fn reset_ball(&mut self, scorer: u8) {
-        let ball = &mut self.world.bodies[self.game_state.ball_index];
-        let rand_z = rand::rng().gen_range(-2.0..2.0);
-        ball.position = Vector3::new(0.0, 0.0, 0.0);
-        ball.velocity = if scorer == 1 {
-            Vector3::new(-7.0, 4.0, rand_z)
-        } else {
-            Vector3::new(7.0, 4.0, rand_z)
+    fn handle_command(&mut self, command: Command) {
+        match command {
+            Command::GetState | Command::GetConfig => {}
+            Command::Step => self.advance(self.fixed_timestep()),
+            // Also how clients restart a finished match, see the R key
+            Command::Reset => {
+                self.reset_to_initial();
+                info!("🔄 World reset");
+            }
+            Command::Pause => {
+                self.paused = true;
+                info!("⏸️  Simulation paused");
+            }
+            Command::Resume => {
+                self.paused = false;
+                info!("▶️  Simulation resumed");
+            }
+            // Actions carry a payload, so they only arrive on the action socket
+            Command::PostAction => warn!("⚠️  Command ignored: send actions on the PULL socket"),
+        }
+    }
+
+    // REP side: every command is answered with the state after it ran
+    fn poll_commands(&mut self) {
+        let Some(channel) = &self.command_channel else {
+            return;
+        };
+        let Ok(bytes) = channel.recv_bytes_nonblocking() else {
+            return;
         };
+        let command = Command::from_msgpack(&bytes);
+        match &command {
+            Ok(command) => self.handle_command(command.clone()),
+            Err(_) => warn!("⚠️  Unreadable command ({} bytes)", bytes.len()),
+        }
+
+        // REP sockets must reply before they can receive again
+        let reply = match command {
+            Ok(Command::GetConfig) => self.sim_config().to_msgpack(),
+            _ => self.get_state().to_msgpack(),
+        }
+        .unwrap_or_default();
+        if let Some(channel) = &self.command_channel {
+            let _ = channel.send_bytes(&reply);
+        }
     }
 
-    pub fn get_state(&self) -> WorldState {
+    pub fn bind_commands(&mut self, endpoint: &str) -> Result<(), zmq::Error> {
+        self.command_channel = Some(IPCChannel::new_server(endpoint)?);
+        Ok(())
+    }
+
+    fn drive_ai(&mut self) {
+        let Some(ai) = &self.paddle_ai else {
+            return;
+        };
+        let client_active = self
+            .last_client_action
+            .get(&ai.paddle_id)
+            .is_some_and(|t| t.elapsed() < AI_TAKEOVER_TIMEOUT);
+        if client_active {
+            return;
+        }
+
+        let state = self.current_state();
+        if let Some(action) = self.paddle_ai.as_mut().and_then(|ai| ai.action(&state)) {
+            if let Some(paddle) = self.world.get_body_mut(&action.body_id) {
+                paddle.update_velocity(action.velocity.x, action.velocity.y, action.velocity.z);
+            }
+        }
+    }
+
+    pub fn get_state(&mut self) -> WorldState {
+        self.tick_id += 1;
+        self.current_state()
+    }
+
+    // Snapshot at the current tick without advancing tick_id
+    fn current_state(&self) -> WorldState {
         WorldState {
             bodies: self.world.bodies.clone(),
             time: self.start_time.elapsed().as_secs_f32(),
             score_player1: self.game_state.score_player1,
             score_player2: self.game_state.score_player2,
+            tick_id: self.tick_id,
+            timestamp_ms: unix_time_ms(),
+            phase: self.game_state.phase,
+            tick_count: self.world.tick_count(),
+            sim_time: self.world.sim_time(),
         }
     }
 
-    pub fn post_action(&mut self, action: Action) {
-        let action_clone = action.clone();
-        let body_id = action.body_id;
-        let body = self.world.get_body_mut(&body_id);
-        if let Some(body) = body {
-            let position_changed = body.position.x != action.position.x
-                || body.position.y != action.position.y
-                || body.position.z != action.position.z;
-            let velocity_changed = body.velocity.x != action.velocity.x
-                || body.velocity.y != action.velocity.y
-                || body.velocity.z != action.velocity.z;
+    pub fn post_action(&mut self, action: Action) -> Result<(), ActionError> {
+        let action = self.limit_action(action)?;
+        apply_action(&mut self.world, action)?;
+        self.metrics.actions += 1;
+        Ok(())
+    }
 
-            if position_changed {
-                body.update_position(action.position.x, action.position.y, action.position.z);
-                println!("🎯 Action: {} position -> ({:.1}, {:.1}, {:.1})",
-                    body_id, action.position.x, action.position.y, action.position.z);
-            }
-            if velocity_changed {
-                body.update_velocity(action.velocity.x, action.velocity.y, action.velocity.z);
-                println!("🚀 Action: {} velocity -> ({:.1}, {:.1}, {:.1})",
-                    body_id, action.velocity.x, action.velocity.y, action.velocity.z);
+    // Route an action to one of the extra rooms, under the same limits as the main world
+    pub fn post_room_action(&mut self, room_action: RoomAction) -> Result<(), ActionError> {
+        let action = self.limit_action(room_action.action)?;
+        match self.rooms.get_mut(&room_action.room_id) {
+            Some(room) => {
+                apply_action(&mut room.world, action)?;
+                self.metrics.actions += 1;
+                Ok(())
             }
-            if body.mass != action.mass {
-                body.mass = action.mass;
+            None => {
+                warn!(
+                    "⚠️  Action ignored: room '{}' not found",
+                    room_action.room_id
+                );
+                Err(ActionError::UnknownRoom(room_action.room_id))
             }
-            if body.restitution != action.restitution {
-                body.restitution = action.restitution;
+        }
+    }
+
+    // Rooms play Pong too, so worlds without both paddles are refused
+    pub fn add_room(&mut self, id: String, world: World) -> Result<(), MissingPaddle> {
+        let room = Room::new(id.clone(), world)?;
+        info!(
+            "🏠 Room '{}' added ({} bodies)",
+            id,
+            room.world.bodies.len()
+        );
+        self.rooms.insert(id, room);
+        Ok(())
+    }
+
+    pub fn remove_room(&mut self, id: &str) -> Option<Room> {
+        self.rooms.remove(id)
+    }
+
+    pub fn room(&self, id: &str) -> Option<&Room> {
+        self.rooms.get(id)
+    }
+
+    fn limit_action(&self, mut action: Action) -> Result<Action, ActionError> {
+        if let Some(limits) = &self.action_limits {
+            if !limits.allows_body(&action.body_id) {
+                warn!(
+                    "⛔ Action rejected: body '{}' is not client controllable",
+                    action.body_id
+                );
+                return Err(ActionError::BodyNotAllowed(action.body_id));
             }
-            if body.dynamic != action.dynamic {
-                body.dynamic = action.dynamic;
+            if !limits.in_bounds(&action.position) {
+                warn!(
+                    "⛔ Action rejected: {} position ({:.1}, {:.1}, {:.1}) is outside the arena",
+                    action.body_id, action.position.x, action.position.y, action.position.z
+                );
+                return Err(ActionError::OutOfBounds(action.body_id));
             }
-        } else {
-            println!("⚠️  Action ignored: body '{}' not found", body_id);
+            action.velocity = limits.clamp_velocity(action.velocity);
         }
+
+        Ok(action)
+    }
+
+    // Back to the bodies the engine was created with and a fresh match. Only the bodies are
+    // swapped, so world settings changed since startup carry over, as in `reset`.
+    pub fn reset_to_initial(&mut self) {
+        self.world.set_bodies(self.initial_world.bodies.clone());
+        self.world.tick_count = 0;
+        self.world.sim_time = 0.0;
+        self.game_state.restart();
+        self.refresh_ball_indices();
+        self.last_broadcast = None; // Deltas can't remove the balls added since
+    }
+
+    // Balls are the bodies whose id starts with "ball", re-derived when the world is replaced
+    fn refresh_ball_indices(&mut self) {
+        self.ball_speed_scales.clear();
+        self.game_state.ball_indices = (0..self.world.bodies.len())
+            .filter(|&i| self.world.bodies[i].id.starts_with("ball"))
+            .collect();
     }
 
+    // New bodies, scores and phase on the same world, so its settings carry over
     pub fn reset(&mut self, state: WorldState) {
-        self.world = World::new(self.world.id.clone(), self.world.tick_rate, state.bodies);
+        self.world.set_bodies(state.bodies);
+        self.world.tick_count = 0;
+        self.world.sim_time = 0.0;
         self.start_time = Instant::now();
         self.game_state.score_player1 = state.score_player1;
         self.game_state.score_player2 = state.score_player2;
+        self.game_state.phase = state.phase;
+        self.refresh_ball_indices();
+        self.last_broadcast = None; // Deltas can't remove bodies missing from `state`
+    }
+
+    pub fn sim_config(&self) -> SimConfig {
+        SimConfig {
+            tick_rate: self.world.tick_rate,
+            substeps: self.physics_substeps,
+            force_fields: self.world.force_fields.clone(),
+            lock_z: self.world.lock_z,
+            arena: self.arena.clone(),
+        }
+    }
+
+    // Bodies, scores, phase and ball speed-ups at the current tick, for save games
+    pub fn snapshot(&self) -> EngineSnapshot {
+        EngineSnapshot {
+            state: self.current_state(),
+            game_state: self.game_state.clone(),
+            ball_speedup: self.ball_speedup,
+            max_ball_speed_scale: self.max_ball_speed_scale,
+            ball_speed_scales: self.ball_speed_scales.clone(),
+        }
+    }
+
+    // Resume from a snapshot, the broadcast after it carries the snapshot's next tick_id
+    pub fn restore(&mut self, snapshot: EngineSnapshot) {
+        let (tick_count, sim_time) = (snapshot.state.tick_count, snapshot.state.sim_time);
+        self.tick_id = snapshot.state.tick_id;
+        self.reset(snapshot.state);
+        self.world.tick_count = tick_count;
+        self.world.sim_time = sim_time;
+        self.game_state = snapshot.game_state;
+        self.ball_speedup = snapshot.ball_speedup;
+        self.max_ball_speed_scale = snapshot.max_ball_speed_scale;
+        self.ball_speed_scales = snapshot.ball_speed_scales;
+        info!(
+            "💾 Restored snapshot at tick {} ({} - {})",
+            self.tick_id, self.game_state.score_player1, self.game_state.score_player2
+        );
+    }
+
+    pub fn metrics(&self) -> &EngineMetrics {
+        &self.metrics
+    }
+
+    pub fn reset_metrics(&mut self) {
+        self.metrics = EngineMetrics::default();
+    }
+
+    fn log_metrics(&mut self) {
+        if self.metrics_logged.elapsed() >= METRICS_LOG_INTERVAL {
+            info!("📊 {}", self.metrics);
+            self.metrics_logged = Instant::now();
+        }
+    }
+
+    // Main world and rooms, timed for the metrics
+    fn broadcast(&mut self) {
+        let started = Instant::now();
+        self.broadcast_state();
+        self.broadcast_rooms();
+        self.metrics.serialization_time += started.elapsed();
+        self.metrics.broadcasts += 1;
+    }
+
+    fn broadcast_state(&mut self) {
+        let world_state = self.get_state();
+        self.record(&world_state);
+        if !self.delta_broadcast {
+            if let Ok(response) = world_state.to_msgpack() {
+                let _ = self.state_channel.send_topic(WORLD_TOPIC, &response);
+            }
+            return;
+        }
+
+        let message = match &self.last_broadcast {
+            // Periodic keyframes let subscribers that joined mid-stream resync, see StateSync
+            Some(prev) if self.deltas_since_keyframe + 1 < self.keyframe_interval.max(1) => {
+                self.deltas_since_keyframe += 1;
+                StateMessage::Delta(world_state.diff(prev))
+            }
+            _ => {
+                self.deltas_since_keyframe = 0;
+                StateMessage::Keyframe(world_state.clone())
+            }
+        };
+        if let Ok(response) = message.to_msgpack() {
+            let _ = self.state_channel.send_topic(WORLD_TOPIC, &response);
+        }
+        self.last_broadcast = Some(world_state);
+    }
+
+    fn record(&mut self, state: &WorldState) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+        if let Err(err) = recorder.record(state).and_then(|_| recorder.flush()) {
+            warn!("⚠️  Recording stopped: {:?}", err);
+            self.recorder = None;
+        }
+    }
+
+    // Each room publishes full snapshots under its own topic
+    fn broadcast_rooms(&mut self) {
+        let time = self.start_time.elapsed().as_secs_f32();
+        let timestamp_ms = unix_time_ms();
+        for room in self.rooms.values_mut() {
+            let state = room.get_state(time, timestamp_ms);
+            let encoded = if self.delta_broadcast {
+                StateMessage::Keyframe(state).to_msgpack()
+            } else {
+                state.to_msgpack()
+            };
+            if let Ok(response) = encoded {
+                let topic = room_topic(&room.id);
+                let _ = self.state_channel.send_topic(&topic, &response);
+            }
+        }
     }
 
     // Updated run method with PUSH/PULL + PUB/SUB
     pub fn run_correct(&mut self) -> Result<(), zmq::Error> {
+        self.run_loop()
+    }
+
+    pub fn run(&mut self) -> Result<(), zmq::Error> {
+        self.run_loop()
+    }
+
+    // Drain actions and commands, step at the fixed timestep and broadcast, forever
+    fn run_loop(&mut self) -> Result<(), zmq::Error> {
         let mut last_time = Instant::now();
         let mut last_state_send = Instant::now();
-        let state_interval = Duration::from_millis(16); // ~60Hz
+        let state_interval = self.state_interval();
         let mut accumulator = 0.0;
-        let fixed_timestep = 1.0 / 120.0; // 120Hz physics
+        let fixed_timestep = self.fixed_timestep();
 
         loop {
             // Calculate delta time
@@ -192,70 +780,666 @@ impl Engine {
             last_time = now;
             accumulator += frame_time;
 
-            // Process ALL pending actions (drain the queue)
+            // Process ALL pending actions (drain the queue), commands share the socket
             while let Ok(bytes) = self.action_channel.recv_bytes_nonblocking() {
                 if let Ok(action) = Action::from_msgpack(&bytes) {
-                    self.post_action(action);
+                    self.post_client_action(action);
+                } else if let Ok(room_action) = RoomAction::from_msgpack(&bytes) {
+                    let _ = self.post_room_action(room_action);
+                } else if let Ok(command) = Command::from_msgpack(&bytes) {
+                    self.handle_command(command);
                 }
             }
 
-            // Fixed timestep physics updates
+            self.poll_commands();
+
+            // Fixed timestep physics updates, dropping time spent paused
+            if self.paused {
+                accumulator = 0.0;
+            }
             while accumulator >= fixed_timestep {
+                self.drive_ai();
                 self.step(fixed_timestep);
                 accumulator -= fixed_timestep;
             }
 
             // Broadcast state periodically
             if last_state_send.elapsed() >= state_interval {
-                let world_state = self.get_state();
-                if let Ok(response) = world_state.to_msgpack() {
-                    let _ = self.state_channel.send_bytes(&response);
-                }
+                self.broadcast();
                 last_state_send = Instant::now();
             }
+            self.log_metrics();
 
             // Small sleep to avoid 100% CPU
             std::thread::sleep(Duration::from_millis(3));
         }
     }
-    pub fn run(&mut self) -> Result<(), zmq::Error> {
-        let mut last_time = Instant::now();
-        let mut last_state_send = Instant::now();
-        let state_interval = Duration::from_millis(16); // ~60Hz
-        let mut accumulator = 0.0;
-        let fixed_timestep = 1.0 / 120.0; // 120Hz physics
+}
 
-        loop {
-            // Calculate delta time
-            let now = Instant::now();
-            let frame_time = (now - last_time).as_secs_f64();
-            last_time = now;
-            accumulator += frame_time;
+// Apply a validated action to a body of `world`, also used by BatchWorld
+pub fn apply_action(world: &mut World, action: Action) -> Result<(), ActionError> {
+    let body_id = action.body_id;
+    let body = world.get_body_mut(&body_id);
+    if let Some(body) = body {
+        let position_changed = !body.position.approx_eq(&action.position, ACTION_EPSILON);
+        let velocity_changed = !body.velocity.approx_eq(&action.velocity, ACTION_EPSILON);
 
-            // Process ALL pending actions (drain the queue)
-            while let Ok(bytes) = self.action_channel.recv_bytes_nonblocking() {
-                if let Ok(action) = Action::from_msgpack(&bytes) {
-                    self.post_action(action);
-                }
+        if position_changed {
+            body.update_position(action.position.x, action.position.y, action.position.z);
+            debug!("🎯 Action: {} position -> ({:.1}, {:.1}, {:.1})",
+                body_id, action.position.x, action.position.y, action.position.z);
+        }
+        if velocity_changed {
+            body.update_velocity(action.velocity.x, action.velocity.y, action.velocity.z);
+            debug!("🚀 Action: {} velocity -> ({:.1}, {:.1}, {:.1})",
+                body_id, action.velocity.x, action.velocity.y, action.velocity.z);
+        }
+        if body.mass != action.mass {
+            body.set_mass(action.mass);
+        }
+        if body.restitution != action.restitution {
+            body.restitution = action.restitution;
+        }
+        if let Some(friction) = action.friction {
+            body.friction = friction;
+        }
+        body.apply_dynamic_flag(action.dynamic);
+        Ok(())
+    } else {
+        warn!("⚠️  Action ignored: body '{}' not found", body_id);
+        Err(ActionError::UnknownBody(body_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f64 = 1.0 / 60.0;
+
+    // Each IPCChannel has its own zmq context, so inproc endpoints don't clash across tests
+    fn engine() -> Engine {
+        let arena = ArenaConfig::default();
+        let (world, _) = arena.pong_world();
+        Engine::new_server("inproc://actions", "inproc://state", world, arena).unwrap()
+    }
+
+    // SUB socket on the engine's own context, connected to its PUB endpoint
+    fn subscriber(engine: &Engine) -> zmq::Socket {
+        let socket = engine.state_channel.context.socket(zmq::SUB).unwrap();
+        socket.connect("inproc://state").unwrap();
+        socket.set_subscribe(WORLD_TOPIC.as_bytes()).unwrap();
+        socket.set_rcvtimeo(1000).unwrap();
+        std::thread::sleep(Duration::from_millis(50)); // Let the subscription reach the PUB
+        socket
+    }
+
+    // Next main-world frame with its topic stripped
+    fn recv_world(subscriber: &zmq::Socket) -> Vec<u8> {
+        let bytes = subscriber.recv_bytes(0).unwrap();
+        bytes[WORLD_TOPIC.len()..].to_vec()
+    }
+
+    // REQ socket on the command channel's context, see bind_commands
+    fn command_client(engine: &mut Engine) -> zmq::Socket {
+        engine.bind_commands("inproc://commands").unwrap();
+        let channel = engine.command_channel.as_ref().unwrap();
+        let socket = channel.context.socket(zmq::REQ).unwrap();
+        socket.connect("inproc://commands").unwrap();
+        socket
+    }
+
+    // Sends `command` and polls the engine until it replies
+    fn send_command(engine: &mut Engine, client: &zmq::Socket, command: Command) -> Vec<u8> {
+        client.send(command.to_msgpack().unwrap(), 0).unwrap();
+        for _ in 0..200 {
+            engine.poll_commands();
+            if let Ok(reply) = client.recv_bytes(zmq::DONTWAIT) {
+                return reply;
             }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        panic!("no reply to {:?}", command);
+    }
 
-            // Fixed timestep physics updates
-            while accumulator >= fixed_timestep {
-                self.step(fixed_timestep);
-                accumulator -= fixed_timestep;
+    // Moves paddle1 to `position` with `velocity`, the rest copied from the paddle as it is
+    fn paddle_action(engine: &Engine, position: Vector3, velocity: Vector3) -> Action {
+        let paddle = engine.world.get_body("paddle1").unwrap();
+        Action {
+            body_id: paddle.id.clone(),
+            velocity,
+            position,
+            aabb: paddle.aabb.clone(),
+            mass: paddle.mass,
+            restitution: paddle.restitution,
+            dynamic: true,
+            friction: None,
+        }
+    }
+
+    fn speed(v: Vector3) -> f32 {
+        v.length()
+    }
+
+    fn place_ball(engine: &mut Engine, ball_index: usize, x: f32) {
+        let ball = &mut engine.world.bodies[ball_index];
+        ball.update_position(x, 0.0, 0.0);
+        ball.velocity = Vector3::new(x.signum() * 5.0, 0.0, 0.0);
+    }
+
+    #[test]
+    fn each_ball_past_a_goal_scores() {
+        let mut engine = engine();
+        let first = engine.game_state.ball_index().unwrap();
+        let second = engine.add_ball("ball_2".to_string(), Vector3::zero());
+        let plane = engine.arena.scoring_plane_x();
+
+        place_ball(&mut engine, first, plane + 1.0);
+        place_ball(&mut engine, second, -plane - 1.0);
+        engine.step(DT);
+
+        assert_eq!(engine.game_state.score_player1, 1);
+        assert_eq!(engine.game_state.score_player2, 1);
+        for ball_index in [first, second] {
+            let ball = &engine.world.bodies[ball_index];
+            assert!(
+                ball.position.x.abs() < 1.0,
+                "ball {} not re-served",
+                ball.id
+            );
+        }
+    }
+
+    #[test]
+    fn tick_id_increases_across_broadcasts() {
+        let mut engine = engine();
+        let subscriber = subscriber(&engine);
+
+        let mut previous = None;
+        for _ in 0..5 {
+            engine.step(DT);
+            engine.broadcast();
+            let bytes = recv_world(&subscriber);
+            let tick_id = WorldState::from_msgpack(&bytes).unwrap().tick_id;
+            if let Some(previous) = previous {
+                assert!(tick_id > previous, "tick {} after {}", tick_id, previous);
             }
+            previous = Some(tick_id);
+        }
+        assert!(engine.get_state().tick_id > previous.unwrap());
+    }
 
-            // Broadcast state periodically
-            if last_state_send.elapsed() >= state_interval {
-                let world_state = self.get_state();
-                if let Ok(response) = world_state.to_msgpack() {
-                    let _ = self.state_channel.send_bytes(&response);
-                }
-                last_state_send = Instant::now();
+    #[test]
+    fn out_of_bounds_action_is_rejected() {
+        let mut engine = engine();
+        engine.action_limits = Some(ActionLimits::pong(&engine.arena));
+        let before = engine.world.get_body("paddle1").unwrap().position;
+
+        let action = paddle_action(&engine, Vector3::new(-25.0, 500.0, 0.0), Vector3::zero());
+        assert_eq!(
+            engine.limit_action(action.clone()).unwrap_err(),
+            ActionError::OutOfBounds("paddle1".to_string())
+        );
+        assert!(engine.post_action(action).is_err());
+        assert_eq!(engine.world.get_body("paddle1").unwrap().position, before);
+    }
+
+    #[test]
+    fn in_bounds_action_is_applied() {
+        let mut engine = engine();
+        engine.action_limits = Some(ActionLimits::pong(&engine.arena));
+        let target = Vector3::new(-25.0, 5.0, 1.0);
+
+        let action = paddle_action(&engine, target, Vector3::new(0.0, 3.0, 0.0));
+        assert!(engine.limit_action(action.clone()).is_ok());
+        engine.post_action(action).unwrap();
+        let paddle = engine.world.get_body("paddle1").unwrap();
+        assert_eq!(paddle.position, target);
+        assert_eq!(paddle.velocity, Vector3::new(0.0, 3.0, 0.0));
+    }
+
+    #[test]
+    fn paddle_past_the_top_wall_is_clamped() {
+        let mut engine = engine();
+        let top = engine.arena_inner_bounds().unwrap().max.y;
+        let paddle = engine.world.get_body_mut("paddle1").unwrap();
+        let half = paddle.get_half_extents();
+        paddle.update_position(-25.0, top + 2.0, 0.0);
+        paddle.update_velocity(0.0, 10.0, 1.0);
+
+        engine.constrain_paddles();
+
+        let paddle = engine.world.get_body("paddle1").unwrap();
+        assert_eq!(paddle.position.y + half.y, top);
+        assert_eq!(paddle.aabb.max.y, top);
+        assert_eq!(paddle.velocity, Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn ball_speeds_up_per_hit_until_the_cap() {
+        let mut engine = engine();
+        let ball_index = engine.game_state.ball_index().unwrap();
+        let base = speed(engine.world.bodies[ball_index].velocity);
+
+        let mut last = base;
+        for _ in 0..30 {
+            engine.speed_up_ball(ball_index);
+            let now = speed(engine.world.bodies[ball_index].velocity);
+            assert!(now >= last);
+            last = now;
+        }
+        let cap = base * engine.max_ball_speed_scale;
+        assert!((last - cap).abs() < 1e-3, "{} vs cap {}", last, cap);
+
+        // A point re-serves the ball at the base rate
+        let plane = engine.arena.scoring_plane_x();
+        place_ball(&mut engine, ball_index, plane + 1.0);
+        engine.step(DT);
+        assert!(engine.ball_speed_scales.is_empty());
+        let served = speed(engine.world.bodies[ball_index].velocity);
+        engine.speed_up_ball(ball_index);
+        let after_hit = speed(engine.world.bodies[ball_index].velocity);
+        assert!((after_hit - served * engine.ball_speedup).abs() < 1e-4);
+    }
+
+    #[test]
+    fn paused_step_changes_nothing() {
+        let mut engine = engine();
+        engine.paused = true;
+        let before: Vec<Vector3> = engine.world.bodies.iter().map(|b| b.position).collect();
+
+        for _ in 0..10 {
+            engine.step(DT);
+        }
+
+        let after: Vec<Vector3> = engine.world.bodies.iter().map(|b| b.position).collect();
+        assert_eq!(after, before);
+        assert_eq!(engine.world.tick_count(), 0);
+        assert_eq!(engine.metrics().steps, 0);
+    }
+
+    #[test]
+    fn get_state_command_replies_with_the_state() {
+        let mut engine = engine();
+        let client = command_client(&mut engine);
+
+        let reply = send_command(&mut engine, &client, Command::GetState);
+        let state = WorldState::from_msgpack(&reply).unwrap();
+        assert_eq!(state.bodies.len(), engine.world.bodies.len());
+        assert_eq!(state.tick_id, engine.tick_id);
+        assert_eq!(state.tick_count, 0);
+    }
+
+    #[test]
+    fn step_command_advances_a_paused_engine() {
+        let mut engine = engine();
+        engine.paused = true;
+        let client = command_client(&mut engine);
+
+        let reply = send_command(&mut engine, &client, Command::Step);
+        let state = WorldState::from_msgpack(&reply).unwrap();
+        assert_eq!(state.tick_count, 1);
+        assert_eq!(engine.world.tick_count(), 1);
+        assert!(engine.paused);
+    }
+
+    #[test]
+    fn reset_command_restores_the_initial_world() {
+        let mut engine = engine();
+        let initial: Vec<Vector3> = engine.world.bodies.iter().map(|b| b.position).collect();
+        let client = command_client(&mut engine);
+        engine.world.lock_z = true;
+        engine.world.restitution_threshold = 0.3;
+        let settings = engine.world.contact_settings();
+        for _ in 0..5 {
+            engine.step(DT);
+        }
+
+        let reply = send_command(&mut engine, &client, Command::Reset);
+        let state = WorldState::from_msgpack(&reply).unwrap();
+        let positions: Vec<Vector3> = state.bodies.iter().map(|b| b.position).collect();
+        assert_eq!(positions, initial);
+        assert_eq!(state.tick_count, 0);
+        assert_eq!(engine.world.contact_settings(), settings);
+    }
+
+    #[test]
+    fn physics_rate_sets_steps_per_simulated_second() {
+        let arena = ArenaConfig::default();
+        let (world, _) = arena.pong_world();
+        let mut engine = Engine::new_server_with_rates(
+            "inproc://actions",
+            "inproc://state",
+            world,
+            arena,
+            240.0,
+            60.0,
+        )
+        .unwrap();
+        assert_eq!(engine.world.tick_rate, 240.0);
+
+        let mut steps = 0;
+        while engine.world.sim_time() < 1.0 - 1e-9 {
+            engine.step(engine.fixed_timestep());
+            steps += 1;
+        }
+        assert_eq!(steps, 240);
+        assert_eq!(engine.world.tick_count(), 240);
+    }
+
+    #[test]
+    fn tick_hook_runs_once_per_step() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let mut engine = engine();
+        engine.physics_substeps = 4; // Still one hook call per engine step
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        engine.set_tick_hook(move |_, _| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        for _ in 0..10 {
+            engine.step(DT);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 10);
+
+        engine.paused = true;
+        engine.step(DT);
+        assert_eq!(calls.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    fn snapshot_restores_a_match_in_progress() {
+        let mut restored = engine();
+        let mut engine = engine();
+        let ball_index = engine.world.body_map["ball"];
+        for _ in 0..20 {
+            engine.step(DT);
+        }
+        engine.game_state.award_point(1);
+        engine.game_state.award_point(1);
+        engine.game_state.award_point(2);
+        engine.speed_up_ball(ball_index);
+        engine.speed_up_ball(ball_index);
+        let bytes = engine.snapshot().to_msgpack().unwrap();
+
+        restored.restore(EngineSnapshot::from_msgpack(&bytes).unwrap());
+        assert_eq!(restored.game_state.score_player1, 2);
+        assert_eq!(restored.game_state.score_player2, 1);
+        assert_eq!(restored.game_state.phase, engine.game_state.phase);
+        assert_eq!(restored.ball_speed_scales, engine.ball_speed_scales);
+        let (ball, original) = (
+            &restored.world.bodies[ball_index],
+            &engine.world.bodies[ball_index],
+        );
+        assert_eq!(ball.velocity, original.velocity);
+        assert_eq!(ball.position, original.position);
+        assert_eq!(restored.world.tick_count(), 20);
+    }
+
+    #[test]
+    fn restore_keeps_the_world_settings() {
+        use crate::physics::constraints::DistanceConstraint;
+        use crate::physics::force_field::ForceField;
+        use crate::physics::plane::Plane;
+        use crate::physics::world::CombineMode;
+
+        let mut engine = engine();
+        let world = &mut engine.world;
+        world.lock_z = true;
+        world.restitution_combine = CombineMode::Max;
+        world.penetration_slop = 0.05;
+        world.restitution_threshold = 0.3;
+        world.set_pair_restitution("ball", "wall", 1.0);
+        world.add_force_field(ForceField::Uniform(Vector3::new(0.0, -1.0, 0.0)));
+        world.add_plane(Plane::new(
+            "floor".to_string(),
+            Vector3::new(0.0, 1.0, 0.0),
+            -30.0,
+        ));
+        world.add_constraint(DistanceConstraint::new(
+            "ball".to_string(),
+            "paddle1".to_string(),
+            30.0,
+            0.1,
+        ));
+        for _ in 0..10 {
+            engine.step(DT);
+        }
+        let snapshot = engine.snapshot();
+        let settings = engine.world.contact_settings();
+        for _ in 0..10 {
+            engine.step(DT);
+        }
+
+        engine.restore(snapshot);
+        assert_eq!(engine.world.contact_settings(), settings);
+        assert_eq!(engine.world.force_fields.len(), 1);
+        assert_eq!(engine.world.planes.len(), 1);
+        assert_eq!(engine.world.constraints.len(), 1);
+        assert_eq!(engine.world.tick_count(), 10);
+        assert_eq!(engine.world.get_body("ball").unwrap().id, "ball");
+    }
+
+    #[test]
+    fn reset_command_clears_the_scores_and_recentres_the_ball() {
+        let mut engine = engine();
+        let client = command_client(&mut engine);
+        for _ in 0..30 {
+            engine.step(DT);
+        }
+        engine.game_state.award_point(1);
+        engine.game_state.award_point(2);
+        let ball_index = engine.world.body_map["ball"];
+        assert_ne!(engine.world.bodies[ball_index].position, Vector3::zero());
+
+        let reply = send_command(&mut engine, &client, Command::Reset);
+        let state = WorldState::from_msgpack(&reply).unwrap();
+        assert_eq!((state.score_player1, state.score_player2), (0, 0));
+        let scores = &engine.game_state;
+        assert_eq!((scores.score_player1, scores.score_player2), (0, 0));
+        assert_eq!(engine.world.bodies[ball_index].position, Vector3::zero());
+    }
+
+    #[test]
+    fn action_velocity_is_per_second_at_any_physics_rate() {
+        let distance_at = |physics_hz| {
+            let arena = ArenaConfig::default();
+            let (world, _) = arena.pong_world();
+            let mut engine = Engine::new_server_with_rates(
+                "inproc://actions",
+                "inproc://state",
+                world,
+                arena,
+                physics_hz,
+                60.0,
+            )
+            .unwrap();
+            let start = engine.world.get_body("paddle1").unwrap().position;
+            let action = paddle_action(&engine, start, Vector3::new(0.0, 2.0, 0.0));
+            engine.post_action(action).unwrap();
+            while engine.world.sim_time() < 1.0 - 1e-9 {
+                engine.step(engine.fixed_timestep());
             }
+            engine.world.get_body("paddle1").unwrap().position.y - start.y
+        };
+        let (at_60, at_120) = (distance_at(60.0), distance_at(120.0));
+        assert!((at_60 - 2.0).abs() < 1e-4, "moved {at_60} at 60 Hz");
+        assert!((at_60 - at_120).abs() < 1e-4, "{at_60} vs {at_120}");
+    }
 
-            // Small sleep to avoid 100% CPU
-            std::thread::sleep(Duration::from_millis(3));
+    #[test]
+    fn get_config_serves_the_engine_gravity_and_tick_rate() {
+        use crate::physics::force_field::ForceField;
+
+        let arena = ArenaConfig::default();
+        let (world, _) = arena.pong_world();
+        let mut engine = Engine::new_server_with_rates(
+            "inproc://actions",
+            "inproc://state",
+            world,
+            arena,
+            240.0,
+            60.0,
+        )
+        .unwrap();
+        let gravity = Vector3::new(0.0, -9.81, 0.0);
+        engine.world.add_force_field(ForceField::Uniform(gravity));
+        let client = command_client(&mut engine);
+
+        let reply = send_command(&mut engine, &client, Command::GetConfig);
+        let config = SimConfig::from_msgpack(&reply).unwrap();
+        assert_eq!(config.tick_rate, engine.world.tick_rate);
+        assert_eq!(config.tick_rate, 240.0);
+        assert_eq!(config.gravity(), gravity);
+        assert_eq!(config.substeps, engine.physics_substeps);
+    }
+
+    #[test]
+    fn deactivation_reaches_clients_in_a_delta() {
+        use crate::client::sync::StateSync;
+
+        let mut engine = engine();
+        engine.delta_broadcast = true;
+        let subscriber = subscriber(&engine);
+        let mut sync = StateSync::new();
+
+        engine.broadcast();
+        let keyframe = sync.decode(&recv_world(&subscriber)).unwrap();
+        assert!(keyframe.bodies.iter().all(|b| b.active));
+
+        assert!(engine.world.set_active("ball", false));
+        engine.broadcast();
+        let bytes = recv_world(&subscriber);
+        assert!(matches!(
+            StateMessage::from_msgpack(&bytes).unwrap(),
+            StateMessage::Delta(_)
+        ));
+        let state = sync.decode(&bytes).unwrap();
+        let ball = state.bodies.iter().find(|b| b.id == "ball").unwrap();
+        assert!(!ball.active);
+    }
+
+    #[test]
+    fn keyframes_follow_the_keyframe_interval() {
+        let mut engine = engine();
+        engine.delta_broadcast = true;
+        engine.keyframe_interval = 3;
+        let subscriber = subscriber(&engine);
+        let client = command_client(&mut engine);
+
+        for i in 0..7 {
+            engine.broadcast();
+            let bytes = recv_world(&subscriber);
+            let keyframe = matches!(
+                StateMessage::from_msgpack(&bytes).unwrap(),
+                StateMessage::Keyframe(_)
+            );
+            // Counted in broadcasts, the first has nothing to diff against
+            assert_eq!(keyframe, i % 3 == 0, "broadcast {i}");
+            // Command replies take tick ids too, they must not shift the cadence
+            for _ in 0..i % 2 + 1 {
+                send_command(&mut engine, &client, Command::GetState);
+            }
+        }
+    }
+
+    #[test]
+    fn reset_to_initial_sends_a_keyframe_without_added_balls() {
+        use crate::client::sync::StateSync;
+
+        let mut engine = engine();
+        engine.delta_broadcast = true;
+        let subscriber = subscriber(&engine);
+        let mut sync = StateSync::new();
+
+        engine.add_ball("ball2".to_string(), Vector3::new(-5.0, 0.0, 0.0));
+        engine.broadcast();
+        engine.broadcast();
+        let state = (0..2)
+            .map(|_| sync.decode(&recv_world(&subscriber)).unwrap())
+            .last()
+            .unwrap();
+        assert!(state.bodies.iter().any(|b| b.id == "ball2"));
+
+        engine.reset_to_initial();
+        engine.broadcast();
+        let bytes = recv_world(&subscriber);
+        assert!(matches!(
+            StateMessage::from_msgpack(&bytes).unwrap(),
+            StateMessage::Keyframe(_)
+        ));
+        let state = sync.decode(&bytes).unwrap();
+        assert!(state.bodies.iter().all(|b| b.id != "ball2"));
+    }
+
+    #[test]
+    fn rooms_advance_independently() {
+        let mut engine = engine();
+        for id in ["a", "b"] {
+            let (world, _) = engine.arena.pong_world();
+            engine.add_room(id.to_string(), world).unwrap();
         }
+        let start = engine.world.get_body("paddle1").unwrap().position;
+        let action = paddle_action(&engine, start, Vector3::new(0.0, 3.0, 0.0));
+        engine
+            .post_room_action(RoomAction {
+                room_id: "a".to_string(),
+                action: action.clone(),
+            })
+            .unwrap();
+        assert!(matches!(
+            engine.post_room_action(RoomAction {
+                room_id: "c".to_string(),
+                action,
+            }),
+            Err(ActionError::UnknownRoom(_))
+        ));
+
+        for _ in 0..30 {
+            engine.step(DT);
+        }
+        let paddle_y = |id: &str| {
+            let room = engine.room(id).unwrap();
+            assert_eq!(room.world.tick_count(), 30);
+            room.world.get_body("paddle1").unwrap().position.y
+        };
+        assert!((paddle_y("a") - start.y - 1.5).abs() < 1e-4);
+        assert_eq!(paddle_y("b"), start.y);
+
+        assert!(engine.remove_room("a").is_some());
+        assert!(engine.room("a").is_none());
+        assert!(engine.remove_room("a").is_none());
+    }
+
+    #[test]
+    fn rooms_without_both_paddles_are_refused() {
+        let mut engine = engine();
+        let (mut world, _) = engine.arena.pong_world();
+        let mut bodies = world.bodies.clone();
+        bodies.retain(|b| b.id != "paddle1");
+        world.set_bodies(bodies);
+
+        assert_eq!(
+            engine.add_room("a".to_string(), world).err(),
+            Some(MissingPaddle("paddle1".to_string()))
+        );
+        assert!(engine.room("a").is_none());
+    }
+
+    #[test]
+    fn main_world_subscribers_skip_room_frames() {
+        let mut engine = engine();
+        let (world, _) = engine.arena.pong_world();
+        engine.add_room("a".to_string(), world).unwrap();
+        let subscriber = subscriber(&engine);
+        subscriber.set_rcvtimeo(100).unwrap();
+
+        engine.broadcast();
+        let bytes = subscriber.recv_bytes(0).unwrap();
+        assert!(bytes.starts_with(WORLD_TOPIC.as_bytes()));
+        assert_eq!(subscriber.recv_bytes(0), Err(zmq::Error::EAGAIN));
     }
 }
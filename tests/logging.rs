@@ -0,0 +1,48 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use volley::game::arena::ArenaConfig;
+
+// Counts the records that reach it, so nothing is printed and nothing is formatted
+struct CountingLogger {
+    records: AtomicUsize,
+}
+
+impl Log for CountingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.records.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CountingLogger = CountingLogger {
+    records: AtomicUsize::new(0),
+};
+
+// One test, because the logger and the max level are process-wide
+#[test]
+fn ticks_log_nothing_at_the_default_level() {
+    log::set_logger(&LOGGER).unwrap();
+    let (mut world, _) = ArenaConfig::default().pong_world();
+
+    // main.rs defaults env_logger to info
+    log::set_max_level(LevelFilter::Info);
+    for _ in 0..120 {
+        world.tick();
+    }
+    assert_eq!(LOGGER.records.load(Ordering::Relaxed), 0);
+    assert!(!log::log_enabled!(Level::Trace));
+
+    // The same ticks do log when tracing, so the capture itself works
+    log::set_max_level(LevelFilter::Trace);
+    for _ in 0..10 {
+        world.tick();
+    }
+    assert!(LOGGER.records.load(Ordering::Relaxed) > 0);
+}